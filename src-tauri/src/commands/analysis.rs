@@ -0,0 +1,47 @@
+use crate::noise::interval::{self, CoordBounds, Interval, RangeReport};
+use serde::Deserialize;
+use serde_json::Value;
+
+#[derive(Deserialize)]
+pub struct RangeAnalysisRequest {
+    /// The density graph as V2 JSON
+    pub graph: Value,
+    /// Optional world-space box; coordinate nodes are unbounded without it
+    #[serde(default)]
+    pub bounds: Option<WorldBounds>,
+}
+
+#[derive(Deserialize)]
+pub struct WorldBounds {
+    pub x_min: f64,
+    pub x_max: f64,
+    pub y_min: f64,
+    pub y_max: f64,
+    pub z_min: f64,
+    pub z_max: f64,
+}
+
+impl From<&WorldBounds> for CoordBounds {
+    fn from(b: &WorldBounds) -> Self {
+        CoordBounds {
+            x: Interval::new(b.x_min, b.x_max),
+            y: Interval::new(b.y_min, b.y_max),
+            z: Interval::new(b.z_min, b.z_max),
+        }
+    }
+}
+
+/// Statically compute the guaranteed output range of every node in a density
+/// graph and flag Normalizers whose source range doesn't cover their input.
+#[tauri::command]
+pub fn analyze_density_ranges(request: RangeAnalysisRequest) -> Result<RangeReport, String> {
+    if !request.graph.is_object() {
+        return Err("Density graph must be a JSON object".into());
+    }
+    let bounds = request
+        .bounds
+        .as_ref()
+        .map(CoordBounds::from)
+        .unwrap_or_default();
+    Ok(interval::analyze(&request.graph, bounds))
+}
@@ -0,0 +1,466 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::bridge::client::{BridgeClient, BridgeState};
+use crate::export::heightmap::{Heightmap, HeightmapFormat};
+use crate::export::map_image::{CategoryMap, MapArea, MapKind};
+use crate::export::mesh::{surface_nets, DensityVolume, MeshFormat};
+use crate::export::schematic::{decode_schematic, write_schematic, SchematicImport};
+use crate::export::volume::BlockVolume;
+use crate::export::vox::{VoxFile, VoxPaletteEntry};
+use crate::worldgen::biome_map::BiomeSelector;
+use crate::worldgen::chunk::ChunkGenerator;
+use crate::worldgen::pack::WorldgenPack;
+
+#[derive(Deserialize)]
+pub struct HeightmapExportRequest {
+    /// Asset pack directory
+    pub pack_path: String,
+    /// Generate everything with this biome instead of the world structure
+    #[serde(default)]
+    pub biome: Option<String>,
+    /// World structure file stem; the first one in the pack when omitted
+    #[serde(default)]
+    pub world_structure: Option<String>,
+    #[serde(default)]
+    pub blend: bool,
+    /// World X and Z of the first column, and the columns along each axis
+    pub x_min: i32,
+    pub z_min: i32,
+    pub width: u32,
+    pub depth: u32,
+    /// Height range to generate, `y_max` exclusive
+    pub y_min: i32,
+    pub y_max: i32,
+    /// Output path without extension; each format adds its own
+    pub output: String,
+    /// Any of `png`, `raw` and `tiff`
+    pub formats: Vec<String>,
+}
+
+/// Largest number of blocks a heightmap export generates: a 4096×4096 area
+/// at full world height.
+const MAX_HEIGHTMAP_SAMPLES: u64 = 4096 * 4096 * 320;
+
+/// Largest density volume a mesh export samples.
+const MAX_MESH_SAMPLES: usize = 256 * 256 * 256;
+
+#[derive(Deserialize)]
+pub struct MeshExportRequest {
+    /// Asset pack directory
+    pub pack_path: String,
+    /// Generate everything with this biome instead of the world structure
+    #[serde(default)]
+    pub biome: Option<String>,
+    /// World structure file stem; the first one in the pack when omitted
+    #[serde(default)]
+    pub world_structure: Option<String>,
+    #[serde(default)]
+    pub blend: bool,
+    /// World position of the first density sample
+    pub x_min: i32,
+    pub y_min: i32,
+    pub z_min: i32,
+    /// Samples along each axis
+    pub size_x: usize,
+    pub size_y: usize,
+    pub size_z: usize,
+    /// Output path without extension
+    pub output: String,
+    /// `obj`, `glb` or `stl`
+    pub format: String,
+    /// Color vertices by the generated block under them
+    #[serde(default)]
+    pub colors: bool,
+}
+
+#[derive(Serialize)]
+pub struct MeshExport {
+    pub path: PathBuf,
+    pub vertices: usize,
+    pub triangles: usize,
+}
+
+/// Largest block region a voxel or schematic export holds in memory.
+const MAX_VOLUME_BLOCKS: usize = 512 * 512 * 128;
+
+#[derive(Deserialize)]
+pub struct BlockExportRequest {
+    /// Asset pack directory
+    pub pack_path: String,
+    /// Generate everything with this biome instead of the world structure
+    #[serde(default)]
+    pub biome: Option<String>,
+    /// World structure file stem; the first one in the pack when omitted
+    #[serde(default)]
+    pub world_structure: Option<String>,
+    #[serde(default)]
+    pub blend: bool,
+    /// World position of the first block
+    pub x_min: i32,
+    pub y_min: i32,
+    pub z_min: i32,
+    /// Blocks along each axis
+    pub size_x: usize,
+    pub size_y: usize,
+    pub size_z: usize,
+    /// Output path without extension
+    pub output: String,
+}
+
+#[derive(Deserialize)]
+pub struct FetchedBlockExportRequest {
+    /// World position of the first block
+    pub x_min: i32,
+    pub y_min: i32,
+    pub z_min: i32,
+    /// Blocks along each axis
+    pub size_x: usize,
+    pub size_y: usize,
+    pub size_z: usize,
+    /// Output path without extension
+    pub output: String,
+    /// Load chunks the server has not generated yet
+    #[serde(default)]
+    pub force_load: bool,
+}
+
+#[derive(Serialize)]
+pub struct VoxExport {
+    pub path: PathBuf,
+    pub models: usize,
+    pub voxels: usize,
+    pub palette: Vec<VoxPaletteEntry>,
+}
+
+/// Largest map image, in pixels across all tiles.
+const MAX_MAP_PIXELS: u64 = 16384 * 16384;
+
+/// Largest number of blocks a material map scans for surfaces, as for heightmaps.
+const MAX_MATERIAL_MAP_BLOCKS: u64 = MAX_HEIGHTMAP_SAMPLES;
+
+/// Default tile edge of map images.
+const DEFAULT_MAP_TILE_SIZE: u32 = 4096;
+
+#[derive(Deserialize)]
+pub struct MapImageRequest {
+    /// Asset pack directory
+    pub pack_path: String,
+    /// `biome` or `material`
+    pub kind: String,
+    /// World structure file stem; the first one in the pack when omitted
+    #[serde(default)]
+    pub world_structure: Option<String>,
+    /// Material maps only: generate everything with this biome instead
+    #[serde(default)]
+    pub biome: Option<String>,
+    /// Material maps only: blend biomes across DefaultTransitionDistance
+    #[serde(default)]
+    pub blend: bool,
+    /// World X and Z of the first pixel's column, and pixels along each axis
+    pub x_min: i32,
+    pub z_min: i32,
+    pub width: u32,
+    pub depth: u32,
+    /// Columns between neighbouring pixels; 1 when omitted
+    #[serde(default)]
+    pub blocks_per_pixel: Option<u32>,
+    /// Material maps only: height range searched for the surface, `y_max`
+    /// exclusive
+    #[serde(default)]
+    pub y_min: i32,
+    #[serde(default)]
+    pub y_max: i32,
+    /// Largest tile edge in pixels; 4096 when omitted
+    #[serde(default)]
+    pub tile_size: Option<u32>,
+    /// Output path without extension
+    pub output: String,
+}
+
+#[derive(Serialize)]
+pub struct SchematicExport {
+    pub path: PathBuf,
+    pub blocks: usize,
+    pub block_types: usize,
+}
+
+/// Generate surface heights over a region and write them as 16-bit
+/// heightmaps with a sidecar JSON. Returns the paths written.
+#[tauri::command]
+pub fn export_heightmap(request: HeightmapExportRequest) -> Result<Vec<PathBuf>, String> {
+    if request.width == 0 || request.depth == 0 {
+        return Err("Width and depth must be positive".into());
+    }
+    if request.y_max <= request.y_min {
+        return Err("y_max must be greater than y_min".into());
+    }
+    // Heights are stored as i16, so the highest block must fit one
+    if request.y_min < i16::MIN as i32 || request.y_max - 1 > i16::MAX as i32 {
+        return Err(format!(
+            "Height range must lie within {}..={}",
+            i16::MIN,
+            i16::MAX
+        ));
+    }
+    let samples =
+        request.width as u64 * request.depth as u64 * (request.y_max - request.y_min) as u64;
+    if samples > MAX_HEIGHTMAP_SAMPLES {
+        return Err(format!(
+            "Region too large: at most {} blocks",
+            MAX_HEIGHTMAP_SAMPLES
+        ));
+    }
+    let formats = request
+        .formats
+        .iter()
+        .map(|f| HeightmapFormat::from_name(f))
+        .collect::<Result<Vec<_>, _>>()?;
+    if formats.is_empty() {
+        return Err("No heightmap formats selected".into());
+    }
+
+    let pack = WorldgenPack::load(Path::new(&request.pack_path))?;
+    let generator = ChunkGenerator::for_pack(
+        &pack,
+        request.biome.as_deref(),
+        request.world_structure.as_deref(),
+        request.blend,
+    )?;
+    let heightmap = Heightmap::generate(
+        &generator,
+        request.x_min,
+        request.z_min,
+        request.width,
+        request.depth,
+        request.y_min,
+        request.y_max,
+    );
+    heightmap.write(Path::new(&request.output), &formats)
+}
+
+/// Render the biome layout or the visible surface material over a region to
+/// RGB PNG tiles with a legend JSON. Returns the paths written.
+#[tauri::command]
+pub fn export_map_image(request: MapImageRequest) -> Result<Vec<PathBuf>, String> {
+    let kind = MapKind::from_name(&request.kind)?;
+    if request.width == 0 || request.depth == 0 {
+        return Err("Width and depth must be positive".into());
+    }
+    if request.width as u64 * request.depth as u64 > MAX_MAP_PIXELS {
+        return Err(format!("Map too large: at most {} pixels", MAX_MAP_PIXELS));
+    }
+    let tile_size = request.tile_size.unwrap_or(DEFAULT_MAP_TILE_SIZE);
+    let blocks_per_pixel = request.blocks_per_pixel.unwrap_or(1);
+    if tile_size == 0 || blocks_per_pixel == 0 {
+        return Err("Tile size and blocks per pixel must be positive".into());
+    }
+    let area = MapArea {
+        x_min: request.x_min,
+        z_min: request.z_min,
+        width: request.width,
+        depth: request.depth,
+        blocks_per_pixel,
+    };
+
+    let pack = WorldgenPack::load(Path::new(&request.pack_path))?;
+    let map = match kind {
+        MapKind::Biome => {
+            let structure = pack.world_structure(request.world_structure.as_deref())?;
+            let selector = BiomeSelector::new(structure, &pack.placement_scope.positions)?;
+            CategoryMap::biomes(&selector, area)
+        }
+        MapKind::Material => {
+            if request.y_max <= request.y_min {
+                return Err("y_max must be greater than y_min".into());
+            }
+            let height = (request.y_max as i64 - request.y_min as i64) as u64;
+            if request.width as u64 * request.depth as u64 * height > MAX_MATERIAL_MAP_BLOCKS {
+                return Err(format!(
+                    "Region too large: at most {} blocks",
+                    MAX_MATERIAL_MAP_BLOCKS
+                ));
+            }
+            let generator = ChunkGenerator::for_pack(
+                &pack,
+                request.biome.as_deref(),
+                request.world_structure.as_deref(),
+                request.blend,
+            )?;
+            CategoryMap::materials(&generator, area, request.y_min, request.y_max)
+        }
+    };
+    map.write(Path::new(&request.output), tile_size)
+}
+
+/// Extract the terrain isosurface over a region and write it as OBJ, binary
+/// glTF or STL.
+#[tauri::command]
+pub fn export_mesh(request: MeshExportRequest) -> Result<MeshExport, String> {
+    let format = MeshFormat::from_name(&request.format)?;
+    let size = [request.size_x, request.size_y, request.size_z];
+    if size.iter().any(|&n| n < 2) {
+        return Err("Each axis needs at least 2 samples".into());
+    }
+    if size.iter().product::<usize>() > MAX_MESH_SAMPLES {
+        return Err(format!(
+            "Region too large: at most {} density samples",
+            MAX_MESH_SAMPLES
+        ));
+    }
+
+    let pack = WorldgenPack::load(Path::new(&request.pack_path))?;
+    let generator = ChunkGenerator::for_pack(
+        &pack,
+        request.biome.as_deref(),
+        request.world_structure.as_deref(),
+        request.blend,
+    )?;
+    let volume = DensityVolume::sample(
+        &generator,
+        [request.x_min, request.y_min, request.z_min],
+        size,
+    );
+    let mut mesh = surface_nets(&volume);
+    if mesh.indices.is_empty() {
+        return Err("No terrain surface in the region".into());
+    }
+    if request.colors {
+        mesh.colorize(&generator, &volume);
+    }
+    Ok(MeshExport {
+        path: mesh.write(Path::new(&request.output), format)?,
+        vertices: mesh.positions.len(),
+        triangles: mesh.triangle_count(),
+    })
+}
+
+fn check_volume_size(size: [usize; 3]) -> Result<(), String> {
+    if size.contains(&0) {
+        return Err("Each axis needs at least 1 block".into());
+    }
+    if size.iter().product::<usize>() > MAX_VOLUME_BLOCKS {
+        return Err(format!(
+            "Region too large: at most {} blocks",
+            MAX_VOLUME_BLOCKS
+        ));
+    }
+    Ok(())
+}
+
+/// Generate a request's region offline.
+fn generate_volume(request: &BlockExportRequest) -> Result<BlockVolume, String> {
+    let size = [request.size_x, request.size_y, request.size_z];
+    check_volume_size(size)?;
+    let pack = WorldgenPack::load(Path::new(&request.pack_path))?;
+    let generator = ChunkGenerator::for_pack(
+        &pack,
+        request.biome.as_deref(),
+        request.world_structure.as_deref(),
+        request.blend,
+    )?;
+    Ok(BlockVolume::generate(
+        &generator,
+        [request.x_min, request.y_min, request.z_min],
+        size,
+    ))
+}
+
+/// Assemble a request's region from chunks fetched from the running server.
+async fn fetch_volume(
+    client: &BridgeClient,
+    request: &FetchedBlockExportRequest,
+) -> Result<BlockVolume, String> {
+    let (min, size) = (
+        [request.x_min, request.y_min, request.z_min],
+        [request.size_x, request.size_y, request.size_z],
+    );
+    check_volume_size(size)?;
+    let palette = client.fetch_palette().await?.palette;
+    let mut volume = BlockVolume::new(min, size);
+    let y_max = min[1] + size[1] as i32;
+    let (chunks_x, chunks_z) = volume.chunk_range();
+    for chunk_z in chunks_z {
+        for chunk_x in chunks_x.clone() {
+            let chunk = client
+                .fetch_chunk(chunk_x, chunk_z, min[1], y_max, request.force_load)
+                .await?;
+            volume.add_chunk(&chunk, &palette);
+        }
+    }
+    Ok(volume)
+}
+
+fn write_vox(volume: &BlockVolume, output: &str) -> Result<VoxExport, String> {
+    if volume.block_count() == 0 {
+        return Err("No blocks in the region".into());
+    }
+    let vox = VoxFile::from_volume(volume)?;
+    Ok(VoxExport {
+        path: vox.write(Path::new(output))?,
+        models: vox.model_count(),
+        voxels: vox.voxel_count(),
+        palette: vox.palette,
+    })
+}
+
+/// Generate a region offline and write it as a MagicaVoxel `.vox`, split
+/// into models of at most 256³.
+#[tauri::command]
+pub fn export_vox(request: BlockExportRequest) -> Result<VoxExport, String> {
+    write_vox(&generate_volume(&request)?, &request.output)
+}
+
+/// Fetch a region's chunks from the bridge and write it as a MagicaVoxel
+/// `.vox`.
+#[tauri::command]
+pub async fn export_fetched_vox(
+    request: FetchedBlockExportRequest,
+    state: tauri::State<'_, BridgeState>,
+) -> Result<VoxExport, String> {
+    let client = state.get_client().await?;
+    write_vox(&fetch_volume(&client, &request).await?, &request.output)
+}
+
+fn write_schematic_export(volume: &BlockVolume, output: &str) -> Result<SchematicExport, String> {
+    if volume.block_count() == 0 {
+        return Err("No blocks in the region".into());
+    }
+    Ok(SchematicExport {
+        path: write_schematic(volume, Path::new(output))?,
+        blocks: volume.block_count(),
+        block_types: volume.names.len() - 1,
+    })
+}
+
+/// Generate a region offline and write it as a gzipped Sponge `.schem`.
+#[tauri::command]
+pub fn export_schematic(request: BlockExportRequest) -> Result<SchematicExport, String> {
+    write_schematic_export(&generate_volume(&request)?, &request.output)
+}
+
+/// Fetch a region's chunks from the bridge and write it as a Sponge
+/// `.schem`.
+#[tauri::command]
+pub async fn export_fetched_schematic(
+    request: FetchedBlockExportRequest,
+    state: tauri::State<'_, BridgeState>,
+) -> Result<SchematicExport, String> {
+    let client = state.get_client().await?;
+    write_schematic_export(&fetch_volume(&client, &request).await?, &request.output)
+}
+
+/// Read a Sponge `.schem` as a List position provider plus the block list
+/// for a prop preview.
+#[tauri::command]
+pub fn import_schematic(path: String) -> Result<SchematicImport, String> {
+    let bytes = fs::read(&path).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+    let volume = decode_schematic(&bytes)?;
+    let name = Path::new(&path)
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or(path);
+    Ok(SchematicImport::from_volume(&volume, &name))
+}
@@ -0,0 +1,129 @@
+use std::collections::HashMap;
+
+use crate::material::column::SolidityColumn;
+use crate::material::evaluator::{parse_space_and_depth, MaterialEvaluator, MaterialScope};
+use crate::material::layers::{column_stacks, SurfaceStack};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+#[derive(Deserialize)]
+pub struct MaterialColumnRequest {
+    /// The material provider as V2 JSON
+    pub provider: Value,
+    /// Exported providers that `Imported` nodes may reference, by name
+    #[serde(default)]
+    pub exports: HashMap<String, Value>,
+    /// BaseHeight values for SimpleHorizontal bounds, by name
+    #[serde(default)]
+    pub base_heights: HashMap<String, f64>,
+    /// Column position
+    pub x: i32,
+    pub z: i32,
+    /// World Y of the first entry in `solid`
+    pub y_min: i32,
+    /// Solidity of each block, bottom to top
+    pub solid: Vec<bool>,
+    /// Biome name for Switch providers
+    #[serde(default)]
+    pub biome: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct MaterialColumnResponse {
+    pub y_min: i32,
+    /// Block name per Y, bottom to top; null where no provider applies
+    pub blocks: Vec<Option<String>>,
+}
+
+#[derive(Deserialize)]
+pub struct LayerStackRequest {
+    /// A SpaceAndDepth material provider as V2 JSON
+    pub provider: Value,
+    /// Exported providers that layer materials may import, by name
+    #[serde(default)]
+    pub exports: HashMap<String, Value>,
+    /// BaseHeight values for SimpleHorizontal bounds in layer materials, by name
+    #[serde(default)]
+    pub base_heights: HashMap<String, f64>,
+    /// Column position
+    pub x: i32,
+    pub z: i32,
+    /// World Y of the first entry in `solid`
+    pub y_min: i32,
+    /// Solidity of each block, bottom to top
+    pub solid: Vec<bool>,
+    /// Biome name for Switch providers in layer materials
+    #[serde(default)]
+    pub biome: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct LayerStackResponse {
+    /// DEPTH_INTO_FLOOR or DEPTH_INTO_CEILING
+    pub layer_context: String,
+    pub max_expected_depth: Option<i32>,
+    /// One stack per floor (or ceiling) surface, top to bottom
+    pub surfaces: Vec<SurfaceStack>,
+}
+
+/// Run a material provider over a solidity column and return the block each
+/// position resolves to.
+#[tauri::command]
+pub fn evaluate_material_column(
+    request: MaterialColumnRequest,
+) -> Result<MaterialColumnResponse, String> {
+    let scope = MaterialScope {
+        exports: request.exports,
+        base_heights: request.base_heights,
+        ..Default::default()
+    };
+    let evaluator = MaterialEvaluator::from_json(&request.provider, &scope)
+        .map_err(|e| format!("Parse error: {}", e))?;
+
+    let column = SolidityColumn::new(request.y_min, request.solid);
+    let blocks = (column.y_min..column.y_max())
+        .map(|y| {
+            evaluator
+                .pick_in_column(&column, request.x, y, request.z, request.biome.as_deref())
+                .map(str::to_string)
+        })
+        .collect();
+
+    Ok(MaterialColumnResponse {
+        y_min: request.y_min,
+        blocks,
+    })
+}
+
+/// Compute the layer thicknesses a SpaceAndDepth provider produces in one
+/// column, and where each layer lands under every surface.
+#[tauri::command]
+pub fn compute_layer_stack(request: LayerStackRequest) -> Result<LayerStackResponse, String> {
+    let scope = MaterialScope {
+        exports: request.exports,
+        base_heights: request.base_heights,
+        ..Default::default()
+    };
+    let provider = parse_space_and_depth(&request.provider, &scope)
+        .map_err(|e| format!("Parse error: {}", e))?;
+
+    let column = SolidityColumn::new(request.y_min, request.solid);
+    let surfaces = column_stacks(
+        &provider,
+        &column,
+        request.x,
+        request.z,
+        request.biome.as_deref(),
+    );
+
+    Ok(LayerStackResponse {
+        layer_context: if provider.ceiling {
+            "DEPTH_INTO_CEILING"
+        } else {
+            "DEPTH_INTO_FLOOR"
+        }
+        .to_string(),
+        max_expected_depth: provider.max_expected_depth,
+        surfaces,
+    })
+}
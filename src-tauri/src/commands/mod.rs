@@ -1,6 +1,12 @@
+pub mod analysis;
 pub mod bridge;
+pub mod export;
 pub mod hardware;
 pub mod io;
+pub mod material;
+pub mod placement;
 pub mod preview;
 pub mod process;
+pub mod tiles;
 pub mod validate;
+pub mod worldgen;
@@ -0,0 +1,408 @@
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::bridge::types::ChunkDataResponse;
+use crate::placement::assignments::{assignment_histogram, Assignment, AssignmentHistogramEntry};
+use crate::placement::block_masks::{BlockMask, MaskResult};
+use crate::placement::directionality::{Directionality, Orientation};
+use crate::placement::patterns::{pattern_mask, Pattern, PatternScope};
+use crate::placement::prefabs::{quarter_turns, Prefab, PrefabBlock, PrefabLibrary};
+use crate::placement::props::{
+    count_by_type, simulate_props, PlacedProp, PlacementScope, PropRuntime,
+};
+use crate::placement::scanners::{ScanResult, Scanner, ScannerScope};
+use crate::placement::voxels::VoxelChunk;
+use crate::worldgen::chunk::{BlockPalette, ChunkGenerator};
+use crate::worldgen::pack::WorldgenPack;
+
+#[derive(Deserialize)]
+pub struct PatternMaskRequest {
+    /// The pattern as V2 JSON
+    pub pattern: Value,
+    /// Exported patterns that `Imported` nodes may reference, by name
+    #[serde(default)]
+    pub exports: HashMap<String, Value>,
+    /// Chunk from `bridge_fetch_chunk` or `generate_offline_chunk`
+    pub chunk: ChunkDataResponse,
+    /// Block id (as a string) to block name
+    pub palette: HashMap<String, String>,
+}
+
+#[derive(Serialize)]
+pub struct PatternMaskResponse {
+    /// One entry per block, in the chunk's block layout
+    pub mask: Vec<bool>,
+    pub matches: usize,
+}
+
+#[derive(Deserialize)]
+pub struct ScanRequest {
+    /// The scanner as V2 JSON; scans only the origin when omitted
+    #[serde(default)]
+    pub scanner: Option<Value>,
+    /// The pattern candidates are tested against; accepts everything when omitted
+    #[serde(default)]
+    pub pattern: Option<Value>,
+    /// Exported patterns, by name
+    #[serde(default)]
+    pub pattern_exports: HashMap<String, Value>,
+    /// Exported scanners, by name
+    #[serde(default)]
+    pub scanner_exports: HashMap<String, Value>,
+    /// BaseHeight values for `BaseHeightName`, by name
+    #[serde(default)]
+    pub base_heights: HashMap<String, f64>,
+    pub chunk: ChunkDataResponse,
+    pub palette: HashMap<String, String>,
+    /// World position the scan starts from
+    pub x: i32,
+    pub y: i32,
+    pub z: i32,
+}
+
+#[derive(Deserialize)]
+pub struct PropSimulationRequest {
+    /// Asset pack directory
+    pub pack_path: String,
+    /// Biome whose Props run; the pack's default biome when omitted
+    #[serde(default)]
+    pub biome: Option<String>,
+    /// Chunk to generate offline with that biome
+    pub chunk_x: i32,
+    pub chunk_z: i32,
+    pub y_min: i32,
+    pub y_max: i32,
+    /// A chunk fetched through the bridge to run on instead; the chunk
+    /// position and Y range above are then ignored
+    #[serde(default)]
+    pub chunk: Option<ChunkDataResponse>,
+    #[serde(default)]
+    pub palette: HashMap<String, String>,
+    /// Game or asset directory prefabs are read from; Prefab props are a
+    /// single block when omitted
+    #[serde(default)]
+    pub prefab_dir: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct PropSimulationResponse {
+    pub biome: String,
+    /// Props in placement order: by runtime, then position
+    pub placed: Vec<PlacedProp>,
+    /// Placed props by type
+    pub counts: BTreeMap<String, usize>,
+    /// Prefabs that couldn't be found or read
+    pub prefab_errors: Vec<String>,
+}
+
+#[derive(Deserialize)]
+pub struct AssignmentRequest {
+    /// The assignment as V2 JSON
+    pub assignment: Value,
+    /// Asset pack to resolve `Imported` assignments, props and patterns from
+    #[serde(default)]
+    pub pack_path: Option<String>,
+    /// Positions to report individually
+    #[serde(default)]
+    pub positions: Vec<[f64; 3]>,
+    /// Grid to build the histogram over; the positions above when omitted
+    #[serde(default)]
+    pub area: Option<AssignmentArea>,
+}
+
+#[derive(Deserialize)]
+pub struct AssignmentArea {
+    pub x_min: f64,
+    pub x_max: f64,
+    pub z_min: f64,
+    pub z_max: f64,
+    pub y: f64,
+    /// Samples along each axis
+    pub resolution: u32,
+}
+
+#[derive(Serialize)]
+pub struct AssignmentSample {
+    pub position: [f64; 3],
+    /// Label of the assigned prop; null when skipped or unassigned
+    pub prop: Option<String>,
+    /// Each decision from the root assignment down
+    pub reason: Vec<String>,
+}
+
+#[derive(Serialize)]
+pub struct AssignmentResponse {
+    pub samples: Vec<AssignmentSample>,
+    pub histogram: Vec<AssignmentHistogramEntry>,
+}
+
+#[derive(Deserialize)]
+pub struct DirectionalityRequest {
+    /// The directionality as V2 JSON
+    pub directionality: Value,
+    /// Asset pack to resolve `Imported` directionalities and patterns from
+    #[serde(default)]
+    pub pack_path: Option<String>,
+    pub chunk: ChunkDataResponse,
+    pub palette: HashMap<String, String>,
+    /// Origins to orient, in world coordinates
+    pub positions: Vec<[i32; 3]>,
+}
+
+#[derive(Serialize)]
+pub struct DirectionalitySample {
+    pub position: [i32; 3],
+    /// Whether the directionality's pattern lets a prefab be placed here
+    pub placeable: bool,
+    /// Rotation in degrees clockwise; null when no directional pattern matched
+    pub rotation: Option<i32>,
+    pub orientation: Option<Orientation>,
+}
+
+#[derive(Deserialize)]
+pub struct PrefabRequest {
+    /// Game or asset directory; paths resolve under `Server/Prefabs` or `Prefabs`
+    pub asset_dir: String,
+    /// A prefab path as written in `WeightedPrefabPaths`
+    pub path: String,
+    /// Which file to load when the path is a directory of variants
+    #[serde(default)]
+    pub variant: usize,
+    /// Rotation in degrees clockwise
+    #[serde(default)]
+    pub rotation: i32,
+    /// BlockMask JSON whose `DontPlace` filters the prefab's blocks
+    #[serde(default)]
+    pub block_mask: Option<Value>,
+    /// Named block masks the mask may `Import`, by name
+    #[serde(default)]
+    pub block_mask_exports: HashMap<String, Value>,
+}
+
+#[derive(Deserialize)]
+pub struct BlockMaskRequest {
+    /// The BlockMask as V2 JSON
+    pub block_mask: Value,
+    /// Named block masks the mask may `Import`, by name
+    #[serde(default)]
+    pub exports: HashMap<String, Value>,
+    /// Terrain the prop is placed into
+    pub chunk: ChunkDataResponse,
+    pub palette: HashMap<String, String>,
+    /// The prop's blocks in world positions
+    pub blocks: Vec<PrefabBlock>,
+}
+
+#[derive(Serialize)]
+pub struct PrefabResponse {
+    /// Every file the path stands for, relative to the asset directory
+    pub variants: Vec<String>,
+    pub prefab: Prefab,
+}
+
+/// Test a pattern at every block of a chunk.
+#[tauri::command]
+pub fn evaluate_pattern_mask(request: PatternMaskRequest) -> Result<PatternMaskResponse, String> {
+    let scope = PatternScope {
+        exports: request.exports,
+    };
+    let pattern =
+        Pattern::from_json(&request.pattern, &scope).map_err(|e| format!("Parse error: {}", e))?;
+    let voxels = VoxelChunk::new(&request.chunk, &request.palette)?;
+
+    let mask = pattern_mask(&pattern, &voxels);
+    Ok(PatternMaskResponse {
+        matches: mask.iter().filter(|m| **m).count(),
+        mask,
+    })
+}
+
+/// Run a scanner from one origin and return every position it visited and
+/// the ones the pattern accepted, both in scan order.
+#[tauri::command]
+pub fn scan_positions(request: ScanRequest) -> Result<ScanResult, String> {
+    let pattern = match &request.pattern {
+        Some(json) => Pattern::from_json(
+            json,
+            &PatternScope {
+                exports: request.pattern_exports,
+            },
+        )
+        .map_err(|e| format!("Pattern parse error: {}", e))?,
+        None => Pattern::Constant(true),
+    };
+    let scope = ScannerScope {
+        exports: request.scanner_exports,
+        base_heights: request.base_heights,
+    };
+    let scanner = Scanner::from_json(request.scanner.as_ref(), &scope)
+        .map_err(|e| format!("Scanner parse error: {}", e))?;
+    let voxels = VoxelChunk::new(&request.chunk, &request.palette)?;
+
+    Ok(scanner.scan(&voxels, &pattern, [request.x, request.y, request.z]))
+}
+
+/// Run a biome's Props over one chunk and return every prop placed, with
+/// its origin, runtime and bounding box.
+#[tauri::command]
+pub fn simulate_biome_props(
+    request: PropSimulationRequest,
+) -> Result<PropSimulationResponse, String> {
+    let pack = WorldgenPack::load(Path::new(&request.pack_path))?;
+    let biome = pack.biome(request.biome.as_deref())?;
+
+    let voxels = match &request.chunk {
+        Some(chunk) => VoxelChunk::new(chunk, &request.palette)?,
+        None => {
+            if request.y_max <= request.y_min {
+                return Err("y_max must be greater than y_min".into());
+            }
+            let generator = ChunkGenerator::for_biome(biome, &pack)?;
+            let offline = generator.generate(
+                request.chunk_x,
+                request.chunk_z,
+                request.y_min,
+                request.y_max,
+                &mut BlockPalette::default(),
+            );
+            VoxelChunk::new(&offline.chunk, &offline.palette)?
+        }
+    };
+
+    let mut props = Vec::new();
+    for (i, asset) in biome.props.iter().enumerate() {
+        if let Some(prop) = PropRuntime::from_asset(asset, i, &pack.placement_scope)? {
+            props.push(prop);
+        }
+    }
+    let mut prefabs = PrefabLibrary::new(request.prefab_dir.as_deref().map(Path::new));
+    let placed = simulate_props(&props, &voxels, &mut prefabs);
+
+    Ok(PropSimulationResponse {
+        biome: biome.name.clone(),
+        counts: count_by_type(&placed),
+        placed,
+        prefab_errors: prefabs.errors(),
+    })
+}
+
+/// Evaluate an assignment at each position, reporting the chosen prop and
+/// why, plus how the choices are distributed over an area.
+#[tauri::command]
+pub fn evaluate_assignments(request: AssignmentRequest) -> Result<AssignmentResponse, String> {
+    let scope = match &request.pack_path {
+        Some(path) => WorldgenPack::load(Path::new(path))?.placement_scope,
+        None => PlacementScope::default(),
+    };
+    let assignment = Assignment::from_json(&request.assignment, &scope)
+        .map_err(|e| format!("Parse error: {}", e))?;
+
+    let samples = request
+        .positions
+        .iter()
+        .map(|&position| {
+            let trace = assignment.trace(position);
+            AssignmentSample {
+                position,
+                prop: trace.prop.map(|p| p.label.clone()),
+                reason: trace.steps,
+            }
+        })
+        .collect();
+
+    let histogram = match &request.area {
+        Some(area) => {
+            if area.resolution == 0 {
+                return Err("resolution must be greater than 0".into());
+            }
+            let n = area.resolution as usize;
+            let step_x = (area.x_max - area.x_min) / n as f64;
+            let step_z = (area.z_max - area.z_min) / n as f64;
+            let grid: Vec<[f64; 3]> = (0..n * n)
+                .map(|i| {
+                    [
+                        area.x_min + ((i % n) as f64 + 0.5) * step_x,
+                        area.y,
+                        area.z_min + ((i / n) as f64 + 0.5) * step_z,
+                    ]
+                })
+                .collect();
+            assignment_histogram(&assignment, &grid)
+        }
+        None => assignment_histogram(&assignment, &request.positions),
+    };
+
+    Ok(AssignmentResponse { samples, histogram })
+}
+
+/// Resolve a prefab directionality at each position, reporting the rotation
+/// chosen and which directional patterns matched.
+#[tauri::command]
+pub fn evaluate_directionality(
+    request: DirectionalityRequest,
+) -> Result<Vec<DirectionalitySample>, String> {
+    let scope = match &request.pack_path {
+        Some(path) => WorldgenPack::load(Path::new(path))?.placement_scope,
+        None => PlacementScope::default(),
+    };
+    let (directionality, pattern) =
+        Directionality::from_json(Some(&request.directionality), &scope)
+            .map_err(|e| format!("Parse error: {}", e))?;
+    let voxels = VoxelChunk::new(&request.chunk, &request.palette)?;
+
+    Ok(request
+        .positions
+        .iter()
+        .map(|&position| {
+            let [x, y, z] = position;
+            let orientation = directionality.orient(&voxels, position);
+            DirectionalitySample {
+                position,
+                placeable: pattern.matches(&voxels, x, y, z) && orientation.is_some(),
+                rotation: orientation.as_ref().map(|o| o.turns * 90),
+                orientation,
+            }
+        })
+        .collect())
+}
+
+/// Load a prefab from a game or asset directory as a block list with bounds,
+/// rotated and filtered by a block mask.
+#[tauri::command]
+pub fn load_prefab(request: PrefabRequest) -> Result<PrefabResponse, String> {
+    let mut library = PrefabLibrary::new(Some(Path::new(&request.asset_dir)));
+    let files = library.variants(&request.path)?;
+    let file = files.get(request.variant).ok_or_else(|| {
+        format!(
+            "Variant {} out of range; '{}' has {}",
+            request.variant,
+            request.path,
+            files.len()
+        )
+    })?;
+    let prefab = library
+        .load_file(file)?
+        .rotated(quarter_turns(request.rotation))
+        .masked(&BlockMask::from_json(
+            request.block_mask.as_ref(),
+            &request.block_mask_exports,
+        )?);
+
+    Ok(PrefabResponse {
+        variants: files.iter().map(|f| library.relative_path(f)).collect(),
+        prefab,
+    })
+}
+
+/// Merge a prop's blocks into terrain under a block mask, reporting which
+/// blocks each rule rejected.
+#[tauri::command]
+pub fn apply_block_mask(request: BlockMaskRequest) -> Result<MaskResult, String> {
+    let mask = BlockMask::from_json(Some(&request.block_mask), &request.exports)
+        .map_err(|e| format!("Parse error: {}", e))?;
+    let voxels = VoxelChunk::new(&request.chunk, &request.palette)?;
+    Ok(mask.apply(&voxels, &request.blocks))
+}
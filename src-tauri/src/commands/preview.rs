@@ -1,6 +1,9 @@
 use crate::noise::evaluator::DensityEvaluator;
+use crate::noise::profiler::NodeProfile;
+use crate::noise::statistics::{PreviewStatistics, StatisticsOptions, StatsAccumulator};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use std::time::Instant;
 
 #[derive(Deserialize)]
 pub struct EvaluateRequest {
@@ -13,6 +16,9 @@ pub struct EvaluateRequest {
     pub range_max: f64,
     /// Y level for 2D evaluation
     pub y_level: f64,
+    /// Request a statistics block in the response
+    #[serde(default)]
+    pub statistics: Option<StatisticsOptions>,
 }
 
 #[derive(Serialize)]
@@ -24,6 +30,56 @@ pub struct EvaluateResponse {
     /// Min/max values in the result (for normalization)
     pub min_value: f32,
     pub max_value: f32,
+    /// Histogram and summary statistics, if requested
+    pub statistics: Option<PreviewStatistics>,
+}
+
+#[derive(Deserialize)]
+pub struct GradientRequest {
+    /// The density graph as V2 JSON
+    pub graph: Value,
+    /// Grid resolution (e.g., 128 for 128x128)
+    pub resolution: u32,
+    /// World coordinate range
+    pub range_min: f64,
+    pub range_max: f64,
+    /// Y level for 2D evaluation
+    pub y_level: f64,
+    /// Central-difference step for nodes without analytic derivatives
+    #[serde(default = "default_gradient_step")]
+    pub step: f64,
+    /// Also return unit surface normals
+    #[serde(default)]
+    pub normals: bool,
+}
+
+fn default_gradient_step() -> f64 {
+    0.5
+}
+
+#[derive(Serialize)]
+pub struct GradientResponse {
+    /// Flattened NxN density values (row-major)
+    pub values: Vec<f32>,
+    /// Flattened NxN gradients as [dx, dy, dz] triples (row-major)
+    pub gradients: Vec<f32>,
+    /// Flattened NxN unit normals as [nx, ny, nz] triples, pointing out of
+    /// solid terrain (empty unless requested)
+    pub normals: Vec<f32>,
+    /// Grid resolution
+    pub resolution: u32,
+    /// Largest gradient magnitude in the result (for slope shading)
+    pub max_slope: f32,
+}
+
+#[derive(Serialize)]
+pub struct ProfileResponse {
+    /// Number of grid samples evaluated
+    pub samples: usize,
+    /// Wall-clock time of the instrumented grid evaluation
+    pub elapsed_ms: f64,
+    /// Per-node costs, most expensive (by self time) first
+    pub nodes: Vec<NodeProfile>,
 }
 
 /// Evaluate a density function graph at an NxN grid of positions.
@@ -32,6 +88,97 @@ pub fn evaluate_density(request: EvaluateRequest) -> Result<EvaluateResponse, St
     let evaluator =
         DensityEvaluator::from_json(&request.graph).map_err(|e| format!("Parse error: {}", e))?;
 
+    let mut stats = request
+        .statistics
+        .clone()
+        .map(StatsAccumulator::new);
+    let (values, min_val, max_val) = evaluate_grid(&evaluator, &request, stats.as_mut());
+    let statistics = stats.map(|s| s.finish());
+
+    Ok(EvaluateResponse {
+        values,
+        resolution: request.resolution,
+        min_value: min_val,
+        max_value: max_val,
+        statistics,
+    })
+}
+
+/// Evaluate a density graph over the same grid as `evaluate_density` with
+/// every node instrumented, and return a per-node cost report.
+#[tauri::command]
+pub fn profile_density(request: EvaluateRequest) -> Result<ProfileResponse, String> {
+    let evaluator = DensityEvaluator::from_json_profiled(&request.graph)
+        .map_err(|e| format!("Parse error: {}", e))?;
+
+    let start = Instant::now();
+    let (values, _, _) = evaluate_grid(&evaluator, &request, None);
+    let elapsed_ms = start.elapsed().as_secs_f64() * 1000.0;
+
+    let samples = values.len();
+    let nodes = evaluator.profile_report(samples).unwrap_or_default();
+
+    Ok(ProfileResponse {
+        samples,
+        elapsed_ms,
+        nodes,
+    })
+}
+
+/// Evaluate density gradients (∂d/∂x, ∂d/∂y, ∂d/∂z) over an NxN grid.
+#[tauri::command]
+pub fn evaluate_gradient(request: GradientRequest) -> Result<GradientResponse, String> {
+    if request.step <= 0.0 {
+        return Err("Gradient step must be positive".into());
+    }
+    let evaluator =
+        DensityEvaluator::from_json(&request.graph).map_err(|e| format!("Parse error: {}", e))?;
+
+    let n = request.resolution as usize;
+    let step = (request.range_max - request.range_min) / n as f64;
+    let mut values = Vec::with_capacity(n * n);
+    let mut gradients = Vec::with_capacity(n * n * 3);
+    let mut normals = Vec::with_capacity(if request.normals { n * n * 3 } else { 0 });
+    let mut max_slope = 0.0f32;
+
+    for z_idx in 0..n {
+        let z = request.range_min + (z_idx as f64 + 0.5) * step;
+        for x_idx in 0..n {
+            let x = request.range_min + (x_idx as f64 + 0.5) * step;
+            let (val, grad) = evaluator.evaluate_gradient(x, request.y_level, z, request.step);
+            values.push(val as f32);
+            gradients.extend(grad.map(|d| d as f32));
+
+            let len = (grad[0] * grad[0] + grad[1] * grad[1] + grad[2] * grad[2]).sqrt();
+            max_slope = max_slope.max(len as f32);
+            if request.normals {
+                // Density increases into the solid, so the outward normal is -∇d
+                let normal = if len > f64::EPSILON {
+                    grad.map(|d| (-d / len) as f32)
+                } else {
+                    [0.0, 1.0, 0.0]
+                };
+                normals.extend(normal);
+            }
+        }
+    }
+
+    Ok(GradientResponse {
+        values,
+        gradients,
+        normals,
+        resolution: request.resolution,
+        max_slope,
+    })
+}
+
+/// Evaluate the request's NxN grid, returning row-major values and their min/max.
+/// Each sample is also fed to `stats` when present.
+fn evaluate_grid(
+    evaluator: &DensityEvaluator,
+    request: &EvaluateRequest,
+    mut stats: Option<&mut StatsAccumulator>,
+) -> (Vec<f32>, f32, f32) {
     let n = request.resolution as usize;
     let mut values = Vec::with_capacity(n * n);
     let step = (request.range_max - request.range_min) / n as f64;
@@ -44,16 +191,14 @@ pub fn evaluate_density(request: EvaluateRequest) -> Result<EvaluateResponse, St
         for x_idx in 0..n {
             let x = request.range_min + (x_idx as f64 + 0.5) * step;
             let val = evaluator.evaluate(x, request.y_level, z) as f32;
+            if let Some(stats) = stats.as_deref_mut() {
+                stats.push(val as f64);
+            }
             min_val = min_val.min(val);
             max_val = max_val.max(val);
             values.push(val);
         }
     }
 
-    Ok(EvaluateResponse {
-        values,
-        resolution: request.resolution,
-        min_value: min_val,
-        max_value: max_val,
-    })
+    (values, min_val, max_val)
 }
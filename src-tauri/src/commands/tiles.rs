@@ -0,0 +1,92 @@
+use crate::noise::evaluator::DensityEvaluator;
+use crate::noise::tiles::{self, TileCache, TileKey, TileSource, MAX_ZOOM, TILE_SIZE};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::sync::Mutex;
+use tauri::Manager;
+
+/// Tile cache shared across preview requests.
+#[derive(Default)]
+pub struct TileCacheState(pub Mutex<TileCache>);
+
+#[derive(Deserialize)]
+pub struct TileRequest {
+    /// The density graph as V2 JSON
+    pub graph: Value,
+    /// Y level for 2D evaluation
+    pub y_level: f64,
+    /// Zoom level, 0 (coarsest) to `MAX_ZOOM` (one sample per block)
+    pub z: u32,
+    /// Tile column and row; tile (0, 0) starts at world X/Z 0
+    pub x: i32,
+    pub y: i32,
+}
+
+#[derive(Serialize)]
+pub struct TileResponse {
+    /// Flattened TILE_SIZE×TILE_SIZE density values (row-major)
+    pub values: Vec<f32>,
+    /// Samples along each tile edge
+    pub size: u32,
+    /// World X/Z of the tile's minimum corner
+    pub world_x: f64,
+    pub world_z: f64,
+    /// Blocks covered by one tile edge
+    pub span: f64,
+    pub min_value: f32,
+    pub max_value: f32,
+    pub source: TileSource,
+}
+
+/// Fetch one z/x/y density tile, serving it from the memory or disk cache or
+/// evaluating it.
+#[tauri::command]
+pub fn get_density_tile(
+    app: tauri::AppHandle,
+    request: TileRequest,
+    state: tauri::State<'_, TileCacheState>,
+) -> Result<TileResponse, String> {
+    if request.z > MAX_ZOOM {
+        return Err(format!("Zoom level must be between 0 and {}", MAX_ZOOM));
+    }
+    let evaluator =
+        DensityEvaluator::from_json(&request.graph).map_err(|e| format!("Parse error: {}", e))?;
+
+    let key = TileKey {
+        graph_hash: tiles::graph_hash(&request.graph, request.y_level),
+        z: request.z,
+        x: request.x,
+        y: request.y,
+    };
+
+    if let Ok(dir) = app.path().app_cache_dir() {
+        let mut cache = state.0.lock().map_err(|_| "Tile cache lock poisoned")?;
+        cache.set_disk_root(dir.join("tiles"));
+    }
+    let (tile, source) = tiles::resolve_tile(&state.0, &evaluator, &key, request.y_level);
+
+    let (world_x, world_z) = key.origin();
+    Ok(TileResponse {
+        values: tile.values.clone(),
+        size: TILE_SIZE as u32,
+        world_x,
+        world_z,
+        span: key.span(),
+        min_value: tile.min_value,
+        max_value: tile.max_value,
+        source,
+    })
+}
+
+/// Remove all cached density tiles from memory and disk.
+#[tauri::command]
+pub fn clear_tile_cache(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, TileCacheState>,
+) -> Result<(), String> {
+    let mut cache = state.0.lock().map_err(|_| "Tile cache lock poisoned")?;
+    if let Ok(dir) = app.path().app_cache_dir() {
+        cache.set_disk_root(dir.join("tiles"));
+    }
+    cache.clear()
+}
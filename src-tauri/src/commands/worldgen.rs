@@ -0,0 +1,211 @@
+use std::path::Path;
+
+use serde::Deserialize;
+
+use crate::placement::positions::Region;
+use crate::worldgen::biome_map::{biome_map, BiomeMap, BiomeSelector};
+use crate::worldgen::blend::BiomeBlender;
+use crate::worldgen::chunk::{BlockPalette, ChunkGenerator, OfflineChunk};
+use crate::worldgen::environment::{environment_map, EnvironmentMap};
+use crate::worldgen::framework::{framework_entries, FrameworkEntry};
+use crate::worldgen::pack::WorldgenPack;
+use crate::worldgen::spawn::{spawn_preview, SpawnPreview};
+
+#[derive(Deserialize)]
+pub struct OfflineChunkRequest {
+    /// Asset pack directory
+    pub pack_path: String,
+    pub chunk_x: i32,
+    pub chunk_z: i32,
+    pub y_min: i32,
+    pub y_max: i32,
+    /// Generate the whole chunk with this biome
+    #[serde(default)]
+    pub biome: Option<String>,
+    /// World structure file stem; the first one in the pack when omitted
+    #[serde(default)]
+    pub world_structure: Option<String>,
+    /// Blend biomes across DefaultTransitionDistance and return the weights
+    #[serde(default)]
+    pub blend: bool,
+}
+
+#[derive(Deserialize)]
+pub struct BiomeMapRequest {
+    /// Asset pack directory
+    pub pack_path: String,
+    /// World structure file stem; the first one in the pack when omitted
+    #[serde(default)]
+    pub world_structure: Option<String>,
+    /// World region to sample
+    pub x_min: f64,
+    pub x_max: f64,
+    pub z_min: f64,
+    pub z_max: f64,
+    /// Samples along each axis
+    pub resolution: u32,
+    /// Blend biomes across DefaultTransitionDistance and return the weights
+    #[serde(default)]
+    pub blend: bool,
+}
+
+#[derive(Deserialize)]
+pub struct EnvironmentMapRequest {
+    /// Asset pack directory
+    pub pack_path: String,
+    /// Biome whose providers are evaluated; the pack's default biome when omitted
+    #[serde(default)]
+    pub biome: Option<String>,
+    /// World region to sample
+    pub x_min: f64,
+    pub x_max: f64,
+    pub z_min: f64,
+    pub z_max: f64,
+    /// Height the providers' densities are evaluated at
+    #[serde(default)]
+    pub y: f64,
+    /// Samples along each axis
+    pub resolution: u32,
+}
+
+#[derive(Deserialize)]
+pub struct SpawnPreviewRequest {
+    /// Asset pack directory
+    pub pack_path: String,
+    /// World structure file stem; the first one in the pack when omitted
+    #[serde(default)]
+    pub world_structure: Option<String>,
+    /// World region to evaluate SpawnPositions in; Y also bounds the terrain
+    pub x_min: f64,
+    pub x_max: f64,
+    pub y_min: f64,
+    pub y_max: f64,
+    pub z_min: f64,
+    pub z_max: f64,
+    /// Blend biomes across DefaultTransitionDistance
+    #[serde(default)]
+    pub blend: bool,
+}
+
+#[derive(Deserialize)]
+pub struct FrameworkEntriesRequest {
+    /// Asset pack directory
+    pub pack_path: String,
+    /// World region Positions entries are generated in
+    pub x_min: f64,
+    pub x_max: f64,
+    pub y_min: f64,
+    pub y_max: f64,
+    pub z_min: f64,
+    pub z_max: f64,
+    /// Points returned per entry; 256 when omitted
+    #[serde(default)]
+    pub sample_limit: Option<usize>,
+}
+
+/// Generate a chunk from an asset pack without a running server, in the same
+/// shape as `bridge_fetch_chunk` plus the palette its block ids refer to.
+///
+/// With `biome` the whole chunk uses that biome; otherwise biomes follow the
+/// world structure, blended across its transition distance if `blend`.
+#[tauri::command]
+pub fn generate_offline_chunk(request: OfflineChunkRequest) -> Result<OfflineChunk, String> {
+    if request.y_max <= request.y_min {
+        return Err("y_max must be greater than y_min".into());
+    }
+    let pack = WorldgenPack::load(Path::new(&request.pack_path))?;
+    let generator = ChunkGenerator::for_pack(
+        &pack,
+        request.biome.as_deref(),
+        request.world_structure.as_deref(),
+        request.blend,
+    )?;
+
+    let mut palette = BlockPalette::default();
+    Ok(generator.generate(
+        request.chunk_x,
+        request.chunk_z,
+        request.y_min,
+        request.y_max,
+        &mut palette,
+    ))
+}
+
+/// Evaluate a NoiseRange world structure over a region and return the biome
+/// index grid with a legend and per-biome coverage.
+#[tauri::command]
+pub fn generate_biome_map(request: BiomeMapRequest) -> Result<BiomeMap, String> {
+    if request.resolution == 0 {
+        return Err("Resolution must be positive".into());
+    }
+    let pack = WorldgenPack::load(Path::new(&request.pack_path))?;
+    let structure = pack.world_structure(request.world_structure.as_deref())?;
+    let distance = if request.blend {
+        structure.default_transition_distance as f64
+    } else {
+        0.0
+    };
+    let selector = BiomeSelector::new(structure, &pack.placement_scope.positions)?;
+    let blender = BiomeBlender::new(selector, distance);
+
+    Ok(biome_map(
+        &blender,
+        (request.x_min, request.x_max),
+        (request.z_min, request.z_max),
+        request.resolution,
+    ))
+}
+
+/// Evaluate a biome's EnvironmentProvider and TintProvider over a region and
+/// return the environment-id grid and an RGB tint buffer.
+#[tauri::command]
+pub fn generate_environment_map(request: EnvironmentMapRequest) -> Result<EnvironmentMap, String> {
+    if request.resolution == 0 {
+        return Err("Resolution must be positive".into());
+    }
+    let pack = WorldgenPack::load(Path::new(&request.pack_path))?;
+    let biome = pack.biome(request.biome.as_deref())?;
+
+    environment_map(
+        biome,
+        &pack.placement_scope.positions,
+        (request.x_min, request.x_max),
+        (request.z_min, request.z_max),
+        request.y,
+        request.resolution,
+    )
+}
+
+/// Evaluate a world structure's SpawnPositions against the offline terrain
+/// and report each candidate's surface, biome and whether it is underwater.
+#[tauri::command]
+pub fn preview_spawn_positions(request: SpawnPreviewRequest) -> Result<SpawnPreview, String> {
+    if request.y_max <= request.y_min {
+        return Err("y_max must be greater than y_min".into());
+    }
+    let pack = WorldgenPack::load(Path::new(&request.pack_path))?;
+    let structure = pack.world_structure(request.world_structure.as_deref())?;
+    let region = Region {
+        min: [request.x_min, request.y_min, request.z_min],
+        max: [request.x_max, request.y_max, request.z_max],
+    };
+    spawn_preview(&pack, structure, &region, request.blend)
+}
+
+/// List every Framework entry in a pack's world structures, with a sample of
+/// the points each Positions entry generates over a region.
+#[tauri::command]
+pub fn list_framework_entries(
+    request: FrameworkEntriesRequest,
+) -> Result<Vec<FrameworkEntry>, String> {
+    let pack = WorldgenPack::load(Path::new(&request.pack_path))?;
+    let region = Region {
+        min: [request.x_min, request.y_min, request.z_min],
+        max: [request.x_max, request.y_max, request.z_max],
+    };
+    Ok(framework_entries(
+        &pack,
+        &region,
+        request.sample_limit.unwrap_or(256),
+    ))
+}
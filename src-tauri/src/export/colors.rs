@@ -0,0 +1,128 @@
+use crate::worldgen::environment::parse_color;
+
+/// Exact block name → color, checked before the keywords. Mirrors
+/// `BLOCK_COLORS` in the frontend's `blockColorMap.ts`; air is transparent
+/// there and black here.
+const BLOCK_COLORS: [(&str, &str); 34] = [
+    // Core terrain
+    ("air", "#000000"),
+    ("stone", "#909090"),
+    ("grass", "#5cb85c"),
+    ("dirt", "#a0724a"),
+    ("sand", "#d4c590"),
+    ("gravel", "#a0a0a0"),
+    ("bedrock", "#2a2a2a"),
+    ("cobblestone", "#7a7a7a"),
+    ("clay", "#b87333"),
+    // Rock variants
+    ("granite", "#9e8b7e"),
+    ("slate", "#5c5c6e"),
+    ("limestone", "#c4b99a"),
+    ("basalt", "#3d3d3d"),
+    ("sandstone", "#d2b48c"),
+    ("marble", "#e0ddd5"),
+    ("quartzite", "#d6cec0"),
+    // Soil variants
+    ("mud", "#6b4423"),
+    ("moss", "#4a7a4a"),
+    // Snow / Ice
+    ("snow", "#e8e8f0"),
+    ("ice", "#b0e0e6"),
+    ("packed_ice", "#9dcad4"),
+    // Wood / Organic
+    ("wood", "#8b6b4a"),
+    ("log", "#8b6b4a"),
+    ("planks", "#c4a870"),
+    ("leaves", "#3e8a3e"),
+    ("cactus", "#5c9e3e"),
+    // Fluids
+    ("water", "#4169e1"),
+    ("lava", "#ff4500"),
+    // Ores / Special
+    ("coal_ore", "#3a3a3a"),
+    ("iron_ore", "#b8916e"),
+    ("gold_ore", "#ffd700"),
+    // Vegetation
+    ("tall_grass", "#5cb85c"),
+    ("flower", "#d63384"),
+    ("mushroom", "#c0392b"),
+];
+
+/// Keyword → color for block names no exact name matches, first match wins.
+/// Mirrors `KEYWORD_COLORS` in `blockColorMap.ts`.
+const KEYWORD_COLORS: [(&str, &str); 31] = [
+    ("lava", "#ff4500"),
+    ("magma", "#b22222"),
+    ("water", "#4169e1"),
+    ("ice", "#b0e0e6"),
+    ("snow", "#e8e8f0"),
+    ("stone", "#909090"),
+    ("rock", "#909090"),
+    ("granite", "#9e8b7e"),
+    ("slate", "#5c5c6e"),
+    ("basalt", "#3d3d3d"),
+    ("limestone", "#c4b99a"),
+    ("sandstone", "#d2b48c"),
+    ("marble", "#e0ddd5"),
+    ("grass", "#5cb85c"),
+    ("dirt", "#a0724a"),
+    ("soil", "#a0724a"),
+    ("mud", "#6b4423"),
+    ("clay", "#b87333"),
+    ("sand", "#d4c590"),
+    ("gravel", "#a0a0a0"),
+    ("moss", "#4a7a4a"),
+    ("wood", "#8b6b4a"),
+    ("log", "#8b6b4a"),
+    ("plank", "#c4a870"),
+    ("leaves", "#3e8a3e"),
+    ("leaf", "#3e8a3e"),
+    ("bedrock", "#2a2a2a"),
+    ("cobble", "#7a7a7a"),
+    ("ore", "#908070"),
+    ("crystal", "#88ccee"),
+    ("glass", "#c0e8ff"),
+];
+
+/// Color of blocks no name or keyword matches.
+pub const UNKNOWN_BLOCK_COLOR: [u8; 3] = [0x80, 0x80, 0x80];
+
+/// Display color of a block name, matched case-insensitively by exact name
+/// and then by keyword, as the viewport resolves it.
+pub fn block_color(name: &str) -> [u8; 3] {
+    let lower = name
+        .strip_prefix("hytale:")
+        .unwrap_or(name)
+        .to_ascii_lowercase();
+    BLOCK_COLORS
+        .iter()
+        .find(|(block, _)| lower == *block)
+        .or_else(|| {
+            KEYWORD_COLORS
+                .iter()
+                .find(|(keyword, _)| lower.contains(keyword))
+        })
+        .and_then(|(_, color)| parse_color(color))
+        .unwrap_or(UNKNOWN_BLOCK_COLOR)
+}
+
+/// Color of a biome name: the hue hashed from the name at 58% saturation and
+/// lightness, as the biome range editor draws it.
+pub fn biome_color(name: &str) -> [u8; 3] {
+    let hash = name.encode_utf16().fold(0i32, |hash, unit| {
+        hash.wrapping_shl(5)
+            .wrapping_sub(hash)
+            .wrapping_add(unit as i32)
+    });
+    hsl_to_rgb(hash.rem_euclid(360) as f64, 0.58, 0.58)
+}
+
+fn hsl_to_rgb(h: f64, s: f64, l: f64) -> [u8; 3] {
+    let a = s * l.min(1.0 - l);
+    let channel = |n: f64| {
+        let k = (n + h / 30.0) % 12.0;
+        let value = l - a * (k - 3.0).min(9.0 - k).clamp(-1.0, 1.0);
+        (value * 255.0).round() as u8
+    };
+    [channel(0.0), channel(8.0), channel(4.0)]
+}
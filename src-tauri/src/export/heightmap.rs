@@ -0,0 +1,242 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+
+use crate::worldgen::chunk::{BlockPalette, ChunkGenerator, CHUNK_SIZE};
+
+/// File formats a heightmap can be written in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HeightmapFormat {
+    /// 16-bit grayscale PNG
+    Png,
+    /// Headerless little-endian u16 samples, row by row
+    Raw,
+    /// Uncompressed 16-bit grayscale TIFF
+    Tiff,
+}
+
+impl HeightmapFormat {
+    pub fn from_name(name: &str) -> Result<Self, String> {
+        match name.to_ascii_lowercase().as_str() {
+            "png" => Ok(HeightmapFormat::Png),
+            "raw" | "r16" => Ok(HeightmapFormat::Raw),
+            "tif" | "tiff" => Ok(HeightmapFormat::Tiff),
+            _ => Err(format!("Unknown heightmap format '{}'", name)),
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            HeightmapFormat::Png => "png",
+            HeightmapFormat::Raw => "raw",
+            HeightmapFormat::Tiff => "tif",
+        }
+    }
+}
+
+/// Surface heights over a block-aligned world region.
+#[derive(Debug, Clone)]
+pub struct Heightmap {
+    /// World X and Z of the first sample
+    pub x_min: i32,
+    pub z_min: i32,
+    pub width: u32,
+    pub depth: u32,
+    /// Generated height range, `y_max` exclusive
+    pub y_min: i32,
+    pub y_max: i32,
+    /// Highest block per column, row-major with Z as the row axis; `y_min`
+    /// where a column is empty
+    pub heights: Vec<i16>,
+}
+
+/// What a reader needs to turn samples back into world heights.
+#[derive(Debug, Clone, Serialize)]
+pub struct HeightmapSidecar {
+    pub width: u32,
+    pub depth: u32,
+    /// World bounds covered, both corners inclusive
+    pub world_min: [i32; 3],
+    pub world_max: [i32; 3],
+    /// Blocks per sample step: `height = y_min + sample * vertical_scale`
+    pub vertical_scale: f64,
+    /// Lowest and highest surface actually present
+    pub min_height: i32,
+    pub max_height: i32,
+    /// Sample byte order of the RAW file; PNG is big-endian by definition
+    pub raw_byte_order: &'static str,
+    /// Files written next to this sidecar
+    pub files: Vec<String>,
+}
+
+impl Heightmap {
+    /// Read the surface heights of every column in the region from offline
+    /// chunks, generating each overlapping chunk once.
+    pub fn generate(
+        generator: &ChunkGenerator,
+        x_min: i32,
+        z_min: i32,
+        width: u32,
+        depth: u32,
+        y_min: i32,
+        y_max: i32,
+    ) -> Self {
+        let mut heights = vec![y_min as i16; width as usize * depth as usize];
+        let (x_max, z_max) = (x_min + width as i32, z_min + depth as i32);
+        let mut palette = BlockPalette::default();
+
+        for chunk_z in z_min.div_euclid(CHUNK_SIZE)..=(z_max - 1).div_euclid(CHUNK_SIZE) {
+            for chunk_x in x_min.div_euclid(CHUNK_SIZE)..=(x_max - 1).div_euclid(CHUNK_SIZE) {
+                let offline = generator.generate(chunk_x, chunk_z, y_min, y_max, &mut palette);
+                for lz in 0..CHUNK_SIZE {
+                    for lx in 0..CHUNK_SIZE {
+                        let (x, z) = (chunk_x * CHUNK_SIZE + lx, chunk_z * CHUNK_SIZE + lz);
+                        if x < x_min || x >= x_max || z < z_min || z >= z_max {
+                            continue;
+                        }
+                        let i = (z - z_min) as usize * width as usize + (x - x_min) as usize;
+                        heights[i] = offline.chunk.heightmap[(lz * CHUNK_SIZE + lx) as usize];
+                    }
+                }
+            }
+        }
+
+        Heightmap {
+            x_min,
+            z_min,
+            width,
+            depth,
+            y_min,
+            y_max,
+            heights,
+        }
+    }
+
+    /// Blocks per sample step, spreading `y_min..y_max` over the full u16 range.
+    pub fn vertical_scale(&self) -> f64 {
+        (self.y_max - 1 - self.y_min).max(1) as f64 / u16::MAX as f64
+    }
+
+    /// Heights scaled to 16-bit samples.
+    pub fn samples(&self) -> Vec<u16> {
+        let span = (self.y_max - 1 - self.y_min).max(1) as f64;
+        self.heights
+            .iter()
+            .map(|&h| {
+                let steps = (h as i32 - self.y_min) as f64 * u16::MAX as f64 / span;
+                steps.round().clamp(0.0, u16::MAX as f64) as u16
+            })
+            .collect()
+    }
+
+    pub fn encode(&self, format: HeightmapFormat) -> Result<Vec<u8>, String> {
+        let samples = self.samples();
+        Ok(match format {
+            HeightmapFormat::Png => encode_png(&samples, self.width, self.depth)?,
+            HeightmapFormat::Raw => samples.iter().flat_map(|s| s.to_le_bytes()).collect(),
+            HeightmapFormat::Tiff => encode_tiff(&samples, self.width, self.depth),
+        })
+    }
+
+    pub fn sidecar(&self, files: Vec<String>) -> HeightmapSidecar {
+        HeightmapSidecar {
+            width: self.width,
+            depth: self.depth,
+            world_min: [self.x_min, self.y_min, self.z_min],
+            world_max: [
+                self.x_min + self.width as i32 - 1,
+                self.y_max - 1,
+                self.z_min + self.depth as i32 - 1,
+            ],
+            vertical_scale: self.vertical_scale(),
+            min_height: self.heights.iter().copied().min().unwrap_or(0) as i32,
+            max_height: self.heights.iter().copied().max().unwrap_or(0) as i32,
+            raw_byte_order: "little-endian",
+            files,
+        }
+    }
+
+    /// Write `<base>.<ext>` for each format plus `<base>.json`, returning
+    /// every path written.
+    pub fn write(&self, base: &Path, formats: &[HeightmapFormat]) -> Result<Vec<PathBuf>, String> {
+        if let Some(parent) = base.parent().filter(|p| !p.as_os_str().is_empty()) {
+            fs::create_dir_all(parent).map_err(|e| format!("Failed to create directory: {}", e))?;
+        }
+        let mut written = Vec::new();
+        for &format in formats {
+            let path = base.with_extension(format.extension());
+            fs::write(&path, self.encode(format)?)
+                .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+            written.push(path);
+        }
+
+        let files = written
+            .iter()
+            .filter_map(|p| p.file_name())
+            .map(|name| name.to_string_lossy().to_string())
+            .collect();
+        let sidecar = base.with_extension("json");
+        let json = serde_json::to_string_pretty(&self.sidecar(files))
+            .map_err(|e| format!("Failed to serialize: {}", e))?;
+        fs::write(&sidecar, json)
+            .map_err(|e| format!("Failed to write {}: {}", sidecar.display(), e))?;
+        written.push(sidecar);
+        Ok(written)
+    }
+}
+
+fn encode_png(samples: &[u16], width: u32, height: u32) -> Result<Vec<u8>, String> {
+    let mut bytes = Vec::new();
+    let mut encoder = png::Encoder::new(&mut bytes, width, height);
+    encoder.set_color(png::ColorType::Grayscale);
+    encoder.set_depth(png::BitDepth::Sixteen);
+    let data: Vec<u8> = samples.iter().flat_map(|s| s.to_be_bytes()).collect();
+    encoder
+        .write_header()
+        .and_then(|mut writer| writer.write_image_data(&data))
+        .map_err(|e| format!("Failed to encode PNG: {}", e))?;
+    Ok(bytes)
+}
+
+/// Baseline little-endian TIFF: one IFD and a single uncompressed strip.
+fn encode_tiff(samples: &[u16], width: u32, height: u32) -> Vec<u8> {
+    const SHORT: u16 = 3;
+    const LONG: u16 = 4;
+    let data_len = samples.len() as u32 * 2;
+    // (tag, type, value), sorted by tag as the format requires
+    let entries: [(u16, u16, u32); 9] = [
+        (256, LONG, width),
+        (257, LONG, height),
+        (258, SHORT, 16), // BitsPerSample
+        (259, SHORT, 1),  // Compression: none
+        (262, SHORT, 1),  // Photometric: BlackIsZero
+        (273, LONG, 0),   // StripOffsets, patched below
+        (277, SHORT, 1),  // SamplesPerPixel
+        (278, LONG, height),
+        (279, LONG, data_len),
+    ];
+    let data_offset = 8 + 2 + entries.len() as u32 * 12 + 4;
+
+    let mut out = Vec::with_capacity(data_offset as usize + data_len as usize);
+    out.extend_from_slice(b"II");
+    out.extend_from_slice(&42u16.to_le_bytes());
+    out.extend_from_slice(&8u32.to_le_bytes());
+    out.extend_from_slice(&(entries.len() as u16).to_le_bytes());
+    for (tag, kind, value) in entries {
+        let value = if tag == 273 { data_offset } else { value };
+        out.extend_from_slice(&tag.to_le_bytes());
+        out.extend_from_slice(&kind.to_le_bytes());
+        out.extend_from_slice(&1u32.to_le_bytes());
+        match kind {
+            SHORT => {
+                out.extend_from_slice(&(value as u16).to_le_bytes());
+                out.extend_from_slice(&[0, 0]);
+            }
+            _ => out.extend_from_slice(&value.to_le_bytes()),
+        }
+    }
+    out.extend_from_slice(&0u32.to_le_bytes());
+    out.extend(samples.iter().flat_map(|s| s.to_le_bytes()));
+    out
+}
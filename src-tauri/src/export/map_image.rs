@@ -0,0 +1,257 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+
+use crate::worldgen::biome_map::BiomeSelector;
+use crate::worldgen::chunk::{ChunkGenerator, EMPTY_BLOCK};
+
+use super::colors::{biome_color, block_color};
+
+/// What a map image colors each column by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MapKind {
+    /// Biome the NoiseRange world structure selects
+    Biome,
+    /// Topmost generated material, or the fluid above it
+    Material,
+}
+
+impl MapKind {
+    pub fn from_name(name: &str) -> Result<Self, String> {
+        match name.to_ascii_lowercase().as_str() {
+            "biome" | "biomes" => Ok(MapKind::Biome),
+            "material" | "materials" => Ok(MapKind::Material),
+            _ => Err(format!("Unknown map kind '{}'", name)),
+        }
+    }
+}
+
+/// World area a map covers: one pixel per `blocks_per_pixel` columns.
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct MapArea {
+    /// World X and Z of the first pixel's column
+    pub x_min: i32,
+    pub z_min: i32,
+    /// Pixels along X and Z
+    pub width: u32,
+    pub depth: u32,
+    pub blocks_per_pixel: u32,
+}
+
+impl MapArea {
+    /// World column a pixel samples.
+    fn column(&self, px: u32, pz: u32) -> (i32, i32) {
+        let step = self.blocks_per_pixel as i32;
+        (self.x_min + px as i32 * step, self.z_min + pz as i32 * step)
+    }
+}
+
+/// One named color in a map image.
+#[derive(Debug, Clone, Serialize)]
+pub struct MapLegendEntry {
+    pub name: String,
+    /// `#RRGGBB`
+    pub color: String,
+    pub pixels: u64,
+    /// Share of the map covered, 0–100
+    pub area_percent: f64,
+}
+
+/// One PNG of a tiled map.
+#[derive(Debug, Clone, Serialize)]
+pub struct MapTile {
+    pub file: String,
+    /// Tile position in the grid of tiles
+    pub column: u32,
+    pub row: u32,
+    /// First pixel of the tile in the whole map
+    pub x_offset: u32,
+    pub z_offset: u32,
+    pub width: u32,
+    pub depth: u32,
+}
+
+/// The legend JSON written next to a map's images.
+#[derive(Debug, Clone, Serialize)]
+pub struct MapLegend {
+    pub kind: MapKind,
+    #[serde(flatten)]
+    pub area: MapArea,
+    /// Largest tile edge in pixels
+    pub tile_size: u32,
+    pub tiles: Vec<MapTile>,
+    /// Entries in order of first appearance, scanning rows from the first
+    /// pixel
+    pub entries: Vec<MapLegendEntry>,
+}
+
+/// A grid of named categories, one per pixel.
+#[derive(Debug, Clone)]
+pub struct CategoryMap {
+    pub kind: MapKind,
+    pub area: MapArea,
+    /// Row-major indices into `names`, Z as the row axis
+    pub cells: Vec<u32>,
+    pub names: Vec<String>,
+}
+
+impl CategoryMap {
+    fn sample(kind: MapKind, area: MapArea, mut name_at: impl FnMut(i32, i32) -> String) -> Self {
+        let mut cells = Vec::with_capacity(area.width as usize * area.depth as usize);
+        let mut names: Vec<String> = Vec::new();
+        let mut ids: HashMap<String, u32> = HashMap::new();
+        for pz in 0..area.depth {
+            for px in 0..area.width {
+                let (x, z) = area.column(px, pz);
+                let name = name_at(x, z);
+                let id = *ids.entry(name).or_insert_with_key(|name| {
+                    names.push(name.clone());
+                    (names.len() - 1) as u32
+                });
+                cells.push(id);
+            }
+        }
+        CategoryMap {
+            kind,
+            area,
+            cells,
+            names,
+        }
+    }
+
+    /// Biome per column as the world structure selects it, without blending.
+    pub fn biomes(selector: &BiomeSelector, area: MapArea) -> Self {
+        Self::sample(MapKind::Biome, area, |x, z| {
+            let index = selector
+                .range_biome(selector.value_at(x as f64, z as f64))
+                .unwrap_or(selector.default_index);
+            selector.names[index].clone()
+        })
+    }
+
+    /// Visible material per column over `y_min..y_max`: the fluid above the
+    /// surface when there is one, else the surface block, else `Empty`.
+    pub fn materials(generator: &ChunkGenerator, area: MapArea, y_min: i32, y_max: i32) -> Self {
+        Self::sample(MapKind::Material, area, |x, z| {
+            let surface = generator.surface(x, z, y_min, y_max);
+            surface
+                .fluid
+                .or(surface.surface_block)
+                .unwrap_or_else(|| EMPTY_BLOCK.to_string())
+        })
+    }
+
+    pub fn color(&self, name: &str) -> [u8; 3] {
+        match self.kind {
+            MapKind::Biome => biome_color(name),
+            MapKind::Material if name == EMPTY_BLOCK => [0, 0, 0],
+            MapKind::Material => block_color(name),
+        }
+    }
+
+    pub fn legend_entries(&self) -> Vec<MapLegendEntry> {
+        let mut counts = vec![0u64; self.names.len()];
+        for &cell in &self.cells {
+            counts[cell as usize] += 1;
+        }
+        let total = self.cells.len().max(1) as f64;
+        self.names
+            .iter()
+            .zip(counts)
+            .map(|(name, pixels)| {
+                let [r, g, b] = self.color(name);
+                MapLegendEntry {
+                    name: name.clone(),
+                    color: format!("#{:02x}{:02x}{:02x}", r, g, b),
+                    pixels,
+                    area_percent: pixels as f64 / total * 100.0,
+                }
+            })
+            .collect()
+    }
+
+    /// RGB pixels of a rectangle of the map.
+    pub fn render(&self, x_offset: u32, z_offset: u32, width: u32, depth: u32) -> Vec<u8> {
+        let colors: Vec<[u8; 3]> = self.names.iter().map(|name| self.color(name)).collect();
+        let mut rgb = Vec::with_capacity(width as usize * depth as usize * 3);
+        for pz in z_offset..z_offset + depth {
+            let row = pz as usize * self.area.width as usize;
+            for px in x_offset..x_offset + width {
+                rgb.extend_from_slice(&colors[self.cells[row + px as usize] as usize]);
+            }
+        }
+        rgb
+    }
+
+    /// Write the map as PNG tiles of at most `tile_size` pixels a side plus
+    /// `<base>.json` with the legend. A map that fits one tile is written as
+    /// `<base>.png`, otherwise tiles are `<base>_<column>_<row>.png`.
+    /// Returns every path written.
+    pub fn write(&self, base: &Path, tile_size: u32) -> Result<Vec<PathBuf>, String> {
+        if let Some(parent) = base.parent().filter(|p| !p.as_os_str().is_empty()) {
+            fs::create_dir_all(parent).map_err(|e| format!("Failed to create directory: {}", e))?;
+        }
+        let stem = base
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .ok_or("Output path has no file name")?;
+        let columns = self.area.width.div_ceil(tile_size);
+        let rows = self.area.depth.div_ceil(tile_size);
+
+        let mut written = Vec::new();
+        let mut tiles = Vec::new();
+        for row in 0..rows {
+            for column in 0..columns {
+                let file = if columns * rows == 1 {
+                    format!("{}.png", stem)
+                } else {
+                    format!("{}_{}_{}.png", stem, column, row)
+                };
+                let tile = MapTile {
+                    file,
+                    column,
+                    row,
+                    x_offset: column * tile_size,
+                    z_offset: row * tile_size,
+                    width: tile_size.min(self.area.width - column * tile_size),
+                    depth: tile_size.min(self.area.depth - row * tile_size),
+                };
+                let rgb = self.render(tile.x_offset, tile.z_offset, tile.width, tile.depth);
+                let path = base.with_file_name(&tile.file);
+                fs::write(&path, encode_rgb_png(&rgb, tile.width, tile.depth)?)
+                    .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+                written.push(path);
+                tiles.push(tile);
+            }
+        }
+
+        let legend = MapLegend {
+            kind: self.kind,
+            area: self.area,
+            tile_size,
+            tiles,
+            entries: self.legend_entries(),
+        };
+        let path = base.with_file_name(format!("{}.json", stem));
+        let json = serde_json::to_string_pretty(&legend)
+            .map_err(|e| format!("Failed to serialize: {}", e))?;
+        fs::write(&path, json).map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+        written.push(path);
+        Ok(written)
+    }
+}
+
+fn encode_rgb_png(rgb: &[u8], width: u32, height: u32) -> Result<Vec<u8>, String> {
+    let mut bytes = Vec::new();
+    let mut encoder = png::Encoder::new(&mut bytes, width, height);
+    encoder.set_color(png::ColorType::Rgb);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder
+        .write_header()
+        .and_then(|mut writer| writer.write_image_data(rgb))
+        .map_err(|e| format!("Failed to encode PNG: {}", e))?;
+    Ok(bytes)
+}
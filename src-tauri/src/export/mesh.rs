@@ -0,0 +1,481 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde_json::json;
+
+use crate::worldgen::chunk::ChunkGenerator;
+
+use super::colors::{block_color, UNKNOWN_BLOCK_COLOR};
+
+/// Terrain density sampled at integer block positions.
+#[derive(Debug, Clone)]
+pub struct DensityVolume {
+    /// World position of the first sample
+    pub min: [i32; 3],
+    /// Samples along X, Y and Z
+    pub size: [usize; 3],
+    /// Indexed `(z * size_y + y) * size_x + x`
+    pub values: Vec<f32>,
+}
+
+impl DensityVolume {
+    /// Sample a generator's terrain density, one column at a time.
+    pub fn sample(generator: &ChunkGenerator, min: [i32; 3], size: [usize; 3]) -> Self {
+        let mut volume = DensityVolume {
+            min,
+            size,
+            values: vec![0.0; size[0] * size[1] * size[2]],
+        };
+        let y_max = min[1] + size[1] as i32;
+        for z in 0..size[2] {
+            for x in 0..size[0] {
+                let column =
+                    generator.density_column(min[0] + x as i32, min[2] + z as i32, min[1], y_max);
+                for (y, density) in column.into_iter().enumerate() {
+                    let i = volume.index([x, y, z]);
+                    volume.values[i] = density as f32;
+                }
+            }
+        }
+        volume
+    }
+
+    fn index(&self, [x, y, z]: [usize; 3]) -> usize {
+        (z * self.size[1] + y) * self.size[0] + x
+    }
+
+    fn solid(&self, p: [usize; 3]) -> bool {
+        self.values[self.index(p)] > 0.0
+    }
+
+    /// The volume wrapped in one layer of air on every face, so terrain
+    /// cut by the region boundary still closes off.
+    fn padded(&self) -> Self {
+        let size = self.size.map(|n| n + 2);
+        let mut padded = DensityVolume {
+            min: self.min.map(|v| v - 1),
+            size,
+            values: vec![-1.0; size[0] * size[1] * size[2]],
+        };
+        for z in 0..self.size[2] {
+            for y in 0..self.size[1] {
+                for x in 0..self.size[0] {
+                    let i = padded.index([x + 1, y + 1, z + 1]);
+                    padded.values[i] = self.values[self.index([x, y, z])];
+                }
+            }
+        }
+        padded
+    }
+}
+
+fn offset(min: [i32; 3], p: [usize; 3]) -> [i32; 3] {
+    [0, 1, 2].map(|i| min[i] + p[i] as i32)
+}
+
+/// An indexed triangle mesh in world block coordinates, Y up.
+#[derive(Debug, Clone, Default)]
+pub struct Mesh {
+    pub positions: Vec<[f32; 3]>,
+    /// Unit vertex normals pointing out of the terrain
+    pub normals: Vec<[f32; 3]>,
+    /// sRGB vertex colors; empty unless colored
+    pub colors: Vec<[u8; 3]>,
+    /// Triangles, counter-clockwise seen from outside the terrain
+    pub indices: Vec<u32>,
+}
+
+/// Cell corner offsets; bit 0 is X, bit 1 is Y and bit 2 is Z.
+const CORNERS: [[usize; 3]; 8] = [
+    [0, 0, 0],
+    [1, 0, 0],
+    [0, 1, 0],
+    [1, 1, 0],
+    [0, 0, 1],
+    [1, 0, 1],
+    [0, 1, 1],
+    [1, 1, 1],
+];
+
+/// Corner pairs joined by the twelve cell edges.
+const EDGES: [(usize, usize); 12] = [
+    (0, 1),
+    (2, 3),
+    (4, 5),
+    (6, 7),
+    (0, 2),
+    (1, 3),
+    (4, 6),
+    (5, 7),
+    (0, 4),
+    (1, 5),
+    (2, 6),
+    (3, 7),
+];
+
+/// Extract the density = 0 isosurface with surface nets: one vertex per cell
+/// the surface crosses, placed at the mean of its edge crossings, and one
+/// quad per sample edge with a sign change.
+///
+/// The volume is treated as surrounded by air, so the mesh is closed: where
+/// terrain reaches the region boundary it is capped flat on the boundary.
+pub fn surface_nets(volume: &DensityVolume) -> Mesh {
+    let mut mesh = Mesh::default();
+    if volume.size.iter().any(|&n| n < 2) {
+        return mesh;
+    }
+    let (low, high) = (
+        volume.min.map(|v| v as f32),
+        [0, 1, 2].map(|i| (volume.min[i] + volume.size[i] as i32 - 1) as f32),
+    );
+    let volume = &volume.padded();
+    let size = volume.size;
+    let cells = size.map(|n| n - 1);
+    let cell_index = |[x, y, z]: [usize; 3]| (z * cells[1] + y) * cells[0] + x;
+    let mut cell_vertex = vec![u32::MAX; cells[0] * cells[1] * cells[2]];
+
+    for z in 0..cells[2] {
+        for y in 0..cells[1] {
+            for x in 0..cells[0] {
+                let corner = |i: usize| [x + CORNERS[i][0], y + CORNERS[i][1], z + CORNERS[i][2]];
+                let d: [f32; 8] = std::array::from_fn(|i| volume.values[volume.index(corner(i))]);
+                let inside = d.iter().filter(|v| **v > 0.0).count();
+                if inside == 0 || inside == 8 {
+                    continue;
+                }
+                let mut sum = [0.0f32; 3];
+                let mut crossings = 0.0;
+                for (a, b) in EDGES {
+                    if (d[a] > 0.0) == (d[b] > 0.0) {
+                        continue;
+                    }
+                    let t = d[a] / (d[a] - d[b]);
+                    for axis in 0..3 {
+                        let (ca, cb) = (CORNERS[a][axis] as f32, CORNERS[b][axis] as f32);
+                        sum[axis] += ca + t * (cb - ca);
+                    }
+                    crossings += 1.0;
+                }
+                let origin = offset(volume.min, [x, y, z]);
+                cell_vertex[cell_index([x, y, z])] = mesh.positions.len() as u32;
+                mesh.positions.push(
+                    [0, 1, 2]
+                        .map(|i| (origin[i] as f32 + sum[i] / crossings).clamp(low[i], high[i])),
+                );
+            }
+        }
+    }
+
+    for z in 0..size[2] {
+        for y in 0..size[1] {
+            for x in 0..size[0] {
+                let p = [x, y, z];
+                for axis in 0..3 {
+                    let (b, c) = ((axis + 1) % 3, (axis + 2) % 3);
+                    // The four cells around the edge must all exist
+                    if p[axis] + 1 >= size[axis]
+                        || p[b] == 0
+                        || p[c] == 0
+                        || p[b] + 1 >= size[b]
+                        || p[c] + 1 >= size[c]
+                    {
+                        continue;
+                    }
+                    let mut q = p;
+                    q[axis] += 1;
+                    let outward = volume.solid(p);
+                    if outward == volume.solid(q) {
+                        continue;
+                    }
+                    let cell = |db: usize, dc: usize| {
+                        let mut cell = p;
+                        cell[b] -= db;
+                        cell[c] -= dc;
+                        cell_vertex[cell_index(cell)]
+                    };
+                    let (v00, v10, v11, v01) = (cell(1, 1), cell(0, 1), cell(0, 0), cell(1, 0));
+                    // (axis, b, c) is right-handed, so this winding faces +axis
+                    if outward {
+                        mesh.indices.extend([v00, v10, v11, v00, v11, v01]);
+                    } else {
+                        mesh.indices.extend([v00, v11, v10, v00, v01, v11]);
+                    }
+                }
+            }
+        }
+    }
+
+    mesh.compute_normals();
+    mesh
+}
+
+fn sub(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+fn cross(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
+}
+
+fn normalize(v: [f32; 3]) -> [f32; 3] {
+    let length = (v[0] * v[0] + v[1] * v[1] + v[2] * v[2]).sqrt();
+    if length > 0.0 {
+        v.map(|c| c / length)
+    } else {
+        [0.0, 1.0, 0.0]
+    }
+}
+
+/// Linear-light value of an sRGB channel, as glTF vertex colors expect.
+fn srgb_to_linear(c: u8) -> f32 {
+    let c = c as f32 / 255.0;
+    if c <= 0.04045 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+/// Output formats for a terrain mesh.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MeshFormat {
+    /// Wavefront OBJ, with vertex colors as extra `v` components
+    Obj,
+    /// Binary glTF 2.0
+    Glb,
+    /// Binary STL; colors are dropped
+    Stl,
+}
+
+impl MeshFormat {
+    pub fn from_name(name: &str) -> Result<Self, String> {
+        match name.to_ascii_lowercase().as_str() {
+            "obj" => Ok(MeshFormat::Obj),
+            "glb" | "gltf" => Ok(MeshFormat::Glb),
+            "stl" => Ok(MeshFormat::Stl),
+            _ => Err(format!("Unknown mesh format '{}'", name)),
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            MeshFormat::Obj => "obj",
+            MeshFormat::Glb => "glb",
+            MeshFormat::Stl => "stl",
+        }
+    }
+}
+
+impl Mesh {
+    pub fn triangle_count(&self) -> usize {
+        self.indices.len() / 3
+    }
+
+    fn triangles(&self) -> impl Iterator<Item = [[f32; 3]; 3]> + '_ {
+        self.indices
+            .chunks(3)
+            .map(|t| [0, 1, 2].map(|k| self.positions[t[k] as usize]))
+    }
+
+    /// Area-weighted vertex normals from the triangles.
+    fn compute_normals(&mut self) {
+        let mut normals = vec![[0.0f32; 3]; self.positions.len()];
+        for (t, [a, b, c]) in self.indices.chunks(3).zip(self.triangles()) {
+            let n = cross(sub(b, a), sub(c, a));
+            for &i in t {
+                for axis in 0..3 {
+                    normals[i as usize][axis] += n[axis];
+                }
+            }
+        }
+        self.normals = normals.into_iter().map(normalize).collect();
+    }
+
+    /// Color each vertex by the generated block at the nearest solid corner
+    /// of its cell.
+    pub fn colorize(&mut self, generator: &ChunkGenerator, volume: &DensityVolume) {
+        let y_max = volume.min[1] + volume.size[1] as i32;
+        let mut columns: HashMap<(i32, i32), Vec<Option<&str>>> = HashMap::new();
+        let mut colors = Vec::with_capacity(self.positions.len());
+        for position in &self.positions {
+            let cell: [usize; 3] = std::array::from_fn(|i| {
+                let local = (position[i] - volume.min[i] as f32).floor().max(0.0) as usize;
+                local.min(volume.size[i].saturating_sub(2))
+            });
+            let nearest = CORNERS
+                .iter()
+                .map(|c| [cell[0] + c[0], cell[1] + c[1], cell[2] + c[2]])
+                .filter(|&p| volume.solid(p))
+                .map(|p| offset(volume.min, p))
+                .min_by(|a, b| {
+                    let distance = |p: &[i32; 3]| {
+                        (0..3)
+                            .map(|i| (p[i] as f32 - position[i]).powi(2))
+                            .sum::<f32>()
+                    };
+                    distance(a).total_cmp(&distance(b))
+                });
+            let color = nearest
+                .and_then(|[x, y, z]| {
+                    let column = columns
+                        .entry((x, z))
+                        .or_insert_with(|| generator.column_blocks(x, z, volume.min[1], y_max));
+                    column[(y - volume.min[1]) as usize]
+                })
+                .map(block_color)
+                .unwrap_or(UNKNOWN_BLOCK_COLOR);
+            colors.push(color);
+        }
+        self.colors = colors;
+    }
+
+    pub fn encode(&self, format: MeshFormat) -> Vec<u8> {
+        match format {
+            MeshFormat::Obj => self.to_obj().into_bytes(),
+            MeshFormat::Glb => self.to_glb(),
+            MeshFormat::Stl => self.to_stl(),
+        }
+    }
+
+    /// Write the mesh to `<base>.<ext>`.
+    pub fn write(&self, base: &Path, format: MeshFormat) -> Result<PathBuf, String> {
+        if let Some(parent) = base.parent().filter(|p| !p.as_os_str().is_empty()) {
+            fs::create_dir_all(parent).map_err(|e| format!("Failed to create directory: {}", e))?;
+        }
+        let path = base.with_extension(format.extension());
+        fs::write(&path, self.encode(format))
+            .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+        Ok(path)
+    }
+
+    fn to_obj(&self) -> String {
+        let mut out = format!(
+            "# TerraNova terrain mesh\n# {} vertices, {} triangles\n",
+            self.positions.len(),
+            self.triangle_count()
+        );
+        for (i, [x, y, z]) in self.positions.iter().enumerate() {
+            match self.colors.get(i) {
+                Some(rgb) => {
+                    let [r, g, b] = rgb.map(|c| c as f32 / 255.0);
+                    out.push_str(&format!("v {} {} {} {:.4} {:.4} {:.4}\n", x, y, z, r, g, b));
+                }
+                None => out.push_str(&format!("v {} {} {}\n", x, y, z)),
+            }
+        }
+        for [x, y, z] in &self.normals {
+            out.push_str(&format!("vn {} {} {}\n", x, y, z));
+        }
+        for t in self.indices.chunks(3) {
+            let [a, b, c] = [t[0] + 1, t[1] + 1, t[2] + 1];
+            out.push_str(&format!("f {a}//{a} {b}//{b} {c}//{c}\n"));
+        }
+        out
+    }
+
+    fn to_stl(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(84 + self.triangle_count() * 50);
+        let mut header = [0u8; 80];
+        let title = b"TerraNova terrain mesh";
+        header[..title.len()].copy_from_slice(title);
+        out.extend_from_slice(&header);
+        out.extend_from_slice(&(self.triangle_count() as u32).to_le_bytes());
+        for [a, b, c] in self.triangles() {
+            let normal = normalize(cross(sub(b, a), sub(c, a)));
+            for v in [normal, a, b, c] {
+                v.iter()
+                    .for_each(|f| out.extend_from_slice(&f.to_le_bytes()));
+            }
+            out.extend_from_slice(&0u16.to_le_bytes());
+        }
+        out
+    }
+
+    fn to_glb(&self) -> Vec<u8> {
+        const ARRAY_BUFFER: u32 = 34962;
+        const ELEMENT_ARRAY_BUFFER: u32 = 34963;
+        const FLOAT: u32 = 5126;
+        const UNSIGNED_INT: u32 = 5125;
+
+        let mut bin: Vec<u8> = Vec::new();
+        let mut views = Vec::new();
+        let mut accessors = Vec::new();
+        let mut push_vec3 = |bin: &mut Vec<u8>, data: &[[f32; 3]], bounds: bool| {
+            let offset = bin.len();
+            data.iter()
+                .flatten()
+                .for_each(|f| bin.extend_from_slice(&f.to_le_bytes()));
+            views.push(json!({
+                "buffer": 0, "byteOffset": offset, "byteLength": data.len() * 12,
+                "target": ARRAY_BUFFER
+            }));
+            let mut accessor = json!({
+                "bufferView": views.len() - 1, "componentType": FLOAT,
+                "count": data.len(), "type": "VEC3"
+            });
+            if bounds {
+                let fold = |init: f32, pick: fn(f32, f32) -> f32| {
+                    [0, 1, 2].map(|i| data.iter().map(|v| v[i]).fold(init, pick))
+                };
+                accessor["min"] = json!(fold(f32::INFINITY, f32::min));
+                accessor["max"] = json!(fold(f32::NEG_INFINITY, f32::max));
+            }
+            accessors.push(accessor);
+            accessors.len() - 1
+        };
+
+        let mut attributes = json!({
+            "POSITION": push_vec3(&mut bin, &self.positions, true),
+            "NORMAL": push_vec3(&mut bin, &self.normals, false),
+        });
+        if !self.colors.is_empty() {
+            let linear: Vec<[f32; 3]> = self.colors.iter().map(|c| c.map(srgb_to_linear)).collect();
+            attributes["COLOR_0"] = json!(push_vec3(&mut bin, &linear, false));
+        }
+        let offset = bin.len();
+        self.indices
+            .iter()
+            .for_each(|i| bin.extend_from_slice(&i.to_le_bytes()));
+        views.push(json!({
+            "buffer": 0, "byteOffset": offset, "byteLength": self.indices.len() * 4,
+            "target": ELEMENT_ARRAY_BUFFER
+        }));
+        accessors.push(json!({
+            "bufferView": views.len() - 1, "componentType": UNSIGNED_INT,
+            "count": self.indices.len(), "type": "SCALAR"
+        }));
+
+        let document = json!({
+            "asset": { "version": "2.0", "generator": "TerraNova" },
+            "scene": 0,
+            "scenes": [{ "nodes": [0] }],
+            "nodes": [{ "mesh": 0, "name": "Terrain" }],
+            "meshes": [{ "primitives": [{
+                "attributes": attributes, "indices": accessors.len() - 1, "mode": 4
+            }] }],
+            "buffers": [{ "byteLength": bin.len() }],
+            "bufferViews": views,
+            "accessors": accessors,
+        });
+
+        let mut json_chunk = document.to_string().into_bytes();
+        json_chunk.resize(json_chunk.len().next_multiple_of(4), b' ');
+        bin.resize(bin.len().next_multiple_of(4), 0);
+        let total = 12 + 8 + json_chunk.len() + 8 + bin.len();
+        let mut out = Vec::with_capacity(total);
+        out.extend_from_slice(b"glTF");
+        out.extend_from_slice(&2u32.to_le_bytes());
+        out.extend_from_slice(&(total as u32).to_le_bytes());
+        for (kind, chunk) in [(b"JSON", &json_chunk), (b"BIN\0", &bin)] {
+            out.extend_from_slice(&(chunk.len() as u32).to_le_bytes());
+            out.extend_from_slice(kind);
+            out.extend_from_slice(chunk);
+        }
+        out
+    }
+}
@@ -0,0 +1,11 @@
+pub mod colors;
+pub mod heightmap;
+pub mod map_image;
+pub mod mesh;
+pub mod nbt;
+pub mod schematic;
+pub mod volume;
+pub mod vox;
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,215 @@
+/// A big-endian NBT value, as used by schematic files. Compounds keep their
+/// entries in written order.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Tag {
+    Byte(i8),
+    Short(i16),
+    Int(i32),
+    Long(i64),
+    Float(f32),
+    Double(f64),
+    ByteArray(Vec<u8>),
+    String(String),
+    /// Elements share one tag type; an empty list is written as a list of End
+    List(Vec<Tag>),
+    Compound(Vec<(String, Tag)>),
+    IntArray(Vec<i32>),
+    LongArray(Vec<i64>),
+}
+
+impl Tag {
+    fn id(&self) -> u8 {
+        match self {
+            Tag::Byte(_) => 1,
+            Tag::Short(_) => 2,
+            Tag::Int(_) => 3,
+            Tag::Long(_) => 4,
+            Tag::Float(_) => 5,
+            Tag::Double(_) => 6,
+            Tag::ByteArray(_) => 7,
+            Tag::String(_) => 8,
+            Tag::List(_) => 9,
+            Tag::Compound(_) => 10,
+            Tag::IntArray(_) => 11,
+            Tag::LongArray(_) => 12,
+        }
+    }
+
+    /// Entry of a compound by name.
+    pub fn get(&self, key: &str) -> Option<&Tag> {
+        match self {
+            Tag::Compound(entries) => entries.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    /// Any integer tag widened to i64.
+    pub fn as_i64(&self) -> Option<i64> {
+        match *self {
+            Tag::Byte(v) => Some(v as i64),
+            Tag::Short(v) => Some(v as i64),
+            Tag::Int(v) => Some(v as i64),
+            Tag::Long(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    fn write_payload(&self, out: &mut Vec<u8>) {
+        match self {
+            Tag::Byte(v) => out.push(*v as u8),
+            Tag::Short(v) => out.extend_from_slice(&v.to_be_bytes()),
+            Tag::Int(v) => out.extend_from_slice(&v.to_be_bytes()),
+            Tag::Long(v) => out.extend_from_slice(&v.to_be_bytes()),
+            Tag::Float(v) => out.extend_from_slice(&v.to_be_bytes()),
+            Tag::Double(v) => out.extend_from_slice(&v.to_be_bytes()),
+            Tag::ByteArray(v) => {
+                out.extend_from_slice(&(v.len() as i32).to_be_bytes());
+                out.extend_from_slice(v);
+            }
+            Tag::String(v) => write_string(out, v),
+            Tag::List(items) => {
+                out.push(items.first().map_or(0, Tag::id));
+                out.extend_from_slice(&(items.len() as i32).to_be_bytes());
+                for item in items {
+                    item.write_payload(out);
+                }
+            }
+            Tag::Compound(entries) => {
+                for (name, tag) in entries {
+                    write_named_tag(out, name, tag);
+                }
+                out.push(0);
+            }
+            Tag::IntArray(v) => {
+                out.extend_from_slice(&(v.len() as i32).to_be_bytes());
+                out.extend(v.iter().flat_map(|x| x.to_be_bytes()));
+            }
+            Tag::LongArray(v) => {
+                out.extend_from_slice(&(v.len() as i32).to_be_bytes());
+                out.extend(v.iter().flat_map(|x| x.to_be_bytes()));
+            }
+        }
+    }
+}
+
+fn write_string(out: &mut Vec<u8>, value: &str) {
+    out.extend_from_slice(&(value.len() as u16).to_be_bytes());
+    out.extend_from_slice(value.as_bytes());
+}
+
+fn write_named_tag(out: &mut Vec<u8>, name: &str, tag: &Tag) {
+    out.push(tag.id());
+    write_string(out, name);
+    tag.write_payload(out);
+}
+
+/// Encode a root tag with its name, uncompressed.
+pub fn write_root(name: &str, tag: &Tag) -> Vec<u8> {
+    let mut out = Vec::new();
+    write_named_tag(&mut out, name, tag);
+    out
+}
+
+/// Decode an uncompressed root tag and its name.
+pub fn read_root(bytes: &[u8]) -> Result<(String, Tag), String> {
+    let mut reader = Reader { bytes, at: 0 };
+    let id = reader.take::<1>()?[0];
+    let name = reader.string()?;
+    Ok((name, reader.payload(id, 0)?))
+}
+
+/// Deepest nesting accepted before a file is treated as malformed.
+const MAX_DEPTH: usize = 512;
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    at: usize,
+}
+
+impl Reader<'_> {
+    fn slice(&mut self, len: usize) -> Result<&[u8], String> {
+        let end = self
+            .at
+            .checked_add(len)
+            .filter(|&end| end <= self.bytes.len())
+            .ok_or("Unexpected end of NBT data")?;
+        let slice = &self.bytes[self.at..end];
+        self.at = end;
+        Ok(slice)
+    }
+
+    fn take<const N: usize>(&mut self) -> Result<[u8; N], String> {
+        Ok(self.slice(N)?.try_into().unwrap())
+    }
+
+    fn len(&mut self) -> Result<usize, String> {
+        let len = i32::from_be_bytes(self.take()?);
+        usize::try_from(len).map_err(|_| format!("Negative NBT length {}", len))
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        let len = u16::from_be_bytes(self.take()?) as usize;
+        Ok(String::from_utf8_lossy(self.slice(len)?).to_string())
+    }
+
+    fn payload(&mut self, id: u8, depth: usize) -> Result<Tag, String> {
+        if depth > MAX_DEPTH {
+            return Err("NBT nested too deeply".into());
+        }
+        Ok(match id {
+            1 => Tag::Byte(self.take::<1>()?[0] as i8),
+            2 => Tag::Short(i16::from_be_bytes(self.take()?)),
+            3 => Tag::Int(i32::from_be_bytes(self.take()?)),
+            4 => Tag::Long(i64::from_be_bytes(self.take()?)),
+            5 => Tag::Float(f32::from_be_bytes(self.take()?)),
+            6 => Tag::Double(f64::from_be_bytes(self.take()?)),
+            7 => {
+                let len = self.len()?;
+                Tag::ByteArray(self.slice(len)?.to_vec())
+            }
+            8 => Tag::String(self.string()?),
+            9 => {
+                let item = self.take::<1>()?[0];
+                let len = self.len()?;
+                let mut items = Vec::new();
+                for _ in 0..len {
+                    items.push(self.payload(item, depth + 1)?);
+                }
+                Tag::List(items)
+            }
+            10 => {
+                let mut entries = Vec::new();
+                loop {
+                    let item = self.take::<1>()?[0];
+                    if item == 0 {
+                        break;
+                    }
+                    let name = self.string()?;
+                    entries.push((name, self.payload(item, depth + 1)?));
+                }
+                Tag::Compound(entries)
+            }
+            11 => {
+                let len = self.len()?;
+                let bytes = self.slice(len.checked_mul(4).ok_or("NBT array too long")?)?;
+                Tag::IntArray(
+                    bytes
+                        .chunks(4)
+                        .map(|b| i32::from_be_bytes(b.try_into().unwrap()))
+                        .collect(),
+                )
+            }
+            12 => {
+                let len = self.len()?;
+                let bytes = self.slice(len.checked_mul(8).ok_or("NBT array too long")?)?;
+                Tag::LongArray(
+                    bytes
+                        .chunks(8)
+                        .map(|b| i64::from_be_bytes(b.try_into().unwrap()))
+                        .collect(),
+                )
+            }
+            _ => return Err(format!("Unknown NBT tag type {}", id)),
+        })
+    }
+}
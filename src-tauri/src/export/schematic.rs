@@ -0,0 +1,251 @@
+use std::collections::{BTreeSet, HashMap};
+use std::fs;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::Serialize;
+use serde_json::{json, Value};
+
+use crate::placement::prefabs::{block_bounds, Prefab, PrefabBlock};
+
+use super::nbt::{self, Tag};
+use super::volume::BlockVolume;
+
+/// Sponge schematic version written; versions 1 to 3 are read.
+const SCHEMATIC_VERSION: i32 = 2;
+
+/// Minecraft data version stamped on exports. Sponge readers require one
+/// even though the blocks are Hytale's.
+const DATA_VERSION: i32 = 3465;
+
+/// Namespace Hytale block names are written under, and stripped on import.
+const HYTALE_NAMESPACE: &str = "hytale:";
+
+/// Palette entries read as air.
+const AIR_BLOCKS: [&str; 5] = [
+    "minecraft:air",
+    "minecraft:cave_air",
+    "minecraft:void_air",
+    "air",
+    "hytale:Empty",
+];
+
+/// Encode a volume as a gzipped Sponge schematic (version 2), keeping its
+/// world position as the schematic offset.
+pub fn encode_schematic(volume: &BlockVolume) -> Result<Vec<u8>, String> {
+    if volume.size.iter().any(|&n| n > u16::MAX as usize) {
+        return Err(format!(
+            "Schematics hold at most {} blocks along each axis",
+            u16::MAX
+        ));
+    }
+    let palette = volume
+        .names
+        .iter()
+        .enumerate()
+        .map(|(id, name)| {
+            let key = match id {
+                0 => AIR_BLOCKS[0].to_string(),
+                _ => format!("{}{}", HYTALE_NAMESPACE, name),
+            };
+            (key, Tag::Int(id as i32))
+        })
+        .collect();
+    let mut data = Vec::with_capacity(volume.blocks.len());
+    for &id in &volume.blocks {
+        write_varint(&mut data, id);
+    }
+    let dimension = |n: usize| Tag::Short(n as u16 as i16);
+
+    let root = Tag::Compound(vec![
+        ("Version".into(), Tag::Int(SCHEMATIC_VERSION)),
+        ("DataVersion".into(), Tag::Int(DATA_VERSION)),
+        ("Width".into(), dimension(volume.size[0])),
+        ("Height".into(), dimension(volume.size[1])),
+        ("Length".into(), dimension(volume.size[2])),
+        ("Offset".into(), Tag::IntArray(volume.min.to_vec())),
+        ("PaletteMax".into(), Tag::Int(volume.names.len() as i32)),
+        ("Palette".into(), Tag::Compound(palette)),
+        ("BlockData".into(), Tag::ByteArray(data)),
+        ("BlockEntities".into(), Tag::List(Vec::new())),
+    ]);
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder
+        .write_all(&nbt::write_root("Schematic", &root))
+        .and_then(|_| encoder.finish())
+        .map_err(|e| format!("Failed to compress schematic: {}", e))
+}
+
+/// Write `<base>.schem`, returning its path.
+pub fn write_schematic(volume: &BlockVolume, base: &Path) -> Result<PathBuf, String> {
+    if let Some(parent) = base.parent().filter(|p| !p.as_os_str().is_empty()) {
+        fs::create_dir_all(parent).map_err(|e| format!("Failed to create directory: {}", e))?;
+    }
+    let path = base.with_extension("schem");
+    fs::write(&path, encode_schematic(volume)?)
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    Ok(path)
+}
+
+/// Decode a Sponge schematic of version 1 to 3, gzipped or not, into a
+/// volume positioned at the schematic's offset. Block states are kept in
+/// the names and the Hytale namespace is stripped.
+pub fn decode_schematic(bytes: &[u8]) -> Result<BlockVolume, String> {
+    let mut raw = Vec::new();
+    let bytes = if bytes.starts_with(&[0x1f, 0x8b]) {
+        GzDecoder::new(bytes)
+            .read_to_end(&mut raw)
+            .map_err(|e| format!("Failed to decompress schematic: {}", e))?;
+        &raw[..]
+    } else {
+        bytes
+    };
+    let (_, root) = nbt::read_root(bytes)?;
+    // Version 3 nests everything under a "Schematic" compound
+    let schematic = root.get("Schematic").unwrap_or(&root);
+    let int = |key: &str| schematic.get(key).and_then(Tag::as_i64);
+    let version = int("Version").ok_or("Not a Sponge schematic: missing Version")?;
+    if !(1..=3).contains(&version) {
+        return Err(format!("Unsupported schematic version {}", version));
+    }
+    let size = ["Width", "Height", "Length"].map(|key| int(key).unwrap_or(0) as u16 as usize);
+    let offset = match schematic.get("Offset") {
+        Some(Tag::IntArray(v)) if v.len() == 3 => [v[0], v[1], v[2]],
+        _ => [0; 3],
+    };
+    let blocks = match version {
+        3 => schematic.get("Blocks").ok_or("Schematic has no Blocks")?,
+        _ => schematic,
+    };
+    let data_key = if version == 3 { "Data" } else { "BlockData" };
+    let Some(Tag::Compound(palette)) = blocks.get("Palette") else {
+        return Err("Schematic has no block palette".into());
+    };
+    let Some(Tag::ByteArray(data)) = blocks.get(data_key) else {
+        return Err("Schematic has no block data".into());
+    };
+
+    let names: HashMap<i64, Option<&str>> = palette
+        .iter()
+        .filter_map(|(key, id)| {
+            let name = (!AIR_BLOCKS.contains(&key.as_str()))
+                .then(|| key.strip_prefix(HYTALE_NAMESPACE).unwrap_or(key));
+            Some((id.as_i64()?, name))
+        })
+        .collect();
+    let ids = read_varints(data)?;
+    let expected = size[0] * size[1] * size[2];
+    if ids.len() != expected {
+        return Err(format!(
+            "Schematic has {} blocks, expected {} for {}x{}x{}",
+            ids.len(),
+            expected,
+            size[0],
+            size[1],
+            size[2]
+        ));
+    }
+
+    let mut volume = BlockVolume::new(offset, size);
+    for (i, id) in ids.into_iter().enumerate() {
+        let name = names
+            .get(&(id as i64))
+            .ok_or_else(|| format!("Block id {} is missing from the palette", id))?;
+        if let Some(name) = name {
+            let x = i % size[0];
+            let z = i / size[0] % size[2];
+            let y = i / (size[0] * size[2]);
+            volume.set([x, y, z], name);
+        }
+    }
+    Ok(volume)
+}
+
+/// A schematic converted for placement previews.
+#[derive(Debug, Clone, Serialize)]
+pub struct SchematicImport {
+    /// Blocks along X, Y and Z
+    pub size: [usize; 3],
+    /// World position the schematic was saved at
+    pub offset: [i32; 3],
+    /// Distinct block names, sorted
+    pub block_types: Vec<String>,
+    /// A List position provider with the position of every block, relative
+    /// to the schematic's first corner
+    pub positions: Value,
+    /// The same blocks as a prop preview
+    pub prefab: Prefab,
+}
+
+impl SchematicImport {
+    pub fn from_volume(volume: &BlockVolume, path: &str) -> Self {
+        let [size_x, size_y, size_z] = volume.size;
+        let mut blocks = Vec::new();
+        for y in 0..size_y {
+            for z in 0..size_z {
+                for x in 0..size_x {
+                    let id = volume.blocks[volume.index([x, y, z])] as usize;
+                    if id != 0 {
+                        blocks.push(PrefabBlock {
+                            x: x as i32,
+                            y: y as i32,
+                            z: z as i32,
+                            name: volume.names[id].clone(),
+                            rotation: 0,
+                        });
+                    }
+                }
+            }
+        }
+        let positions: Vec<Value> = blocks
+            .iter()
+            .map(|b| json!({ "x": b.x, "y": b.y, "z": b.z }))
+            .collect();
+        let block_types: BTreeSet<&String> = blocks.iter().map(|b| &b.name).collect();
+
+        SchematicImport {
+            size: volume.size,
+            offset: volume.min,
+            block_types: block_types.into_iter().cloned().collect(),
+            positions: json!({ "Type": "List", "Positions": positions }),
+            prefab: Prefab {
+                path: path.to_string(),
+                bounds: block_bounds(&blocks),
+                blocks,
+            },
+        }
+    }
+}
+
+/// Unsigned LEB128, the block data encoding of Sponge schematics.
+fn write_varint(out: &mut Vec<u8>, mut value: u32) {
+    while value >= 0x80 {
+        out.push((value as u8 & 0x7f) | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+fn read_varints(bytes: &[u8]) -> Result<Vec<u32>, String> {
+    let mut values = Vec::new();
+    let (mut value, mut shift) = (0u32, 0);
+    for &byte in bytes {
+        if shift > 28 {
+            return Err("Block id in schematic data is too long".into());
+        }
+        value |= ((byte & 0x7f) as u32) << shift;
+        if byte & 0x80 == 0 {
+            values.push(value);
+            (value, shift) = (0, 0);
+        } else {
+            shift += 7;
+        }
+    }
+    if shift != 0 {
+        return Err("Schematic block data ends inside a block id".into());
+    }
+    Ok(values)
+}
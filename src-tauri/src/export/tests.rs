@@ -0,0 +1,545 @@
+use std::collections::HashMap;
+use std::fs;
+
+use serde_json::json;
+
+use super::colors::{biome_color, block_color};
+use super::heightmap::{Heightmap, HeightmapFormat};
+use super::map_image::{CategoryMap, MapArea, MapKind};
+use super::mesh::{surface_nets, DensityVolume, Mesh, MeshFormat};
+use super::nbt::{self, Tag};
+use super::schematic::{decode_schematic, encode_schematic, SchematicImport};
+use super::volume::BlockVolume;
+use super::vox::VoxFile;
+use crate::bridge::types::ChunkDataResponse;
+use crate::io::asset_pack::AssetPack;
+use crate::worldgen::biome_map::BiomeSelector;
+use crate::worldgen::chunk::ChunkGenerator;
+use crate::worldgen::pack::WorldgenPack;
+
+fn flat_pack() -> WorldgenPack {
+    let biome = json!({
+        "Name": "Flat",
+        "Terrain": { "Type": "DAOTerrain", "Density": { "Type": "Constant", "Value": 1.0 } },
+        "MaterialProvider": { "Type": "Constant", "Material": "Rock_Stone" }
+    });
+    WorldgenPack::from_asset_pack(&AssetPack {
+        path: String::new(),
+        assets: HashMap::from([("HytaleGenerator/Biomes/Flat.json".to_string(), biome)]),
+    })
+}
+
+fn sample_heightmap() -> Heightmap {
+    Heightmap {
+        x_min: -2,
+        z_min: 5,
+        width: 3,
+        depth: 2,
+        y_min: 0,
+        y_max: 11,
+        heights: vec![0, 5, 10, 10, 0, 2],
+    }
+}
+
+#[test]
+fn heightmap_spans_chunks_and_scales_to_u16() {
+    let pack = flat_pack();
+    let generator = ChunkGenerator::for_pack(&pack, None, None, false).unwrap();
+    let heightmap = Heightmap::generate(&generator, -40, 20, 50, 30, 0, 16);
+    assert_eq!(heightmap.heights.len(), 50 * 30);
+    assert!(heightmap.heights.iter().all(|&h| h == 15));
+    assert!(heightmap.samples().iter().all(|&s| s == u16::MAX));
+
+    let sample = sample_heightmap();
+    assert_eq!(sample.samples(), vec![0, 32768, 65535, 65535, 0, 13107]);
+    let sidecar = sample.sidecar(Vec::new());
+    assert_eq!(sidecar.world_min, [-2, 0, 5]);
+    assert_eq!(sidecar.world_max, [0, 10, 6]);
+    assert_eq!((sidecar.min_height, sidecar.max_height), (0, 10));
+    assert!((sidecar.vertical_scale * 65535.0 - 10.0).abs() < 1e-9);
+}
+
+#[test]
+fn heightmap_writes_png_raw_tiff_and_sidecar() {
+    let heightmap = sample_heightmap();
+    let samples = heightmap.samples();
+    let dir = std::env::temp_dir().join(format!("terranova-heightmap-{}", std::process::id()));
+    let formats = [
+        HeightmapFormat::Png,
+        HeightmapFormat::Raw,
+        HeightmapFormat::Tiff,
+    ];
+    let written = heightmap.write(&dir.join("terrain"), &formats).unwrap();
+    let names: Vec<String> = written
+        .iter()
+        .map(|p| p.file_name().unwrap().to_string_lossy().to_string())
+        .collect();
+    assert_eq!(
+        names,
+        vec!["terrain.png", "terrain.raw", "terrain.tif", "terrain.json"]
+    );
+
+    let raw = fs::read(dir.join("terrain.raw")).unwrap();
+    let decoded: Vec<u16> = raw
+        .chunks(2)
+        .map(|b| u16::from_le_bytes([b[0], b[1]]))
+        .collect();
+    assert_eq!(decoded, samples);
+
+    let decoder = png::Decoder::new(fs::File::open(dir.join("terrain.png")).unwrap());
+    let mut reader = decoder.read_info().unwrap();
+    let mut buf = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut buf).unwrap();
+    assert_eq!((info.width, info.height), (3, 2));
+    assert_eq!(info.bit_depth, png::BitDepth::Sixteen);
+    let pixels: Vec<u16> = buf[..info.buffer_size()]
+        .chunks(2)
+        .map(|b| u16::from_be_bytes([b[0], b[1]]))
+        .collect();
+    assert_eq!(pixels, samples);
+
+    let tiff = fs::read(dir.join("terrain.tif")).unwrap();
+    assert_eq!(&tiff[..4], b"II\x2a\x00");
+    let entries = u16::from_le_bytes([tiff[8], tiff[9]]) as usize;
+    let tag = |i: usize| {
+        let at = 10 + i * 12;
+        let value = u32::from_le_bytes([tiff[at + 8], tiff[at + 9], tiff[at + 10], tiff[at + 11]]);
+        (u16::from_le_bytes([tiff[at], tiff[at + 1]]), value)
+    };
+    let tags: HashMap<u16, u32> = (0..entries).map(tag).collect();
+    assert_eq!((tags[&256], tags[&257], tags[&258]), (3, 2, 16));
+    let offset = tags[&273] as usize;
+    assert_eq!(tags[&279] as usize, samples.len() * 2);
+    assert_eq!(&tiff[offset..], &raw[..]);
+
+    let sidecar: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(dir.join("terrain.json")).unwrap()).unwrap();
+    assert_eq!(
+        sidecar["files"],
+        json!(["terrain.png", "terrain.raw", "terrain.tif"])
+    );
+    assert_eq!(sidecar["world_min"], json!([-2, 0, 5]));
+
+    fs::remove_dir_all(&dir).unwrap();
+    assert!(HeightmapFormat::from_name("exr").is_err());
+}
+
+/// Radius-5 ball centred on the origin.
+fn sphere() -> DensityVolume {
+    ball_in([-8, -8, -8], [17, 17, 17])
+}
+
+/// Density of a radius-5 ball centred on the origin, sampled over a region.
+fn ball_in(min: [i32; 3], size: [usize; 3]) -> DensityVolume {
+    let mut values = Vec::new();
+    for z in 0..size[2] as i32 {
+        for y in 0..size[1] as i32 {
+            for x in 0..size[0] as i32 {
+                let [x, y, z] = [min[0] + x, min[1] + y, min[2] + z];
+                values.push(5.0 - ((x * x + y * y + z * z) as f32).sqrt());
+            }
+        }
+    }
+    DensityVolume { min, size, values }
+}
+
+#[test]
+fn surface_nets_builds_closed_outward_mesh() {
+    let mesh = surface_nets(&sphere());
+    assert!(mesh.triangle_count() > 100);
+    assert_eq!(mesh.normals.len(), mesh.positions.len());
+    for p in &mesh.positions {
+        let r = (p[0] * p[0] + p[1] * p[1] + p[2] * p[2]).sqrt();
+        assert!((4.0..6.0).contains(&r), "vertex at radius {}", r);
+    }
+    assert_closed_outward(&mesh, [0.0, 0.0, 0.0]);
+
+    // The upper half only: the cut through the middle is capped on the
+    // region boundary
+    let mesh = surface_nets(&ball_in([-8, 0, -8], [17, 9, 17]));
+    assert!(mesh.positions.iter().all(|p| p[1] >= 0.0));
+    assert!(mesh.positions.iter().filter(|p| p[1] == 0.0).count() > 20);
+    assert_closed_outward(&mesh, [0.0, 1.0, 0.0]);
+}
+
+/// Every edge is shared by exactly two triangles, in opposite directions,
+/// and every triangle faces away from `inside`.
+fn assert_closed_outward(mesh: &Mesh, inside: [f32; 3]) {
+    let mut edges: HashMap<(u32, u32), i32> = HashMap::new();
+    for t in mesh.indices.chunks(3) {
+        for (a, b) in [(t[0], t[1]), (t[1], t[2]), (t[2], t[0])] {
+            *edges.entry((a, b)).or_insert(0) += 1;
+        }
+        let [a, b, c] = [0, 1, 2].map(|k| mesh.positions[t[k] as usize]);
+        let (u, v) = (
+            [0, 1, 2].map(|i| b[i] - a[i]),
+            [0, 1, 2].map(|i| c[i] - a[i]),
+        );
+        let normal = [
+            u[1] * v[2] - u[2] * v[1],
+            u[2] * v[0] - u[0] * v[2],
+            u[0] * v[1] - u[1] * v[0],
+        ];
+        let outward: f32 = (0..3)
+            .map(|i| normal[i] * (a[i] + b[i] + c[i] - 3.0 * inside[i]))
+            .sum();
+        assert!(outward > 0.0);
+    }
+    assert!(edges
+        .iter()
+        .all(|(&(a, b), &n)| n == 1 && edges.get(&(b, a)) == Some(&1)));
+}
+
+#[test]
+fn mesh_formats_round_trip_counts() {
+    let mut mesh = surface_nets(&sphere());
+    mesh.colors = vec![[255, 128, 0]; mesh.positions.len()];
+    let (vertices, triangles) = (mesh.positions.len(), mesh.triangle_count());
+
+    let obj = String::from_utf8(mesh.encode(MeshFormat::Obj)).unwrap();
+    assert_eq!(
+        obj.lines().filter(|l| l.starts_with("v ")).count(),
+        vertices
+    );
+    assert_eq!(
+        obj.lines().filter(|l| l.starts_with("f ")).count(),
+        triangles
+    );
+    assert!(obj.contains(" 1.0000 0.5020 0.0000\n"));
+
+    let stl = mesh.encode(MeshFormat::Stl);
+    assert_eq!(
+        u32::from_le_bytes(stl[80..84].try_into().unwrap()) as usize,
+        triangles
+    );
+    assert_eq!(stl.len(), 84 + triangles * 50);
+
+    let glb = mesh.encode(MeshFormat::Glb);
+    assert_eq!(&glb[..4], b"glTF");
+    assert_eq!(
+        u32::from_le_bytes(glb[8..12].try_into().unwrap()) as usize,
+        glb.len()
+    );
+    let json_len = u32::from_le_bytes(glb[12..16].try_into().unwrap()) as usize;
+    assert_eq!(&glb[16..20], b"JSON");
+    let document: serde_json::Value = serde_json::from_slice(&glb[20..20 + json_len]).unwrap();
+    let primitive = &document["meshes"][0]["primitives"][0];
+    let accessor = |name: &str| {
+        &document["accessors"][primitive["attributes"][name].as_u64().unwrap() as usize]
+    };
+    assert_eq!(accessor("POSITION")["count"], json!(vertices));
+    assert_eq!(accessor("COLOR_0")["count"], json!(vertices));
+    let indices = &document["accessors"][primitive["indices"].as_u64().unwrap() as usize];
+    assert_eq!(indices["count"], json!(triangles * 3));
+    let bin_len = u32::from_le_bytes(glb[20 + json_len..24 + json_len].try_into().unwrap());
+    assert_eq!(document["buffers"][0]["byteLength"], json!(bin_len));
+}
+
+#[test]
+fn mesh_colors_come_from_generated_blocks() {
+    let mut assets = HashMap::from([
+        (
+            "HytaleGenerator/Biomes/Hills.json".to_string(),
+            json!({
+                "Name": "Hills",
+                "Terrain": { "Type": "DAOTerrain", "Density": { "Type": "Constant", "Value": 1.0 } },
+                "MaterialProvider": { "Type": "Constant", "Material": "Soil_Grass" }
+            }),
+        ),
+        (
+            "HytaleGenerator/Biomes/Void.json".to_string(),
+            json!({
+                "Name": "Void",
+                "Terrain": { "Type": "DAOTerrain", "Density": { "Type": "Constant", "Value": -1.0 } },
+                "MaterialProvider": { "Type": "Constant", "Material": "Rock_Stone" }
+            }),
+        ),
+    ]);
+    assets.insert(
+        "HytaleGenerator/WorldStructures/Main.json".to_string(),
+        json!({
+            "Type": "NoiseRange",
+            "DefaultBiome": "Void",
+            "Density": { "Type": "SimplexNoise2D", "Seed": "hills", "Scale": 4 },
+            "Biomes": [{ "Biome": "Hills", "Min": 0.0, "Max": 1.0 }]
+        }),
+    );
+    let pack = WorldgenPack::from_asset_pack(&AssetPack {
+        path: String::new(),
+        assets,
+    });
+    let generator = ChunkGenerator::for_pack(&pack, None, None, false).unwrap();
+    let volume = DensityVolume::sample(&generator, [0, 0, 0], [48, 6, 48]);
+    let mut mesh = surface_nets(&volume);
+    assert!(mesh.triangle_count() > 0);
+    mesh.colorize(&generator, &volume);
+    assert_eq!(mesh.colors.len(), mesh.positions.len());
+    assert!(mesh.colors.iter().all(|c| *c == block_color("Soil_Grass")));
+}
+
+fn block_at(volume: &BlockVolume, position: [usize; 3]) -> &str {
+    &volume.names[volume.blocks[volume.index(position)] as usize]
+}
+
+#[test]
+fn block_volume_copies_generated_and_fetched_chunks() {
+    let pack = flat_pack();
+    let generator = ChunkGenerator::for_pack(&pack, None, None, false).unwrap();
+    let volume = BlockVolume::generate(&generator, [-3, 0, 30], [6, 4, 5]);
+    assert_eq!(volume.block_count(), 6 * 4 * 5);
+    assert_eq!(block_at(&volume, [5, 3, 4]), "Rock_Stone");
+    assert_eq!(volume.chunk_range(), (-1..=0, 0..=1));
+
+    // A 2x2 chunk with two layers: ids 1 and 2 on the bottom, air above
+    let chunk = ChunkDataResponse {
+        chunk_x: 1,
+        chunk_z: 0,
+        y_min: 10,
+        y_max: 12,
+        size_x: 2,
+        size_z: 2,
+        blocks: vec![1, 0, 2, 0, 7, 0, 1, 1],
+        heightmap: vec![10, 10, 10, 11],
+    };
+    let palette = HashMap::from([
+        ("0".to_string(), "Empty".to_string()),
+        ("1".to_string(), "Soil_Dirt".to_string()),
+        ("2".to_string(), "Rock_Stone".to_string()),
+    ]);
+    let mut fetched = BlockVolume::new([3, 11, 0], [4, 3, 2]);
+    fetched.add_chunk(&chunk, &palette);
+    assert_eq!(fetched.block_count(), 1);
+    assert_eq!(block_at(&fetched, [0, 0, 1]), "Soil_Dirt");
+
+    let mut fetched = BlockVolume::new([2, 10, 0], [2, 2, 2]);
+    fetched.add_chunk(&chunk, &palette);
+    assert_eq!(block_at(&fetched, [0, 0, 0]), "Soil_Dirt");
+    assert_eq!(block_at(&fetched, [1, 0, 0]), "Rock_Stone");
+    assert_eq!(block_at(&fetched, [0, 0, 1]), "Unknown_7");
+    assert_eq!(block_at(&fetched, [0, 1, 0]), "Empty");
+}
+
+/// Walk the top-level chunks of a `.vox` file.
+fn vox_chunks(bytes: &[u8]) -> Vec<(String, &[u8])> {
+    let mut chunks = Vec::new();
+    let mut at = 20;
+    while at < bytes.len() {
+        let len = u32::from_le_bytes(bytes[at + 4..at + 8].try_into().unwrap()) as usize;
+        let id = String::from_utf8_lossy(&bytes[at..at + 4]).to_string();
+        chunks.push((id, &bytes[at + 12..at + 12 + len]));
+        at += 12 + len;
+    }
+    chunks
+}
+
+#[test]
+fn vox_splits_models_at_256_and_maps_palette() {
+    let mut volume = BlockVolume::new([0, 0, 0], [300, 2, 3]);
+    volume.set([0, 0, 0], "Rock_Stone");
+    volume.set([299, 1, 0], "Soil_Grass");
+    volume.set([10, 0, 2], "Rock_Stone");
+    let vox = VoxFile::from_volume(&volume).unwrap();
+    assert_eq!((vox.model_count(), vox.voxel_count()), (2, 3));
+    assert_eq!(vox.palette[1].block, "Soil_Grass");
+    assert_eq!(vox.palette[1].color, block_color("Soil_Grass"));
+
+    let bytes = vox.encode();
+    assert_eq!(&bytes[..8], b"VOX \x96\0\0\0");
+    assert_eq!(&bytes[8..12], b"MAIN");
+    assert_eq!(
+        u32::from_le_bytes(bytes[16..20].try_into().unwrap()) as usize,
+        bytes.len() - 20
+    );
+    let chunks = vox_chunks(&bytes);
+    let sizes: Vec<&[u8]> = chunks
+        .iter()
+        .filter(|(id, _)| id == "SIZE")
+        .map(|(_, c)| *c)
+        .collect();
+    let dims = |c: &[u8]| -> Vec<i32> {
+        c.chunks(4)
+            .map(|b| i32::from_le_bytes(b.try_into().unwrap()))
+            .collect()
+    };
+    assert_eq!(dims(sizes[0]), vec![256, 3, 2]);
+    assert_eq!(dims(sizes[1]), vec![44, 3, 2]);
+
+    // World Z is mirrored onto vox Y and world Y becomes vox Z
+    let voxels: Vec<&[u8]> = chunks
+        .iter()
+        .filter(|(id, _)| id == "XYZI")
+        .map(|(_, c)| *c)
+        .collect();
+    assert_eq!(voxels[0], &[2, 0, 0, 0, 10, 0, 0, 1, 0, 2, 0, 1][..]);
+    assert_eq!(voxels[1], &[1, 0, 0, 0, 43, 2, 1, 2][..]);
+
+    let rgba = chunks.iter().find(|(id, _)| id == "RGBA").unwrap().1;
+    assert_eq!(rgba.len(), 1024);
+    let grass = block_color("Soil_Grass");
+    assert_eq!(&rgba[4..8], &[grass[0], grass[1], grass[2], 255]);
+    assert_eq!(chunks.iter().filter(|(id, _)| id == "nSHP").count(), 2);
+    let translations: Vec<String> = chunks
+        .iter()
+        .filter(|(id, c)| id == "nTRN" && c.windows(2).any(|w| w == b"_t"))
+        .map(|(_, c)| String::from_utf8_lossy(&c[c.len() - 7..]).to_string())
+        .collect();
+    assert_eq!(translations, vec!["128 1 1", "278 1 1"]);
+
+    let mut crowded = BlockVolume::new([0, 0, 0], [256, 1, 1]);
+    for x in 0..256 {
+        crowded.set([x, 0, 0], &format!("Block_{}", x));
+    }
+    assert!(VoxFile::from_volume(&crowded).is_err());
+}
+
+#[test]
+fn schematic_round_trips_volume_and_palette() {
+    let mut volume = BlockVolume::new([-5, 64, 12], [3, 2, 4]);
+    volume.set([0, 0, 0], "Rock_Stone");
+    volume.set([2, 1, 3], "Soil_Grass");
+    // Enough block types for multi-byte varint ids
+    for i in 0..200 {
+        volume.set([1, 0, 1], &format!("Block_{}", i));
+    }
+
+    let bytes = encode_schematic(&volume).unwrap();
+    assert_eq!(&bytes[..2], &[0x1f, 0x8b]);
+    let decoded = decode_schematic(&bytes).unwrap();
+    assert_eq!((decoded.min, decoded.size), ([-5, 64, 12], [3, 2, 4]));
+    assert_eq!(decoded.block_count(), 3);
+    assert_eq!(block_at(&decoded, [0, 0, 0]), "Rock_Stone");
+    assert_eq!(block_at(&decoded, [2, 1, 3]), "Soil_Grass");
+    assert_eq!(block_at(&decoded, [1, 0, 1]), "Block_199");
+
+    let import = SchematicImport::from_volume(&decoded, "arch.schem");
+    assert_eq!(import.offset, [-5, 64, 12]);
+    assert_eq!(
+        import.block_types,
+        vec!["Block_199", "Rock_Stone", "Soil_Grass"]
+    );
+    assert_eq!(import.positions["Type"], "List");
+    assert_eq!(
+        import.positions["Positions"][2],
+        json!({ "x": 2, "y": 1, "z": 3 })
+    );
+    assert_eq!(import.prefab.blocks.len(), 3);
+    assert_eq!(import.prefab.bounds.max, [2, 1, 3]);
+}
+
+#[test]
+fn schematic_reads_uncompressed_version_3() {
+    let palette = Tag::Compound(vec![
+        ("minecraft:air".into(), Tag::Int(0)),
+        ("minecraft:oak_log[axis=y]".into(), Tag::Int(1)),
+        ("hytale:Rock_Stone".into(), Tag::Int(2)),
+    ]);
+    let blocks = Tag::Compound(vec![
+        ("Palette".into(), palette),
+        ("Data".into(), Tag::ByteArray(vec![1, 0, 0, 2])),
+    ]);
+    let schematic = Tag::Compound(vec![
+        ("Version".into(), Tag::Int(3)),
+        ("Width".into(), Tag::Short(2)),
+        ("Height".into(), Tag::Short(2)),
+        ("Length".into(), Tag::Short(1)),
+        ("Blocks".into(), blocks),
+    ]);
+    let bytes = nbt::write_root("", &Tag::Compound(vec![("Schematic".into(), schematic)]));
+    let volume = decode_schematic(&bytes).unwrap();
+    assert_eq!(volume.min, [0, 0, 0]);
+    assert_eq!(block_at(&volume, [0, 0, 0]), "minecraft:oak_log[axis=y]");
+    assert_eq!(block_at(&volume, [1, 1, 0]), "Rock_Stone");
+    assert_eq!(volume.block_count(), 2);
+
+    let (name, tag) = nbt::read_root(&bytes).unwrap();
+    assert_eq!(name, "");
+    assert_eq!(
+        tag.get("Schematic").and_then(|s| s.get("Width")),
+        Some(&Tag::Short(2))
+    );
+    assert!(decode_schematic(&bytes[..bytes.len() - 3]).is_err());
+}
+
+#[test]
+fn block_colors_match_the_viewport() {
+    // Exact names win over keywords: "cobblestone" would match "stone" first
+    assert_eq!(block_color("hytale:Cobblestone"), [0x7a, 0x7a, 0x7a]);
+    assert_eq!(block_color("Gold_Ore"), [0xff, 0xd7, 0x00]);
+    assert_eq!(block_color("Packed_Ice"), [0x9d, 0xca, 0xd4]);
+    assert_eq!(block_color("Rock_Granite"), [0x90, 0x90, 0x90]);
+    assert_eq!(block_color("Soil_Grass"), [0x5c, 0xb8, 0x5c]);
+    assert_eq!(block_color("Furniture_Chair"), [0x80, 0x80, 0x80]);
+}
+
+#[test]
+fn biome_colors_match_the_range_editor() {
+    assert_eq!(biome_color("Plains"), [0x96, 0xd2, 0x56]);
+    assert_eq!(biome_color("Ocean"), [0x56, 0xd2, 0x62]);
+    assert_eq!(biome_color("Zone1_Forest"), [0xbb, 0xd2, 0x56]);
+}
+
+#[test]
+fn map_image_writes_tiles_and_legend() {
+    let pack = WorldgenPack::from_asset_pack(&AssetPack {
+        path: String::new(),
+        assets: HashMap::from([(
+            "HytaleGenerator/WorldStructures/Main.json".to_string(),
+            json!({
+                "Type": "NoiseRange",
+                "DefaultBiome": "Ocean",
+                "Density": { "Type": "SimplexNoise2D", "Seed": "map", "Scale": 4 },
+                "Biomes": [{ "Biome": "Plains", "Min": 0.0, "Max": 1.0 }]
+            }),
+        )]),
+    });
+    let structure = pack.world_structure(None).unwrap();
+    let selector = BiomeSelector::new(structure, &pack.placement_scope.positions).unwrap();
+    let area = MapArea {
+        x_min: -10,
+        z_min: 4,
+        width: 5,
+        depth: 3,
+        blocks_per_pixel: 2,
+    };
+    let map = CategoryMap::biomes(&selector, area);
+    let mut names = map.names.clone();
+    names.sort();
+    assert_eq!(names, vec!["Ocean", "Plains"]);
+
+    let dir = std::env::temp_dir().join(format!("terranova-map-{}", std::process::id()));
+    let written = map.write(&dir.join("biomes"), 2).unwrap();
+    assert_eq!(written.len(), 3 * 2 + 1);
+    let legend: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(dir.join("biomes.json")).unwrap()).unwrap();
+    assert_eq!(legend["kind"], "biome");
+    assert_eq!(legend["blocks_per_pixel"], 2);
+    assert_eq!(legend["tiles"][5]["file"], "biomes_2_1.png");
+    assert_eq!(legend["tiles"][5]["width"], 1);
+    let pixels: u64 = legend["entries"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|e| e["pixels"].as_u64().unwrap())
+        .sum();
+    assert_eq!(pixels, 15);
+
+    // The last tile holds the bottom-right pixel, column (-2, 8)
+    let decoder = png::Decoder::new(fs::File::open(dir.join("biomes_2_1.png")).unwrap());
+    let mut reader = decoder.read_info().unwrap();
+    let mut buf = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut buf).unwrap();
+    assert_eq!((info.width, info.height), (1, 1));
+    let index = selector
+        .range_biome(selector.value_at(-2.0, 8.0))
+        .unwrap_or(selector.default_index);
+    assert_eq!(buf[..3], biome_color(&selector.names[index]));
+    fs::remove_dir_all(&dir).unwrap();
+
+    let generator = ChunkGenerator::for_pack(&flat_pack(), None, None, false).unwrap();
+    let materials = CategoryMap::materials(&generator, area, 0, 8);
+    assert_eq!(materials.kind, MapKind::Material);
+    assert_eq!(materials.names, vec!["Rock_Stone"]);
+    assert_eq!(materials.render(0, 0, 1, 1), block_color("Rock_Stone"));
+    assert_eq!(materials.legend_entries()[0].area_percent, 100.0);
+    assert!(MapKind::from_name("height").is_err());
+}
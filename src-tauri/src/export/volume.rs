@@ -0,0 +1,121 @@
+use std::collections::HashMap;
+use std::ops::RangeInclusive;
+
+use crate::bridge::types::ChunkDataResponse;
+use crate::worldgen::chunk::{ChunkGenerator, CHUNK_SIZE, EMPTY_BLOCK};
+
+/// Named blocks over a box-shaped world region, the common input of the
+/// voxel exporters.
+#[derive(Debug, Clone)]
+pub struct BlockVolume {
+    /// World position of the first block
+    pub min: [i32; 3],
+    /// Blocks along X, Y and Z
+    pub size: [usize; 3],
+    /// Indices into `names`, 0 for air; indexed `(y * size_z + z) * size_x + x`
+    pub blocks: Vec<u32>,
+    /// Block names by index, `Empty` first
+    pub names: Vec<String>,
+    ids: HashMap<String, u32>,
+}
+
+impl BlockVolume {
+    /// An all-air volume.
+    pub fn new(min: [i32; 3], size: [usize; 3]) -> Self {
+        BlockVolume {
+            min,
+            size,
+            blocks: vec![0; size[0] * size[1] * size[2]],
+            names: vec![EMPTY_BLOCK.to_string()],
+            ids: HashMap::from([(EMPTY_BLOCK.to_string(), 0)]),
+        }
+    }
+
+    /// Generate the region offline, one column at a time.
+    pub fn generate(generator: &ChunkGenerator, min: [i32; 3], size: [usize; 3]) -> Self {
+        let mut volume = BlockVolume::new(min, size);
+        let y_max = min[1] + size[1] as i32;
+        for z in 0..size[2] {
+            for x in 0..size[0] {
+                let column =
+                    generator.column_blocks(min[0] + x as i32, min[2] + z as i32, min[1], y_max);
+                for (y, block) in column.into_iter().enumerate() {
+                    if let Some(name) = block {
+                        volume.set([x, y, z], name);
+                    }
+                }
+            }
+        }
+        volume
+    }
+
+    pub fn index(&self, [x, y, z]: [usize; 3]) -> usize {
+        (y * self.size[2] + z) * self.size[0] + x
+    }
+
+    /// Place a block at a local position, registering its name on first use.
+    pub fn set(&mut self, position: [usize; 3], name: &str) {
+        let id = match self.ids.get(name) {
+            Some(&id) => id,
+            None => {
+                let id = self.names.len() as u32;
+                self.ids.insert(name.to_string(), id);
+                self.names.push(name.to_string());
+                id
+            }
+        };
+        let i = self.index(position);
+        self.blocks[i] = id;
+    }
+
+    /// Number of non-air blocks.
+    pub fn block_count(&self) -> usize {
+        self.blocks.iter().filter(|&&id| id != 0).count()
+    }
+
+    /// Chunk X and Z coordinates overlapping the region.
+    pub fn chunk_range(&self) -> (RangeInclusive<i32>, RangeInclusive<i32>) {
+        let chunks = |min: i32, size: usize| {
+            min.div_euclid(CHUNK_SIZE)..=(min + size as i32 - 1).div_euclid(CHUNK_SIZE)
+        };
+        (
+            chunks(self.min[0], self.size[0]),
+            chunks(self.min[2], self.size[2]),
+        )
+    }
+
+    /// Copy the part of a chunk inside the region. `palette` maps the chunk's
+    /// block ids to names as in `BlockPaletteResponse`; ids missing from it
+    /// are kept as `Unknown_<id>`.
+    pub fn add_chunk(&mut self, chunk: &ChunkDataResponse, palette: &HashMap<String, String>) {
+        let height = (chunk.y_max - chunk.y_min).max(0) as usize;
+        for lz in 0..chunk.size_z {
+            for lx in 0..chunk.size_x {
+                let x = chunk.chunk_x * chunk.size_x + lx - self.min[0];
+                let z = chunk.chunk_z * chunk.size_z + lz - self.min[2];
+                if x < 0 || z < 0 || x as usize >= self.size[0] || z as usize >= self.size[2] {
+                    continue;
+                }
+                let base = (lz * chunk.size_x + lx) as usize * height;
+                for i in 0..height {
+                    let y = chunk.y_min + i as i32 - self.min[1];
+                    if y < 0 || y as usize >= self.size[1] {
+                        continue;
+                    }
+                    let Some(&id) = chunk.blocks.get(base + i) else {
+                        continue;
+                    };
+                    if id == 0 {
+                        continue;
+                    }
+                    let position = [x as usize, y as usize, z as usize];
+                    match palette.get(&id.to_string()) {
+                        Some(name) if name != EMPTY_BLOCK => self.set(position, name),
+                        Some(_) => {}
+                        None => self.set(position, &format!("Unknown_{}", id)),
+                    }
+                }
+            }
+        }
+    }
+}
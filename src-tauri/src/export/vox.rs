@@ -0,0 +1,202 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+
+use super::colors::block_color;
+use super::volume::BlockVolume;
+
+/// Largest model MagicaVoxel accepts along any axis.
+pub const VOX_MODEL_SIZE: usize = 256;
+
+/// Palette slots available to blocks; slot 0 is reserved for empty.
+const VOX_PALETTE_SLOTS: usize = 255;
+
+/// Which block a palette slot stands for.
+#[derive(Debug, Clone, Serialize)]
+pub struct VoxPaletteEntry {
+    /// 1-based slot as shown in MagicaVoxel
+    pub index: u8,
+    pub block: String,
+    pub color: [u8; 3],
+}
+
+/// One model of at most 256³ voxels, in MagicaVoxel's Z-up axes.
+#[derive(Debug, Clone)]
+struct VoxModel {
+    size: [usize; 3],
+    /// Position of the model's first voxel in the scene
+    origin: [usize; 3],
+    /// x, y, z and palette slot
+    voxels: Vec<[u8; 4]>,
+}
+
+/// A MagicaVoxel scene built from a block volume.
+#[derive(Debug, Clone)]
+pub struct VoxFile {
+    models: Vec<VoxModel>,
+    pub palette: Vec<VoxPaletteEntry>,
+}
+
+impl VoxFile {
+    /// Split a volume into models of at most 256³ and give every block name
+    /// its own palette slot colored like the viewport. World Y becomes the
+    /// vox Z axis and world Z is mirrored onto vox Y to keep the handedness.
+    /// Empty models are left out.
+    pub fn from_volume(volume: &BlockVolume) -> Result<Self, String> {
+        let block_types = volume.names.len() - 1;
+        if block_types > VOX_PALETTE_SLOTS {
+            return Err(format!(
+                "Region has {} block types; a .vox palette holds at most {}",
+                block_types, VOX_PALETTE_SLOTS
+            ));
+        }
+        let palette = volume.names[1..]
+            .iter()
+            .enumerate()
+            .map(|(i, name)| VoxPaletteEntry {
+                index: i as u8 + 1,
+                block: name.clone(),
+                color: block_color(name),
+            })
+            .collect();
+
+        let [size_x, size_y, size_z] = volume.size;
+        let scene = [size_x, size_z, size_y];
+        let tiles = scene.map(|n| n.div_ceil(VOX_MODEL_SIZE));
+        let mut models = Vec::new();
+        for tz in 0..tiles[2] {
+            for ty in 0..tiles[1] {
+                for tx in 0..tiles[0] {
+                    let origin = [tx, ty, tz].map(|t| t * VOX_MODEL_SIZE);
+                    let size: [usize; 3] =
+                        std::array::from_fn(|i| (scene[i] - origin[i]).min(VOX_MODEL_SIZE));
+                    let mut voxels = Vec::new();
+                    for vz in 0..size[2] {
+                        for vy in 0..size[1] {
+                            for vx in 0..size[0] {
+                                let [sx, sy, sz] = [origin[0] + vx, origin[1] + vy, origin[2] + vz];
+                                let id = volume.blocks[volume.index([sx, sz, size_z - 1 - sy])];
+                                if id != 0 {
+                                    voxels.push([vx as u8, vy as u8, vz as u8, id as u8]);
+                                }
+                            }
+                        }
+                    }
+                    if !voxels.is_empty() {
+                        models.push(VoxModel {
+                            size,
+                            origin,
+                            voxels,
+                        });
+                    }
+                }
+            }
+        }
+        Ok(VoxFile { models, palette })
+    }
+
+    pub fn model_count(&self) -> usize {
+        self.models.len()
+    }
+
+    pub fn voxel_count(&self) -> usize {
+        self.models.iter().map(|m| m.voxels.len()).sum()
+    }
+
+    /// Encode as a version 150 `.vox`: SIZE/XYZI per model, a transform per
+    /// model under one group so the pieces line up, then the palette.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut children = Vec::new();
+        for model in &self.models {
+            let size = model.size.map(|n| n as i32);
+            write_chunk(&mut children, b"SIZE", &i32_bytes(&size));
+            let mut xyzi = (model.voxels.len() as u32).to_le_bytes().to_vec();
+            xyzi.extend(model.voxels.iter().flatten());
+            write_chunk(&mut children, b"XYZI", &xyzi);
+        }
+
+        // Node ids: 0 root transform, 1 group, then a transform and shape
+        // per model
+        let shape_ids = |i: usize| (2 + 2 * i as i32, 3 + 2 * i as i32);
+        write_chunk(&mut children, b"nTRN", &transform_node(0, 1, -1, None));
+        let mut group = i32_bytes(&[1, 0, self.models.len() as i32]);
+        group.extend(i32_bytes(
+            &(0..self.models.len())
+                .map(|i| shape_ids(i).0)
+                .collect::<Vec<_>>(),
+        ));
+        write_chunk(&mut children, b"nGRP", &group);
+        for (i, model) in self.models.iter().enumerate() {
+            let (transform, shape) = shape_ids(i);
+            // MagicaVoxel places a model by its centre
+            let centre: [i32; 3] =
+                std::array::from_fn(|k| (model.origin[k] + model.size[k] / 2) as i32);
+            write_chunk(
+                &mut children,
+                b"nTRN",
+                &transform_node(transform, shape, 0, Some(centre)),
+            );
+            write_chunk(
+                &mut children,
+                b"nSHP",
+                &i32_bytes(&[shape, 0, 1, i as i32, 0]),
+            );
+        }
+
+        let mut rgba = vec![0u8; 256 * 4];
+        for entry in &self.palette {
+            let at = (entry.index as usize - 1) * 4;
+            rgba[at..at + 3].copy_from_slice(&entry.color);
+            rgba[at + 3] = 255;
+        }
+        write_chunk(&mut children, b"RGBA", &rgba);
+
+        let mut out = b"VOX ".to_vec();
+        out.extend_from_slice(&150u32.to_le_bytes());
+        out.extend_from_slice(b"MAIN");
+        out.extend_from_slice(&0u32.to_le_bytes());
+        out.extend_from_slice(&(children.len() as u32).to_le_bytes());
+        out.extend(children);
+        out
+    }
+
+    /// Write `<base>.vox`, returning its path.
+    pub fn write(&self, base: &Path) -> Result<PathBuf, String> {
+        if let Some(parent) = base.parent().filter(|p| !p.as_os_str().is_empty()) {
+            fs::create_dir_all(parent).map_err(|e| format!("Failed to create directory: {}", e))?;
+        }
+        let path = base.with_extension("vox");
+        fs::write(&path, self.encode())
+            .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+        Ok(path)
+    }
+}
+
+fn i32_bytes(values: &[i32]) -> Vec<u8> {
+    values.iter().flat_map(|v| v.to_le_bytes()).collect()
+}
+
+/// A chunk without children.
+fn write_chunk(out: &mut Vec<u8>, id: &[u8; 4], content: &[u8]) {
+    out.extend_from_slice(id);
+    out.extend_from_slice(&(content.len() as u32).to_le_bytes());
+    out.extend_from_slice(&0u32.to_le_bytes());
+    out.extend_from_slice(content);
+}
+
+/// Content of an nTRN node with a single frame, translated by `translation`.
+fn transform_node(id: i32, child: i32, layer: i32, translation: Option<[i32; 3]>) -> Vec<u8> {
+    let mut out = i32_bytes(&[id, 0, child, -1, layer, 1]);
+    match translation {
+        Some([x, y, z]) => {
+            let value = format!("{} {} {}", x, y, z);
+            out.extend(i32_bytes(&[1, 2]));
+            out.extend_from_slice(b"_t");
+            out.extend(i32_bytes(&[value.len() as i32]));
+            out.extend_from_slice(value.as_bytes());
+        }
+        None => out.extend(i32_bytes(&[0])),
+    }
+    out
+}
@@ -1,11 +1,20 @@
 mod bridge;
 mod commands;
+mod export;
 mod io;
+mod material;
 mod noise;
+mod placement;
 mod schema;
+mod worldgen;
 
 use bridge::client::BridgeState;
-use commands::{bridge as bridge_commands, hardware, io as io_commands, preview, process, validate};
+use commands::tiles::TileCacheState;
+use commands::{
+    analysis, bridge as bridge_commands, export as export_commands, hardware, io as io_commands,
+    material as material_commands, placement as placement_commands, preview, process, tiles,
+    validate, worldgen as worldgen_commands,
+};
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
@@ -15,6 +24,7 @@ pub fn run() {
         .plugin(tauri_plugin_updater::Builder::new().build())
         .plugin(tauri_plugin_process::init())
         .manage(BridgeState::default())
+        .manage(TileCacheState::default())
         .invoke_handler(tauri::generate_handler![
             io_commands::open_asset_pack,
             io_commands::save_asset_pack,
@@ -28,6 +38,33 @@ pub fn run() {
             io_commands::create_blank_project,
             validate::validate_asset_pack,
             preview::evaluate_density,
+            preview::profile_density,
+            preview::evaluate_gradient,
+            analysis::analyze_density_ranges,
+            tiles::get_density_tile,
+            tiles::clear_tile_cache,
+            material_commands::evaluate_material_column,
+            material_commands::compute_layer_stack,
+            worldgen_commands::generate_offline_chunk,
+            worldgen_commands::generate_biome_map,
+            worldgen_commands::generate_environment_map,
+            worldgen_commands::preview_spawn_positions,
+            worldgen_commands::list_framework_entries,
+            export_commands::export_heightmap,
+            export_commands::export_mesh,
+            export_commands::export_map_image,
+            export_commands::export_vox,
+            export_commands::export_fetched_vox,
+            export_commands::export_schematic,
+            export_commands::export_fetched_schematic,
+            export_commands::import_schematic,
+            placement_commands::evaluate_pattern_mask,
+            placement_commands::scan_positions,
+            placement_commands::simulate_biome_props,
+            placement_commands::evaluate_assignments,
+            placement_commands::load_prefab,
+            placement_commands::evaluate_directionality,
+            placement_commands::apply_block_mask,
             bridge_commands::bridge_connect,
             bridge_commands::bridge_disconnect,
             bridge_commands::bridge_status,
@@ -0,0 +1,102 @@
+/// Solid/empty flags for one vertical column, bottom to top.
+#[derive(Debug, Clone)]
+pub struct SolidityColumn {
+    /// World Y of `solid[0]`
+    pub y_min: i32,
+    pub solid: Vec<bool>,
+}
+
+impl SolidityColumn {
+    pub fn new(y_min: i32, solid: Vec<bool>) -> Self {
+        SolidityColumn { y_min, solid }
+    }
+
+    /// Exclusive upper bound of the column.
+    pub fn y_max(&self) -> i32 {
+        self.y_min + self.solid.len() as i32
+    }
+
+    fn index(&self, y: i32) -> Option<usize> {
+        let i = y - self.y_min;
+        (i >= 0 && (i as usize) < self.solid.len()).then_some(i as usize)
+    }
+
+    /// Inclusive bounds of the run of equal solidity containing index `i`.
+    fn run(&self, i: usize) -> (usize, usize) {
+        let value = self.solid[i];
+        let mut bottom = i;
+        while bottom > 0 && self.solid[bottom - 1] == value {
+            bottom -= 1;
+        }
+        let mut top = i;
+        while top + 1 < self.solid.len() && self.solid[top + 1] == value {
+            top += 1;
+        }
+        (bottom, top)
+    }
+
+    /// Length of the run starting at index `start` (0 when out of range).
+    fn run_len_from(&self, start: Option<usize>) -> i32 {
+        match start {
+            Some(i) if i < self.solid.len() => {
+                let (bottom, top) = self.run(i);
+                (top - bottom + 1) as i32
+            }
+            _ => 0,
+        }
+    }
+
+    /// Material context for the block at (`x`, `y`, `z`) in this column.
+    pub fn context<'a>(&self, x: i32, y: i32, z: i32) -> MaterialContext<'a> {
+        let Some(i) = self.index(y) else {
+            return MaterialContext {
+                x,
+                y,
+                z,
+                ..Default::default()
+            };
+        };
+        let (bottom, top) = self.run(i);
+        let solid = self.solid[i];
+        let above = self.run_len_from(Some(top + 1));
+        let below = self.run_len_from(bottom.checked_sub(1));
+
+        let (space_above_floor, space_below_ceiling) = if solid {
+            (above, below)
+        } else {
+            ((i - bottom) as i32, (top - i) as i32)
+        };
+
+        MaterialContext {
+            x,
+            y,
+            z,
+            solid,
+            depth_into_floor: (top - i) as i32,
+            depth_into_ceiling: (i - bottom) as i32,
+            space_above_floor,
+            space_below_ceiling,
+            biome: None,
+        }
+    }
+}
+
+/// Everything a material provider can look at for one block.
+///
+/// For solid blocks the depths count solid blocks above (floor) or below
+/// (ceiling) within the same solid run, and the spaces count the empty blocks
+/// beyond that run. For empty blocks the spaces are the distance to the floor
+/// below and the ceiling above.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MaterialContext<'a> {
+    pub x: i32,
+    pub y: i32,
+    pub z: i32,
+    pub solid: bool,
+    pub depth_into_floor: i32,
+    pub depth_into_ceiling: i32,
+    pub space_above_floor: i32,
+    pub space_below_ceiling: i32,
+    /// Biome name matched by Switch providers
+    pub biome: Option<&'a str>,
+}
@@ -0,0 +1,107 @@
+use serde_json::Value;
+
+use crate::schema::material::ConditionType;
+
+use super::column::MaterialContext;
+
+/// Column measurement a condition compares against.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContextValue {
+    SpaceAboveFloor,
+    SpaceBelowCeiling,
+}
+
+impl ContextValue {
+    fn parse(name: Option<&str>, path: &str) -> Result<Self, String> {
+        match name {
+            Some("SPACE_ABOVE_FLOOR") => Ok(ContextValue::SpaceAboveFloor),
+            Some("SPACE_BELOW_CEILING") => Ok(ContextValue::SpaceBelowCeiling),
+            Some(other) => Err(format!("Unknown ContextToCheck '{}' at {}", other, path)),
+            None => Err(format!("Missing 'ContextToCheck' at {}", path)),
+        }
+    }
+
+    pub fn read(self, ctx: &MaterialContext) -> i32 {
+        match self {
+            ContextValue::SpaceAboveFloor => ctx.space_above_floor,
+            ContextValue::SpaceBelowCeiling => ctx.space_below_ceiling,
+        }
+    }
+}
+
+/// Compiled `ConditionType` tree.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Condition {
+    Always,
+    Equals(ContextValue, i32),
+    GreaterThan(ContextValue, i32),
+    SmallerThan(ContextValue, i32),
+    And(Vec<Condition>),
+    Or(Vec<Condition>),
+    Not(Box<Condition>),
+}
+
+impl Condition {
+    /// Parse a condition; a missing condition always validates.
+    pub fn from_json(json: Option<&Value>, path: &str) -> Result<Self, String> {
+        let Some(json) = json.filter(|v| v.is_object()) else {
+            return Ok(Condition::Always);
+        };
+        let condition = serde_json::from_value::<ConditionType>(json.clone())
+            .map_err(|e| format!("Invalid condition at {}: {}", path, e))?;
+
+        let list = |conditions: &[Value], key: &str| -> Result<Vec<Condition>, String> {
+            conditions
+                .iter()
+                .enumerate()
+                .map(|(i, c)| Condition::from_json(Some(c), &format!("{}.{}[{}]", path, key, i)))
+                .collect()
+        };
+
+        use ConditionType as C;
+        Ok(match condition {
+            C::AlwaysTrueCondition {} => Condition::Always,
+            C::EqualsCondition {
+                context_to_check,
+                value,
+            } => Condition::Equals(
+                ContextValue::parse(context_to_check.as_deref(), path)?,
+                value.unwrap_or(0),
+            ),
+            C::GreaterThanCondition {
+                context_to_check,
+                threshold,
+            } => Condition::GreaterThan(
+                ContextValue::parse(context_to_check.as_deref(), path)?,
+                threshold.unwrap_or(0),
+            ),
+            C::SmallerThanCondition {
+                context_to_check,
+                threshold,
+            } => Condition::SmallerThan(
+                ContextValue::parse(context_to_check.as_deref(), path)?,
+                threshold.unwrap_or(0),
+            ),
+            C::AndCondition { conditions } => Condition::And(list(&conditions, "Conditions")?),
+            C::OrCondition { conditions } => Condition::Or(list(&conditions, "Conditions")?),
+            C::NotCondition { condition } => Condition::Not(Box::new(Condition::from_json(
+                condition.as_ref(),
+                &format!("{}.Condition", path),
+            )?)),
+        })
+    }
+
+    /// Whether the condition validates for this context. An empty And is
+    /// true and an empty Or is false.
+    pub fn test(&self, ctx: &MaterialContext) -> bool {
+        match self {
+            Condition::Always => true,
+            Condition::Equals(value, expected) => value.read(ctx) == *expected,
+            Condition::GreaterThan(value, threshold) => value.read(ctx) > *threshold,
+            Condition::SmallerThan(value, threshold) => value.read(ctx) < *threshold,
+            Condition::And(conditions) => conditions.iter().all(|c| c.test(ctx)),
+            Condition::Or(conditions) => conditions.iter().any(|c| c.test(ctx)),
+            Condition::Not(condition) => !condition.test(ctx),
+        }
+    }
+}
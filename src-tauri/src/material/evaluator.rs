@@ -0,0 +1,470 @@
+use std::collections::HashMap;
+
+use serde_json::Value;
+
+use crate::noise::evaluator::DensityEvaluator;
+use crate::noise::hash::seed_hash;
+use crate::noise::interval::{point_x, point_y};
+use crate::placement::positions::PositionScope;
+use crate::schema::material::{LayerType, MaterialProviderType};
+
+use super::column::SolidityColumn;
+use super::conditions::Condition;
+use super::layers::{Layer, Thickness};
+use super::providers::*;
+
+/// Named values a material graph can refer to outside its own JSON.
+#[derive(Debug, Clone, Default)]
+pub struct MaterialScope {
+    /// Exported material providers by `ExportAs` name
+    pub exports: HashMap<String, Value>,
+    /// BaseHeight offsets by name, for SimpleHorizontal bounds
+    pub base_heights: HashMap<String, f64>,
+    /// Position providers the densities inside a material graph may import
+    pub positions: PositionScope,
+}
+
+impl MaterialScope {
+    /// Register every `Exported` material provider found anywhere in `json`.
+    pub fn collect_exports(&mut self, json: &Value) {
+        match json {
+            Value::Object(obj) => {
+                if obj.get("Type").and_then(|v| v.as_str()) == Some("Exported") {
+                    if let (Some(name), Some(material)) = (
+                        obj.get("ExportAs").and_then(|v| v.as_str()),
+                        obj.get("Material").filter(|v| v.is_object()),
+                    ) {
+                        self.exports.insert(name.to_string(), material.clone());
+                    }
+                }
+                obj.values().for_each(|v| self.collect_exports(v));
+            }
+            Value::Array(items) => items.iter().for_each(|v| self.collect_exports(v)),
+            _ => {}
+        }
+    }
+}
+
+/// Material provider evaluator.
+/// Parses a V2 MaterialProvider JSON and picks a block name per position.
+pub struct MaterialEvaluator {
+    root: Box<dyn MaterialEval>,
+}
+
+impl MaterialEvaluator {
+    /// Parse a V2 material provider JSON. Exports declared inside the graph
+    /// are visible to its own `Imported` nodes alongside those in `scope`.
+    pub fn from_json(json: &Value, scope: &MaterialScope) -> Result<Self, String> {
+        let mut scope = scope.clone();
+        scope.collect_exports(json);
+        let mut ctx = ParseContext {
+            scope: &scope,
+            imports: Vec::new(),
+        };
+        let root = parse_provider(json, "$", &mut ctx)?;
+        Ok(MaterialEvaluator { root })
+    }
+
+    /// Block name at world `y` of the column at (`x`, `z`).
+    pub fn pick_in_column(
+        &self,
+        column: &SolidityColumn,
+        x: i32,
+        y: i32,
+        z: i32,
+        biome: Option<&str>,
+    ) -> Option<&str> {
+        let mut ctx = column.context(x, y, z);
+        ctx.biome = biome;
+        self.root.pick(&ctx)
+    }
+}
+
+/// Parse a SpaceAndDepth provider on its own, for layer stack inspection.
+pub fn parse_space_and_depth(
+    json: &Value,
+    scope: &MaterialScope,
+) -> Result<SpaceAndDepthProvider, String> {
+    let mut scope = scope.clone();
+    scope.collect_exports(json);
+    let mut ctx = ParseContext {
+        scope: &scope,
+        imports: Vec::new(),
+    };
+    let provider = serde_json::from_value::<MaterialProviderType>(json.clone())
+        .map_err(|e| format!("Invalid material provider: {}", e))?;
+    space_and_depth(provider, "$", &mut ctx)
+}
+
+struct ParseContext<'s> {
+    scope: &'s MaterialScope,
+    /// Names of the imports currently being expanded, to detect cycles
+    imports: Vec<String>,
+}
+
+/// Parse an optional child provider; absent children never apply.
+fn parse_child(
+    json: Option<&Value>,
+    path: &str,
+    ctx: &mut ParseContext,
+) -> Result<Box<dyn MaterialEval>, String> {
+    match json {
+        Some(v) if v.is_object() => parse_provider(v, path, ctx),
+        _ => Ok(Box::new(NoneProvider)),
+    }
+}
+
+fn parse_list(
+    items: &[Value],
+    path: &str,
+    ctx: &mut ParseContext,
+) -> Result<Vec<Box<dyn MaterialEval>>, String> {
+    items
+        .iter()
+        .enumerate()
+        .map(|(i, item)| parse_provider(item, &format!("{}[{}]", path, i), ctx))
+        .collect()
+}
+
+/// Recursively parse a JSON material provider into an evaluable node.
+fn parse_provider(
+    json: &Value,
+    path: &str,
+    ctx: &mut ParseContext,
+) -> Result<Box<dyn MaterialEval>, String> {
+    let obj = json
+        .as_object()
+        .ok_or_else(|| format!("Material provider at {} must be a JSON object", path))?;
+    if obj.get("Type").and_then(|v| v.as_str()).is_none() {
+        return Err(format!("Missing 'Type' field at {}", path));
+    }
+    if obj.get("Skip").and_then(|v| v.as_bool()) == Some(true) {
+        return Ok(Box::new(NoneProvider));
+    }
+
+    // Unknown types never apply
+    let Ok(provider) = serde_json::from_value::<MaterialProviderType>(json.clone()) else {
+        return Ok(Box::new(NoneProvider));
+    };
+
+    use MaterialProviderType as M;
+    Ok(match provider {
+        M::Constant {
+            block_type,
+            material,
+        } => match material.as_ref().and_then(block_name).or(block_type) {
+            Some(block) => Box::new(ConstantProvider { block }),
+            None => Box::new(NoneProvider),
+        },
+
+        M::Solidity { solid, empty } => Box::new(SolidityProvider {
+            solid: parse_child(solid.as_ref(), &format!("{}.Solid", path), ctx)?,
+            empty: parse_child(empty.as_ref(), &format!("{}.Empty", path), ctx)?,
+        }),
+
+        M::Queue { queue } => Box::new(QueueProvider {
+            queue: parse_list(&queue, &format!("{}.Queue", path), ctx)?,
+        }),
+
+        M::SimpleHorizontal {
+            top_y,
+            top_base_height,
+            bottom_y,
+            bottom_base_height,
+            material,
+        } => {
+            let offset = |name: &Option<String>| {
+                name.as_ref()
+                    .and_then(|n| ctx.scope.base_heights.get(n))
+                    .copied()
+                    .unwrap_or(0.0)
+            };
+            let top = top_y.map_or(f64::INFINITY, |y| y as f64 + offset(&top_base_height));
+            let bottom = bottom_y.map_or(f64::NEG_INFINITY, |y| {
+                y as f64 + offset(&bottom_base_height)
+            });
+            Box::new(SimpleHorizontalProvider {
+                bottom,
+                top,
+                material: parse_child(material.as_ref(), &format!("{}.Material", path), ctx)?,
+            })
+        }
+
+        M::Striped { stripes, material } => {
+            let stripes = stripes
+                .iter()
+                .filter_map(|s| {
+                    let top = s.get("TopY")?.as_i64()? as i32;
+                    let bottom = s.get("BottomY")?.as_i64()? as i32;
+                    Some((bottom.min(top), bottom.max(top)))
+                })
+                .collect();
+            Box::new(StripedProvider {
+                stripes,
+                material: parse_child(material.as_ref(), &format!("{}.Material", path), ctx)?,
+            })
+        }
+
+        M::Weighted {
+            seed,
+            skip_chance,
+            weighted_materials,
+        } => {
+            let mut entries = Vec::new();
+            for (i, entry) in weighted_materials.iter().enumerate() {
+                let weight = entry
+                    .get("Weight")
+                    .and_then(|v| v.as_f64())
+                    .unwrap_or(1.0)
+                    .max(0.0);
+                let material = parse_child(
+                    entry.get("Material"),
+                    &format!("{}.WeightedMaterials[{}].Material", path, i),
+                    ctx,
+                )?;
+                entries.push((weight, material));
+            }
+            let total_weight = entries.iter().map(|(w, _)| w).sum();
+            Box::new(WeightedProvider {
+                seed: seed_hash(seed.as_deref().unwrap_or("")),
+                skip_chance: skip_chance.unwrap_or(0.0),
+                entries,
+                total_weight,
+            })
+        }
+
+        M::FieldFunction {
+            field_function,
+            delimiters,
+        } => {
+            let field =
+                parse_density_with_positions(field_function.as_ref(), &ctx.scope.positions)?;
+            Box::new(FieldFunctionProvider {
+                field,
+                delimiters: parse_delimiters(&delimiters, &format!("{}.Delimiters", path), ctx)?,
+            })
+        }
+
+        M::SpaceAndDepth { .. } => Box::new(space_and_depth(provider, path, ctx)?),
+
+        M::Imported { name } => {
+            let name = name.unwrap_or_default();
+            let Some(export) = ctx.scope.exports.get(&name) else {
+                return Err(format!("Unknown material export '{}' at {}", name, path));
+            };
+            if ctx.imports.contains(&name) {
+                return Err(format!("Cyclic material import '{}' at {}", name, path));
+            }
+            ctx.imports.push(name.clone());
+            let provider = parse_provider(export, &format!("{}<{}>", path, name), ctx);
+            ctx.imports.pop();
+            provider?
+        }
+
+        M::Exported { material, .. } => {
+            parse_child(material.as_ref(), &format!("{}.Material", path), ctx)?
+        }
+
+        M::Switch { switch_cases } => {
+            let mut cases = Vec::new();
+            for (i, case) in switch_cases.iter().enumerate() {
+                let state = ["CaseState", "State", "Biome"]
+                    .iter()
+                    .find_map(|key| case.get(*key).and_then(|v| v.as_str()))
+                    .filter(|s| !s.is_empty() && *s != "Default")
+                    .map(str::to_string);
+                let material = parse_child(
+                    case.get("Material"),
+                    &format!("{}.SwitchCases[{}].Material", path, i),
+                    ctx,
+                )?;
+                cases.push((state, material));
+            }
+            Box::new(SwitchProvider { cases })
+        }
+
+        M::DepthBased {
+            depth_curve,
+            materials,
+        } => Box::new(DepthBasedProvider {
+            curve: depth_curve.as_ref().map(curve_points).unwrap_or_default(),
+            materials: parse_delimiters(&materials, &format!("{}.Materials", path), ctx)?,
+        }),
+
+        M::GradientBased {
+            gradient,
+            materials,
+        } => Box::new(GradientBasedProvider {
+            gradient: parse_density_with_positions(gradient.as_ref(), &ctx.scope.positions)?,
+            materials: parse_delimiters(&materials, &format!("{}.Materials", path), ctx)?,
+        }),
+
+        M::Pipeline { steps } => Box::new(PipelineProvider {
+            steps: parse_list(&steps, &format!("{}.Steps", path), ctx)?,
+        }),
+    })
+}
+
+fn space_and_depth(
+    provider: MaterialProviderType,
+    path: &str,
+    ctx: &mut ParseContext,
+) -> Result<SpaceAndDepthProvider, String> {
+    let MaterialProviderType::SpaceAndDepth {
+        layer_context,
+        max_expected_depth,
+        condition,
+        layers,
+    } = provider
+    else {
+        return Err(format!("Expected a SpaceAndDepth provider at {}", path));
+    };
+    Ok(SpaceAndDepthProvider {
+        ceiling: layer_context.as_deref() == Some("DEPTH_INTO_CEILING"),
+        max_expected_depth,
+        condition: Condition::from_json(condition.as_ref(), &format!("{}.Condition", path))?,
+        layers: layers
+            .iter()
+            .enumerate()
+            .map(|(i, layer)| parse_layer(layer, &format!("{}.Layers[{}]", path, i), ctx))
+            .collect::<Result<_, _>>()?,
+    })
+}
+
+/// Parse one `LayerType` entry of a SpaceAndDepth stack.
+fn parse_layer(json: &Value, path: &str, ctx: &mut ParseContext) -> Result<Layer, String> {
+    let layer = serde_json::from_value::<LayerType>(json.clone())
+        .map_err(|e| format!("Invalid layer at {}: {}", path, e))?;
+
+    let material_path = format!("{}.Material", path);
+    let (layer_type, thickness, material) = match layer {
+        LayerType::ConstantThickness {
+            material,
+            thickness,
+        } => (
+            "ConstantThickness",
+            Thickness::Constant(thickness.unwrap_or(1)),
+            material,
+        ),
+        LayerType::RangeThickness {
+            material,
+            range_min,
+            range_max,
+            seed,
+        } => (
+            "RangeThickness",
+            Thickness::Range {
+                min: range_min.unwrap_or(1),
+                max: range_max.unwrap_or(1),
+                seed: seed_hash(seed.as_deref().unwrap_or("")),
+            },
+            material,
+        ),
+        LayerType::WeightedThickness {
+            material,
+            possible_thicknesses,
+            seed,
+        } => {
+            let entries: Vec<(f64, i32)> = possible_thicknesses
+                .iter()
+                .map(|e| {
+                    let weight = e.get("Weight").and_then(|v| v.as_f64()).unwrap_or(1.0);
+                    let thickness = e.get("Thickness").and_then(|v| v.as_i64()).unwrap_or(1);
+                    (weight.max(0.0), thickness as i32)
+                })
+                .collect();
+            let total_weight = entries.iter().map(|(w, _)| w).sum();
+            (
+                "WeightedThickness",
+                Thickness::Weighted {
+                    entries,
+                    total_weight,
+                    seed: seed_hash(seed.as_deref().unwrap_or("")),
+                },
+                material,
+            )
+        }
+        LayerType::NoiseThickness {
+            material,
+            thickness_function_xz,
+        } => (
+            "NoiseThickness",
+            Thickness::Noise(parse_density_with_positions(
+                thickness_function_xz.as_ref(),
+                &ctx.scope.positions,
+            )?),
+            material,
+        ),
+    };
+
+    Ok(Layer {
+        layer_type,
+        thickness,
+        material: parse_child(material.as_ref(), &material_path, ctx)?,
+    })
+}
+
+/// Parse a density input, resolving position providers against `positions`;
+/// a missing function evaluates as zero.
+pub fn parse_density_with_positions(
+    json: Option<&Value>,
+    positions: &PositionScope,
+) -> Result<DensityEvaluator, String> {
+    match json {
+        Some(v) if v.is_object() => DensityEvaluator::from_json_with_positions(v, positions),
+        _ => DensityEvaluator::from_json(&serde_json::json!({ "Type": "Constant", "Value": 0.0 })),
+    }
+}
+
+/// Parse `{ From, To, Material }` entries; missing bounds are unbounded.
+fn parse_delimiters(
+    items: &[Value],
+    path: &str,
+    ctx: &mut ParseContext,
+) -> Result<Vec<Delimiter>, String> {
+    let mut delimiters = Vec::new();
+    for (i, item) in items.iter().enumerate() {
+        let from = item.get("From").and_then(|v| v.as_f64());
+        let to = item.get("To").and_then(|v| v.as_f64());
+        let material = parse_child(
+            item.get("Material"),
+            &format!("{}[{}].Material", path, i),
+            ctx,
+        )?;
+        delimiters.push(Delimiter {
+            from: from.unwrap_or(f64::NEG_INFINITY),
+            to: to.unwrap_or(f64::INFINITY),
+            material,
+        });
+    }
+    Ok(delimiters)
+}
+
+/// Block name from a material value: a plain string or a `{ "Solid": ... }` object.
+pub fn block_name(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) if !s.is_empty() => Some(s.clone()),
+        Value::Object(obj) => obj
+            .get("Solid")
+            .and_then(|v| v.as_str())
+            .filter(|s| !s.is_empty())
+            .map(str::to_string),
+        _ => None,
+    }
+}
+
+/// Points of a Manual curve sorted by X; other curve types yield no points.
+pub fn curve_points(curve: &Value) -> Vec<(f64, f64)> {
+    let mut points: Vec<(f64, f64)> = curve
+        .get("Points")
+        .and_then(|v| v.as_array())
+        .map(|points| {
+            points
+                .iter()
+                .filter_map(|p| Some((point_x(p)?, point_y(p)?)))
+                .collect()
+        })
+        .unwrap_or_default();
+    points.sort_by(|a, b| a.0.total_cmp(&b.0));
+    points
+}
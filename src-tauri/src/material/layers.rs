@@ -0,0 +1,193 @@
+use serde::Serialize;
+
+use crate::noise::evaluator::DensityEvaluator;
+use crate::noise::hash::position_random;
+
+use super::column::SolidityColumn;
+use super::providers::{MaterialEval, SpaceAndDepthProvider};
+
+/// How a SpaceAndDepth layer decides its thickness at a column.
+pub enum Thickness {
+    Constant(i32),
+    /// Uniform in `min..=max` per column
+    Range {
+        min: i32,
+        max: i32,
+        seed: i32,
+    },
+    /// Weighted choice of thickness per column
+    Weighted {
+        entries: Vec<(f64, i32)>,
+        total_weight: f64,
+        seed: i32,
+    },
+    /// Rounded value of a density function sampled at (x, 0, z)
+    Noise(DensityEvaluator),
+}
+
+impl Thickness {
+    /// Thickness of the layer in the column at (`x`, `z`), never negative.
+    pub fn at(&self, x: i32, z: i32) -> i32 {
+        let thickness = match self {
+            Thickness::Constant(t) => *t,
+            Thickness::Range { min, max, seed } => {
+                let (lo, hi) = ((*min).min(*max), (*min).max(*max));
+                let span = (hi - lo + 1) as f64;
+                lo + (position_random(x, 0, z, *seed) * span).floor() as i32
+            }
+            Thickness::Weighted {
+                entries,
+                total_weight,
+                seed,
+            } => {
+                if *total_weight <= 0.0 {
+                    return 0;
+                }
+                let target = position_random(x, 0, z, *seed) * total_weight;
+                let mut cumulative = 0.0;
+                entries
+                    .iter()
+                    .find(|(weight, _)| {
+                        cumulative += weight;
+                        target < cumulative
+                    })
+                    .or(entries.last())
+                    .map(|(_, t)| *t)
+                    .unwrap_or(0)
+            }
+            Thickness::Noise(field) => field.evaluate(x as f64, 0.0, z as f64).round() as i32,
+        };
+        thickness.max(0)
+    }
+}
+
+/// One layer of a SpaceAndDepth stack.
+pub struct Layer {
+    /// `LayerType` name, for reports
+    pub layer_type: &'static str,
+    pub thickness: Thickness,
+    pub material: Box<dyn MaterialEval>,
+}
+
+/// Depth range a layer occupies in one column.
+#[derive(Debug, Clone, Serialize)]
+pub struct LayerSpan {
+    pub index: usize,
+    pub layer_type: &'static str,
+    pub thickness: i32,
+    /// First depth covered (0 is the surface block)
+    pub depth_start: i32,
+    /// Depth just past the layer
+    pub depth_end: i32,
+}
+
+/// Stack the layers at (`x`, `z`) from depth 0 down, stopping at
+/// `max_depth` when given.
+pub fn stack_layers(layers: &[Layer], x: i32, z: i32, max_depth: Option<i32>) -> Vec<LayerSpan> {
+    let mut spans = Vec::with_capacity(layers.len());
+    let mut depth = 0;
+    for (index, layer) in layers.iter().enumerate() {
+        if max_depth.is_some_and(|max| depth >= max) {
+            break;
+        }
+        let thickness = layer.thickness.at(x, z);
+        let mut depth_end = depth + thickness;
+        if let Some(max) = max_depth {
+            depth_end = depth_end.min(max);
+        }
+        spans.push(LayerSpan {
+            index,
+            layer_type: layer.layer_type,
+            thickness,
+            depth_start: depth,
+            depth_end,
+        });
+        depth = depth_end;
+    }
+    spans
+}
+
+/// A layer as it lands on one surface of a column.
+#[derive(Debug, Clone, Serialize)]
+pub struct PlacedLayer {
+    #[serde(flatten)]
+    pub span: LayerSpan,
+    /// Blocks of this layer that fit inside the solid run
+    pub blocks: i32,
+    /// World Y of the layer's first block, if any of it fits
+    pub y_start: Option<i32>,
+    /// Material the layer provides at its first block
+    pub material: Option<String>,
+}
+
+/// The layer stack under (or above, for ceilings) one surface of a column.
+#[derive(Debug, Clone, Serialize)]
+pub struct SurfaceStack {
+    /// World Y of the surface block (depth 0)
+    pub surface_y: i32,
+    pub space_above_floor: i32,
+    pub space_below_ceiling: i32,
+    /// Whether the provider's Condition validates at this surface
+    pub condition_met: bool,
+    pub layers: Vec<PlacedLayer>,
+}
+
+/// Layer stacks for every floor (or ceiling) surface in a column, top to bottom,
+/// with `biome` in the context the way `MaterialEvaluator::pick_in_column` sets it.
+pub fn column_stacks(
+    provider: &SpaceAndDepthProvider,
+    column: &SolidityColumn,
+    x: i32,
+    z: i32,
+    biome: Option<&str>,
+) -> Vec<SurfaceStack> {
+    let spans = provider.stack(x, z);
+    // Going down from a floor or up from a ceiling
+    let direction = if provider.ceiling { 1 } else { -1 };
+
+    (column.y_min..column.y_max())
+        .rev()
+        .filter_map(|y| {
+            let mut ctx = column.context(x, y, z);
+            ctx.biome = biome;
+            let (depth, run_len) = if provider.ceiling {
+                (ctx.depth_into_ceiling, ctx.depth_into_floor + 1)
+            } else {
+                (ctx.depth_into_floor, ctx.depth_into_ceiling + 1)
+            };
+            if !ctx.solid || depth != 0 {
+                return None;
+            }
+
+            let layers = spans
+                .iter()
+                .map(|span| {
+                    let blocks = (span.depth_end.min(run_len) - span.depth_start).max(0);
+                    let y_start = (blocks > 0).then_some(y + direction * span.depth_start);
+                    let material = y_start.and_then(|layer_y| {
+                        let mut layer_ctx = column.context(x, layer_y, z);
+                        layer_ctx.biome = biome;
+                        provider.layers[span.index]
+                            .material
+                            .pick(&layer_ctx)
+                            .map(str::to_string)
+                    });
+                    PlacedLayer {
+                        span: span.clone(),
+                        blocks,
+                        y_start,
+                        material,
+                    }
+                })
+                .collect();
+
+            Some(SurfaceStack {
+                surface_y: y,
+                space_above_floor: ctx.space_above_floor,
+                space_below_ceiling: ctx.space_below_ceiling,
+                condition_met: provider.condition.test(&ctx),
+                layers,
+            })
+        })
+        .collect()
+}
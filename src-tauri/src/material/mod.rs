@@ -0,0 +1,8 @@
+pub mod column;
+pub mod conditions;
+pub mod evaluator;
+pub mod layers;
+pub mod providers;
+
+#[cfg(test)]
+mod tests;
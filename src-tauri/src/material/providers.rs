@@ -0,0 +1,288 @@
+use crate::noise::evaluator::DensityEvaluator;
+use crate::noise::hash::position_random;
+
+use super::column::MaterialContext;
+use super::conditions::Condition;
+use super::layers::{stack_layers, Layer, LayerSpan};
+
+/// Trait for evaluable material provider nodes.
+pub trait MaterialEval: Send + Sync {
+    /// Block name picked for this context, or `None` if the provider doesn't apply.
+    fn pick<'a>(&'a self, ctx: &MaterialContext) -> Option<&'a str>;
+}
+
+/// Provider that never applies (skipped or unsupported nodes).
+pub struct NoneProvider;
+
+impl MaterialEval for NoneProvider {
+    fn pick<'a>(&'a self, _ctx: &MaterialContext) -> Option<&'a str> {
+        None
+    }
+}
+
+pub struct ConstantProvider {
+    pub block: String,
+}
+
+impl MaterialEval for ConstantProvider {
+    fn pick<'a>(&'a self, _ctx: &MaterialContext) -> Option<&'a str> {
+        Some(&self.block)
+    }
+}
+
+pub struct SolidityProvider {
+    pub solid: Box<dyn MaterialEval>,
+    pub empty: Box<dyn MaterialEval>,
+}
+
+impl MaterialEval for SolidityProvider {
+    fn pick<'a>(&'a self, ctx: &MaterialContext) -> Option<&'a str> {
+        if ctx.solid {
+            self.solid.pick(ctx)
+        } else {
+            self.empty.pick(ctx)
+        }
+    }
+}
+
+/// First entry that applies wins.
+pub struct QueueProvider {
+    pub queue: Vec<Box<dyn MaterialEval>>,
+}
+
+impl MaterialEval for QueueProvider {
+    fn pick<'a>(&'a self, ctx: &MaterialContext) -> Option<&'a str> {
+        self.queue.iter().find_map(|p| p.pick(ctx))
+    }
+}
+
+/// Applies its material within `bottom <= y < top`.
+pub struct SimpleHorizontalProvider {
+    pub bottom: f64,
+    pub top: f64,
+    pub material: Box<dyn MaterialEval>,
+}
+
+impl MaterialEval for SimpleHorizontalProvider {
+    fn pick<'a>(&'a self, ctx: &MaterialContext) -> Option<&'a str> {
+        let y = ctx.y as f64;
+        if y >= self.bottom && y < self.top {
+            self.material.pick(ctx)
+        } else {
+            None
+        }
+    }
+}
+
+/// Applies its material on any of a set of inclusive Y bands.
+pub struct StripedProvider {
+    pub stripes: Vec<(i32, i32)>,
+    pub material: Box<dyn MaterialEval>,
+}
+
+impl MaterialEval for StripedProvider {
+    fn pick<'a>(&'a self, ctx: &MaterialContext) -> Option<&'a str> {
+        if self
+            .stripes
+            .iter()
+            .any(|&(bottom, top)| ctx.y >= bottom && ctx.y <= top)
+        {
+            self.material.pick(ctx)
+        } else {
+            None
+        }
+    }
+}
+
+/// Seeded weighted choice per block position.
+pub struct WeightedProvider {
+    pub seed: i32,
+    pub skip_chance: f64,
+    pub entries: Vec<(f64, Box<dyn MaterialEval>)>,
+    pub total_weight: f64,
+}
+
+impl MaterialEval for WeightedProvider {
+    fn pick<'a>(&'a self, ctx: &MaterialContext) -> Option<&'a str> {
+        if self.total_weight <= 0.0 {
+            return None;
+        }
+        if self.skip_chance > 0.0 {
+            // Decorrelate the skip roll from the selection roll
+            let skip = position_random(ctx.x, ctx.y, ctx.z, self.seed ^ 0x5bd1_e995);
+            if skip < self.skip_chance {
+                return None;
+            }
+        }
+        let target = position_random(ctx.x, ctx.y, ctx.z, self.seed) * self.total_weight;
+        let mut cumulative = 0.0;
+        for (weight, provider) in &self.entries {
+            cumulative += weight;
+            if target < cumulative {
+                return provider.pick(ctx);
+            }
+        }
+        self.entries.last().and_then(|(_, p)| p.pick(ctx))
+    }
+}
+
+/// A value range `[from, to)` mapped to a provider.
+pub struct Delimiter {
+    pub from: f64,
+    pub to: f64,
+    pub material: Box<dyn MaterialEval>,
+}
+
+/// First delimiter containing `value` picks the material.
+fn pick_delimited<'a>(
+    delimiters: &'a [Delimiter],
+    value: f64,
+    ctx: &MaterialContext,
+) -> Option<&'a str> {
+    delimiters
+        .iter()
+        .find(|d| value >= d.from && value < d.to)
+        .and_then(|d| d.material.pick(ctx))
+}
+
+/// Selects by the value of a density function at the block.
+pub struct FieldFunctionProvider {
+    pub field: DensityEvaluator,
+    pub delimiters: Vec<Delimiter>,
+}
+
+impl MaterialEval for FieldFunctionProvider {
+    fn pick<'a>(&'a self, ctx: &MaterialContext) -> Option<&'a str> {
+        let value = self
+            .field
+            .evaluate(ctx.x as f64, ctx.y as f64, ctx.z as f64);
+        pick_delimited(&self.delimiters, value, ctx)
+    }
+}
+
+/// Stacks layers into the floor or ceiling of solid terrain.
+pub struct SpaceAndDepthProvider {
+    /// Count depth from the ceiling instead of the floor
+    pub ceiling: bool,
+    pub max_expected_depth: Option<i32>,
+    pub condition: Condition,
+    pub layers: Vec<Layer>,
+}
+
+impl SpaceAndDepthProvider {
+    /// Depth ranges of every layer in the column at (`x`, `z`).
+    pub fn stack(&self, x: i32, z: i32) -> Vec<LayerSpan> {
+        stack_layers(&self.layers, x, z, self.max_expected_depth)
+    }
+}
+
+impl MaterialEval for SpaceAndDepthProvider {
+    fn pick<'a>(&'a self, ctx: &MaterialContext) -> Option<&'a str> {
+        if !ctx.solid || !self.condition.test(ctx) {
+            return None;
+        }
+        let depth = if self.ceiling {
+            ctx.depth_into_ceiling
+        } else {
+            ctx.depth_into_floor
+        };
+        if self.max_expected_depth.is_some_and(|max| depth >= max) {
+            return None;
+        }
+        let mut top = 0;
+        for layer in &self.layers {
+            let bottom = top + layer.thickness.at(ctx.x, ctx.z);
+            if depth < bottom {
+                return layer.material.pick(ctx);
+            }
+            top = bottom;
+        }
+        None
+    }
+}
+
+/// Selects by depth into the floor, optionally remapped through a curve.
+pub struct DepthBasedProvider {
+    /// Piecewise-linear curve points sorted by X; identity when empty
+    pub curve: Vec<(f64, f64)>,
+    pub materials: Vec<Delimiter>,
+}
+
+impl MaterialEval for DepthBasedProvider {
+    fn pick<'a>(&'a self, ctx: &MaterialContext) -> Option<&'a str> {
+        if !ctx.solid {
+            return None;
+        }
+        let depth = ctx.depth_into_floor as f64;
+        let value = if self.curve.is_empty() {
+            depth
+        } else {
+            sample_curve(&self.curve, depth)
+        };
+        pick_delimited(&self.materials, value, ctx)
+    }
+}
+
+/// Linear interpolation between sorted points, clamped at both ends.
+pub fn sample_curve(points: &[(f64, f64)], x: f64) -> f64 {
+    let (first, last) = (points[0], points[points.len() - 1]);
+    if x <= first.0 {
+        return first.1;
+    }
+    if x >= last.0 {
+        return last.1;
+    }
+    for pair in points.windows(2) {
+        let ((x0, y0), (x1, y1)) = (pair[0], pair[1]);
+        if x <= x1 {
+            let t = if x1 > x0 { (x - x0) / (x1 - x0) } else { 0.0 };
+            return y0 + t * (y1 - y0);
+        }
+    }
+    last.1
+}
+
+/// Selects by the steepness (gradient magnitude) of a density function.
+pub struct GradientBasedProvider {
+    pub gradient: DensityEvaluator,
+    pub materials: Vec<Delimiter>,
+}
+
+impl MaterialEval for GradientBasedProvider {
+    fn pick<'a>(&'a self, ctx: &MaterialContext) -> Option<&'a str> {
+        let (_, g) = self
+            .gradient
+            .evaluate_gradient(ctx.x as f64, ctx.y as f64, ctx.z as f64, 0.5);
+        let slope = (g[0] * g[0] + g[1] * g[1] + g[2] * g[2]).sqrt();
+        pick_delimited(&self.materials, slope, ctx)
+    }
+}
+
+/// Selects a case by the biome in the context; a case without a state is the default.
+pub struct SwitchProvider {
+    pub cases: Vec<(Option<String>, Box<dyn MaterialEval>)>,
+}
+
+impl MaterialEval for SwitchProvider {
+    fn pick<'a>(&'a self, ctx: &MaterialContext) -> Option<&'a str> {
+        let matched = ctx.biome.and_then(|biome| {
+            self.cases
+                .iter()
+                .find(|(state, _)| state.as_deref() == Some(biome))
+        });
+        matched
+            .or_else(|| self.cases.iter().find(|(state, _)| state.is_none()))
+            .and_then(|(_, provider)| provider.pick(ctx))
+    }
+}
+
+/// Runs every step in order; later steps override earlier ones where they apply.
+pub struct PipelineProvider {
+    pub steps: Vec<Box<dyn MaterialEval>>,
+}
+
+impl MaterialEval for PipelineProvider {
+    fn pick<'a>(&'a self, ctx: &MaterialContext) -> Option<&'a str> {
+        self.steps.iter().rev().find_map(|p| p.pick(ctx))
+    }
+}
@@ -0,0 +1,433 @@
+use serde_json::json;
+
+use super::column::SolidityColumn;
+use super::conditions::Condition;
+use super::evaluator::{parse_space_and_depth, MaterialEvaluator, MaterialScope};
+use super::layers::column_stacks;
+
+fn constant(block: &str) -> serde_json::Value {
+    json!({ "Type": "Constant", "Material": { "Solid": block } })
+}
+
+/// Solid 0..=2 and 5..=6, empty 3..=4.
+fn test_column() -> SolidityColumn {
+    SolidityColumn::new(0, vec![true, true, true, false, false, true, true])
+}
+
+#[test]
+fn column_context_depths_and_spaces() {
+    let column = test_column();
+
+    let surface = column.context(0, 2, 0);
+    assert!(surface.solid);
+    assert_eq!(surface.depth_into_floor, 0);
+    assert_eq!(surface.depth_into_ceiling, 2);
+    assert_eq!(surface.space_above_floor, 2);
+    assert_eq!(surface.space_below_ceiling, 0);
+
+    let ceiling = column.context(0, 5, 0);
+    assert_eq!(ceiling.depth_into_floor, 1);
+    assert_eq!(ceiling.depth_into_ceiling, 0);
+    assert_eq!(ceiling.space_below_ceiling, 2);
+
+    let air = column.context(0, 3, 0);
+    assert!(!air.solid);
+    assert_eq!(air.space_above_floor, 0);
+    assert_eq!(air.space_below_ceiling, 1);
+}
+
+#[test]
+fn queue_takes_first_match() {
+    let provider = json!({
+        "Type": "Queue",
+        "Queue": [
+            { "Type": "Solidity", "Empty": constant("Water") },
+            { "Type": "SimpleHorizontal", "BottomY": 5, "Material": constant("Snow") },
+            constant("Rock_Stone")
+        ]
+    });
+    let evaluator = MaterialEvaluator::from_json(&provider, &MaterialScope::default()).unwrap();
+    let column = test_column();
+
+    let picks: Vec<_> = (0..7)
+        .map(|y| evaluator.pick_in_column(&column, 0, y, 0, None))
+        .collect();
+    assert_eq!(
+        picks,
+        vec![
+            Some("Rock_Stone"),
+            Some("Rock_Stone"),
+            Some("Rock_Stone"),
+            Some("Water"),
+            Some("Water"),
+            Some("Snow"),
+            Some("Snow"),
+        ]
+    );
+}
+
+#[test]
+fn weighted_is_seeded_and_respects_weights() {
+    let provider = |seed: &str, skip: f64| {
+        json!({
+            "Type": "Weighted",
+            "Seed": seed,
+            "SkipChance": skip,
+            "WeightedMaterials": [
+                { "Weight": 0.1, "Material": constant("Soil_Grass") },
+                { "Weight": 0.9, "Material": constant("Rock_Stone") }
+            ]
+        })
+    };
+    let scope = MaterialScope::default();
+    let a = MaterialEvaluator::from_json(&provider("A", 0.0), &scope).unwrap();
+    let a_again = MaterialEvaluator::from_json(&provider("A", 0.0), &scope).unwrap();
+    let column = SolidityColumn::new(0, vec![true; 1]);
+
+    let mut grass = 0;
+    for x in 0..100 {
+        for z in 0..100 {
+            let pick = a.pick_in_column(&column, x, 0, z, None);
+            assert_eq!(pick, a_again.pick_in_column(&column, x, 0, z, None));
+            if pick == Some("Soil_Grass") {
+                grass += 1;
+            }
+        }
+    }
+    assert!((500..1500).contains(&grass), "grass count {}", grass);
+
+    let skipped = MaterialEvaluator::from_json(&provider("A", 1.0), &scope).unwrap();
+    assert_eq!(skipped.pick_in_column(&column, 3, 0, 7, None), None);
+}
+
+#[test]
+fn space_and_depth_layers_through_imports() {
+    let layered = json!({
+        "Type": "SpaceAndDepth",
+        "LayerContext": "DEPTH_INTO_FLOOR",
+        "Layers": [
+            { "Type": "ConstantThickness", "Thickness": 1, "Material": constant("Soil_Grass") },
+            { "Type": "ConstantThickness", "Thickness": 2, "Material": constant("Soil_Dirt") }
+        ]
+    });
+    let mut scope = MaterialScope::default();
+    scope.exports.insert("Surface".into(), layered);
+
+    let provider = json!({
+        "Type": "Queue",
+        "Queue": [{ "Type": "Imported", "Name": "Surface" }, constant("Rock_Stone")]
+    });
+    let evaluator = MaterialEvaluator::from_json(&provider, &scope).unwrap();
+    let column = SolidityColumn::new(0, vec![true; 5]);
+
+    let picks: Vec<_> = (0..5)
+        .rev()
+        .map(|y| evaluator.pick_in_column(&column, 0, y, 0, None).unwrap())
+        .collect();
+    assert_eq!(
+        picks,
+        vec![
+            "Soil_Grass",
+            "Soil_Dirt",
+            "Soil_Dirt",
+            "Rock_Stone",
+            "Rock_Stone"
+        ]
+    );
+
+    let mut cyclic = MaterialScope::default();
+    cyclic
+        .exports
+        .insert("Loop".into(), json!({ "Type": "Imported", "Name": "Loop" }));
+    let looped = json!({ "Type": "Imported", "Name": "Loop" });
+    assert!(MaterialEvaluator::from_json(&looped, &cyclic).is_err());
+}
+
+#[test]
+fn condition_tree_reads_column_spaces() {
+    let condition = Condition::from_json(
+        Some(&json!({
+            "Type": "AndCondition",
+            "Conditions": [
+                { "Type": "GreaterThanCondition", "ContextToCheck": "SPACE_ABOVE_FLOOR", "Threshold": 1 },
+                {
+                    "Type": "NotCondition",
+                    "Condition": { "Type": "EqualsCondition", "ContextToCheck": "SPACE_BELOW_CEILING", "Value": 0 }
+                }
+            ]
+        })),
+        "$",
+    )
+    .unwrap();
+    let column = test_column();
+
+    // Lower surface: 2 blocks of air above, but nothing below the run
+    assert!(!condition.test(&column.context(0, 2, 0)));
+    // Upper run: 2 blocks of air below, column top above
+    assert!(!condition.test(&column.context(0, 6, 0)));
+
+    let open = SolidityColumn::new(0, vec![false, true, true, false, false, false]);
+    assert!(condition.test(&open.context(0, 2, 0)));
+
+    let bad = json!({ "Type": "SmallerThanCondition", "ContextToCheck": "DEPTH", "Threshold": 1 });
+    assert!(Condition::from_json(Some(&bad), "$").is_err());
+}
+
+#[test]
+fn layer_stack_reports_thickness_and_placement() {
+    let provider = json!({
+        "Type": "SpaceAndDepth",
+        "MaxExpectedDepth": 6,
+        "Condition": { "Type": "GreaterThanCondition", "ContextToCheck": "SPACE_ABOVE_FLOOR", "Threshold": 1 },
+        "Layers": [
+            {
+                "Type": "WeightedThickness",
+                "Seed": "grass",
+                "PossibleThicknesses": [{ "Weight": 1, "Thickness": 3 }],
+                "Material": constant("Soil_Grass")
+            },
+            { "Type": "RangeThickness", "RangeMin": 2, "RangeMax": 4, "Seed": "A", "Material": constant("Soil_Dirt") },
+            { "Type": "ConstantThickness", "Thickness": 10, "Material": constant("Rock_Stone") }
+        ]
+    });
+    let sad = parse_space_and_depth(&provider, &MaterialScope::default()).unwrap();
+
+    for x in 0..32 {
+        let spans = sad.stack(x, 7);
+        assert_eq!(spans[0].thickness, 3);
+        assert!((2..=4).contains(&spans[1].thickness));
+        // The last layer is cut at MaxExpectedDepth
+        assert_eq!(spans.last().unwrap().depth_end, 6);
+    }
+
+    let column = test_column();
+    let stacks = column_stacks(&sad, &column, 0, 0, None);
+    assert_eq!(
+        stacks.iter().map(|s| s.surface_y).collect::<Vec<_>>(),
+        vec![6, 2]
+    );
+
+    let lower = &stacks[1];
+    assert!(lower.condition_met);
+    assert_eq!(lower.layers[0].blocks, 3);
+    assert_eq!(lower.layers[0].y_start, Some(2));
+    assert_eq!(lower.layers[0].material.as_deref(), Some("Soil_Grass"));
+    assert_eq!(lower.layers[1].blocks, 0);
+}
+
+#[test]
+fn striped_applies_within_inclusive_stripes() {
+    let provider = json!({
+        "Type": "Striped",
+        "Stripes": [{ "TopY": 1, "BottomY": 0 }, { "TopY": 5, "BottomY": 5 }],
+        "Material": constant("Rock_Sandstone")
+    });
+    let evaluator = MaterialEvaluator::from_json(&provider, &MaterialScope::default()).unwrap();
+    let column = test_column();
+
+    let striped: Vec<_> = (0..7)
+        .filter(|&y| evaluator.pick_in_column(&column, 0, y, 0, None).is_some())
+        .collect();
+    assert_eq!(striped, vec![0, 1, 5]);
+}
+
+#[test]
+fn field_function_picks_by_delimited_value() {
+    let provider = json!({
+        "Type": "FieldFunction",
+        "FieldFunction": { "Type": "XValue" },
+        "Delimiters": [
+            { "To": 0.0, "Material": constant("Rock_Basalt") },
+            { "From": 0.0, "To": 10.0, "Material": constant("Rock_Stone") }
+        ]
+    });
+    let evaluator = MaterialEvaluator::from_json(&provider, &MaterialScope::default()).unwrap();
+    let column = test_column();
+
+    assert_eq!(
+        evaluator.pick_in_column(&column, -3, 0, 0, None),
+        Some("Rock_Basalt")
+    );
+    // Ranges include From and exclude To
+    assert_eq!(
+        evaluator.pick_in_column(&column, 0, 0, 0, None),
+        Some("Rock_Stone")
+    );
+    assert_eq!(evaluator.pick_in_column(&column, 10, 0, 0, None), None);
+}
+
+#[test]
+fn depth_based_maps_floor_depth_through_curve() {
+    let materials = json!([
+        { "To": 1.0, "Material": constant("Soil_Grass") },
+        { "From": 1.0, "Material": constant("Rock_Stone") }
+    ]);
+    let column = test_column();
+
+    let plain = json!({ "Type": "DepthBased", "Materials": materials });
+    let evaluator = MaterialEvaluator::from_json(&plain, &MaterialScope::default()).unwrap();
+    let picks: Vec<_> = (0..7)
+        .map(|y| evaluator.pick_in_column(&column, 0, y, 0, None))
+        .collect();
+    assert_eq!(
+        picks,
+        vec![
+            Some("Rock_Stone"),
+            Some("Rock_Stone"),
+            Some("Soil_Grass"),
+            None,
+            None,
+            Some("Rock_Stone"),
+            Some("Soil_Grass"),
+        ]
+    );
+
+    // The curve halves the depth, so the grass reaches one block deeper
+    let curved = json!({
+        "Type": "DepthBased",
+        "DepthCurve": { "Points": [[0.0, 0.0], [4.0, 2.0]] },
+        "Materials": materials
+    });
+    let evaluator = MaterialEvaluator::from_json(&curved, &MaterialScope::default()).unwrap();
+    assert_eq!(
+        evaluator.pick_in_column(&column, 0, 1, 0, None),
+        Some("Soil_Grass")
+    );
+    assert_eq!(
+        evaluator.pick_in_column(&column, 0, 0, 0, None),
+        Some("Rock_Stone")
+    );
+}
+
+#[test]
+fn gradient_based_picks_by_slope() {
+    let provider = |gradient: serde_json::Value| {
+        json!({
+            "Type": "GradientBased",
+            "Gradient": gradient,
+            "Materials": [
+                { "To": 0.5, "Material": constant("Soil_Grass") },
+                { "From": 0.5, "Material": constant("Rock_Stone") }
+            ]
+        })
+    };
+    let scope = MaterialScope::default();
+    let column = test_column();
+
+    let flat = MaterialEvaluator::from_json(
+        &provider(json!({ "Type": "Constant", "Value": 1.0 })),
+        &scope,
+    )
+    .unwrap();
+    assert_eq!(
+        flat.pick_in_column(&column, 4, 0, 4, None),
+        Some("Soil_Grass")
+    );
+
+    let steep =
+        MaterialEvaluator::from_json(&provider(json!({ "Type": "XValue" })), &scope).unwrap();
+    assert_eq!(
+        steep.pick_in_column(&column, 4, 0, 4, None),
+        Some("Rock_Stone")
+    );
+}
+
+#[test]
+fn switch_selects_case_by_biome() {
+    let provider = json!({
+        "Type": "Switch",
+        "SwitchCases": [
+            { "CaseState": "Desert", "Material": constant("Soil_Sand") },
+            { "CaseState": "Default", "Material": constant("Soil_Grass") }
+        ]
+    });
+    let evaluator = MaterialEvaluator::from_json(&provider, &MaterialScope::default()).unwrap();
+    let column = test_column();
+
+    assert_eq!(
+        evaluator.pick_in_column(&column, 0, 0, 0, Some("Desert")),
+        Some("Soil_Sand")
+    );
+    assert_eq!(
+        evaluator.pick_in_column(&column, 0, 0, 0, Some("Forest")),
+        Some("Soil_Grass")
+    );
+    assert_eq!(
+        evaluator.pick_in_column(&column, 0, 0, 0, None),
+        Some("Soil_Grass")
+    );
+}
+
+#[test]
+fn pipeline_later_steps_override_earlier_ones() {
+    let provider = json!({
+        "Type": "Pipeline",
+        "Steps": [
+            constant("Rock_Stone"),
+            { "Type": "SimpleHorizontal", "BottomY": 5, "Material": constant("Snow") },
+            { "Type": "Solidity", "Empty": { "Type": "Constant" } }
+        ]
+    });
+    let evaluator = MaterialEvaluator::from_json(&provider, &MaterialScope::default()).unwrap();
+    let column = test_column();
+
+    let picks: Vec<_> = (0..7)
+        .map(|y| evaluator.pick_in_column(&column, 0, y, 0, None))
+        .collect();
+    // A step that picks nothing leaves the earlier steps' choice in place
+    assert_eq!(
+        picks,
+        vec![
+            Some("Rock_Stone"),
+            Some("Rock_Stone"),
+            Some("Rock_Stone"),
+            Some("Rock_Stone"),
+            Some("Rock_Stone"),
+            Some("Snow"),
+            Some("Snow"),
+        ]
+    );
+}
+
+#[test]
+fn layer_stack_materials_see_biome_and_base_heights() {
+    let provider = json!({
+        "Type": "SpaceAndDepth",
+        "Layers": [
+            {
+                "Type": "ConstantThickness",
+                "Thickness": 1,
+                "Material": {
+                    "Type": "Switch",
+                    "SwitchCases": [
+                        { "CaseState": "Desert", "Material": constant("Soil_Sand") },
+                        { "CaseState": "Default", "Material": constant("Soil_Grass") }
+                    ]
+                }
+            },
+            {
+                "Type": "ConstantThickness",
+                "Thickness": 2,
+                "Material": {
+                    "Type": "SimpleHorizontal",
+                    "BottomY": 0,
+                    "BottomBaseHeight": "Sea",
+                    "Material": constant("Rock_Stone")
+                }
+            }
+        ]
+    });
+    let mut scope = MaterialScope::default();
+    scope.base_heights.insert("Sea".into(), 5.0);
+    let sad = parse_space_and_depth(&provider, &scope).unwrap();
+    let column = test_column();
+
+    let desert = column_stacks(&sad, &column, 0, 0, Some("Desert"));
+    assert_eq!(desert[0].layers[0].material.as_deref(), Some("Soil_Sand"));
+    // The upper run's second layer sits at Y 5, the lower run's below the sea base height
+    assert_eq!(desert[0].layers[1].material.as_deref(), Some("Rock_Stone"));
+    assert_eq!(desert[1].layers[1].material, None);
+
+    let other = column_stacks(&sad, &column, 0, 0, None);
+    assert_eq!(other[0].layers[0].material.as_deref(), Some("Soil_Grass"));
+}
@@ -1,37 +1,159 @@
 use serde_json::Value;
 
-use super::nodes::NodeEval;
+use crate::placement::positions::{parse_positions, PositionProvider, PositionScope};
+
+use super::nodes::{CellReturn, NodeEval};
+use super::profiler::{NodeProfile, ProfiledNode, Profiler};
+
+/// JSON path of the graph root, used as the prefix for node paths.
+pub const ROOT_PATH: &str = "$";
 
 /// Density function evaluator.
 /// Parses a V2 density graph JSON and evaluates it at (x, y, z) coordinates.
 pub struct DensityEvaluator {
     root: Box<dyn NodeEval>,
+    profiler: Option<Profiler>,
+    /// `Type at path` of every node evaluated as zero because its type is
+    /// not implemented
+    unsupported: Vec<String>,
 }
 
 impl DensityEvaluator {
     /// Parse a V2 density function JSON into an evaluable graph.
     pub fn from_json(json: &Value) -> Result<Self, String> {
-        let root = parse_node(json)?;
-        Ok(DensityEvaluator { root })
+        Self::from_json_with_positions(json, &PositionScope::default())
+    }
+
+    /// Parse a graph whose positions-driven nodes resolve `Imported` and
+    /// `Framework` position providers against `scope`.
+    pub fn from_json_with_positions(json: &Value, scope: &PositionScope) -> Result<Self, String> {
+        Self::from_json_in_scope(json, scope, &mut Vec::new())
+    }
+
+    /// Like `from_json_with_positions`, continuing the import stack of a
+    /// position provider the graph is nested in.
+    pub(crate) fn from_json_in_scope(
+        json: &Value,
+        scope: &PositionScope,
+        imports: &mut Vec<String>,
+    ) -> Result<Self, String> {
+        let mut positions = scope.clone();
+        positions.collect_exports(json);
+        let mut ctx = ParseContext {
+            positions,
+            position_imports: std::mem::take(imports),
+            ..Default::default()
+        };
+        let root = parse_node(json, ROOT_PATH, &mut ctx);
+        *imports = ctx.position_imports;
+        Ok(DensityEvaluator {
+            root: root?,
+            profiler: None,
+            unsupported: ctx.unsupported,
+        })
+    }
+
+    /// Parse a graph with every node instrumented to record call counts and timings.
+    pub fn from_json_profiled(json: &Value) -> Result<Self, String> {
+        let mut positions = PositionScope::default();
+        positions.collect_exports(json);
+        let mut ctx = ParseContext {
+            profiler: Some(Profiler::default()),
+            positions,
+            ..Default::default()
+        };
+        let root = parse_node(json, ROOT_PATH, &mut ctx)?;
+        Ok(DensityEvaluator {
+            root,
+            profiler: ctx.profiler,
+            unsupported: ctx.unsupported,
+        })
     }
 
     /// Evaluate the density function at a world position.
     pub fn evaluate(&self, x: f64, y: f64, z: f64) -> f64 {
         self.root.eval(x, y, z)
     }
+
+    /// Evaluate the density and its gradient at a world position, using
+    /// analytic derivatives where nodes support them and central differences
+    /// with step `h` otherwise.
+    pub fn evaluate_gradient(&self, x: f64, y: f64, z: f64, h: f64) -> (f64, [f64; 3]) {
+        self.root.eval_gradient(x, y, z, h)
+    }
+
+    /// Nodes of a type this evaluator does not implement, as `Type at path`.
+    /// They evaluate as zero.
+    pub fn unsupported_nodes(&self) -> &[String] {
+        &self.unsupported
+    }
+
+    /// Per-node cost report ranked by self time, if this evaluator was built
+    /// with `from_json_profiled`. `samples` is the number of root evaluations.
+    pub fn profile_report(&self, samples: usize) -> Option<Vec<NodeProfile>> {
+        self.profiler.as_ref().map(|p| p.report(samples))
+    }
 }
 
-/// Recursively parse a JSON node into an evaluable node.
-fn parse_node(json: &Value) -> Result<Box<dyn NodeEval>, String> {
+/// State threaded through graph parsing.
+#[derive(Default)]
+struct ParseContext {
+    /// Present when parsing in profiling mode
+    profiler: Option<Profiler>,
+    /// Slot indices of the nodes currently being parsed (innermost last)
+    stack: Vec<usize>,
+    /// Position exports and framework entries for positions-driven nodes
+    positions: PositionScope,
+    /// Position imports being resolved, shared with enclosing providers
+    position_imports: Vec<String>,
+    /// Nodes of unknown type, as `Type at path`
+    unsupported: Vec<String>,
+}
+
+/// Search radius for positions-driven nodes without a `MaxDistance`.
+pub(crate) const DEFAULT_CELL_DISTANCE: f64 = 64.0;
+
+/// Recursively parse a JSON node into an evaluable node, wrapping it in a
+/// `ProfiledNode` when profiling.
+fn parse_node(
+    json: &Value,
+    path: &str,
+    ctx: &mut ParseContext,
+) -> Result<Box<dyn NodeEval>, String> {
     let obj = json
         .as_object()
-        .ok_or("Density node must be a JSON object")?;
+        .ok_or_else(|| format!("Density node at {} must be a JSON object", path))?;
 
     let node_type = obj
         .get("Type")
         .and_then(|v| v.as_str())
-        .ok_or("Missing 'Type' field")?;
+        .ok_or_else(|| format!("Missing 'Type' field at {}", path))?;
+
+    let Some(profiler) = ctx.profiler.as_mut() else {
+        return parse_typed(obj, node_type, path, ctx);
+    };
 
+    let parent = ctx.stack.last().copied();
+    let index = profiler.register(path.to_string(), node_type.to_string(), parent);
+    ctx.stack.push(index);
+    let inner = parse_typed(obj, node_type, path, ctx);
+    ctx.stack.pop();
+
+    let slot = ctx
+        .profiler
+        .as_ref()
+        .map(|p| p.slot(index))
+        .ok_or("Profiler missing while parsing")?;
+    Ok(Box::new(ProfiledNode { inner: inner?, slot }))
+}
+
+/// Build the evaluable node for a known `Type`.
+fn parse_typed(
+    obj: &serde_json::Map<String, Value>,
+    node_type: &str,
+    path: &str,
+    ctx: &mut ParseContext,
+) -> Result<Box<dyn NodeEval>, String> {
     match node_type {
         "Constant" => {
             let value = obj
@@ -53,12 +175,31 @@ fn parse_node(json: &Value) -> Result<Box<dyn NodeEval>, String> {
         }
 
         "Sum" => {
-            let inputs = parse_inputs(obj)?;
+            let inputs = parse_inputs(obj, path, ctx)?;
             Ok(Box::new(super::nodes::SumNode { inputs }))
         }
 
+        "Multiplier" => {
+            let inputs = parse_inputs(obj, path, ctx)?;
+            Ok(Box::new(super::nodes::ProductNode { inputs }))
+        }
+
+        "Inverter" => {
+            let input = parse_single_input(obj, path, ctx)?;
+            Ok(Box::new(super::nodes::NegateNode { input }))
+        }
+
+        "XValue" | "YValue" | "ZValue" => {
+            let axis = match node_type {
+                "XValue" => 0,
+                "YValue" => 1,
+                _ => 2,
+            };
+            Ok(Box::new(super::nodes::CoordinateNode { axis }))
+        }
+
         "Clamp" => {
-            let input = parse_single_input(obj)?;
+            let input = parse_single_input(obj, path, ctx)?;
             let wall_a = obj.get("WallA").and_then(|v| v.as_f64()).unwrap_or(0.0);
             let wall_b = obj.get("WallB").and_then(|v| v.as_f64()).unwrap_or(1.0);
             Ok(Box::new(super::nodes::ClampNode {
@@ -69,7 +210,7 @@ fn parse_node(json: &Value) -> Result<Box<dyn NodeEval>, String> {
         }
 
         "Normalizer" => {
-            let input = parse_single_input(obj)?;
+            let input = parse_single_input(obj, path, ctx)?;
             let from_min = obj.get("FromMin").and_then(|v| v.as_f64()).unwrap_or(-1.0);
             let from_max = obj.get("FromMax").and_then(|v| v.as_f64()).unwrap_or(1.0);
             let to_min = obj.get("ToMin").and_then(|v| v.as_f64()).unwrap_or(0.0);
@@ -83,16 +224,54 @@ fn parse_node(json: &Value) -> Result<Box<dyn NodeEval>, String> {
             }))
         }
 
+        "PositionsCellNoise" | "CellWallDistance" => {
+            let positions = match obj.get("Positions").filter(|v| v.is_object()) {
+                Some(p) => parse_positions(
+                    p,
+                    &format!("{}.Positions", path),
+                    &ctx.positions,
+                    &mut ctx.position_imports,
+                )?,
+                None => PositionProvider::List(Vec::new()),
+            };
+            let max_distance = obj
+                .get("MaxDistance")
+                .and_then(|v| v.as_f64())
+                .filter(|d| *d > 0.0)
+                .unwrap_or(DEFAULT_CELL_DISTANCE);
+            let return_type = if node_type == "CellWallDistance" {
+                CellReturn::WallDistance
+            } else {
+                CellReturn::from_name(type_name(obj.get("ReturnType")).unwrap_or(""))
+            };
+            let manhattan = type_name(obj.get("DistanceFunction")) == Some("Manhattan");
+            Ok(Box::new(super::nodes::PositionsCellNode::new(
+                positions,
+                max_distance,
+                manhattan,
+                return_type,
+            )))
+        }
+
         _ => {
             // Unknown types evaluate as zero
+            ctx.unsupported.push(format!("{} at {}", node_type, path));
             Ok(Box::new(super::nodes::ConstantNode { value: 0.0 }))
         }
     }
 }
 
+/// A nested type given either as a string or as `{ "Type": ... }`.
+fn type_name(json: Option<&Value>) -> Option<&str> {
+    let json = json?;
+    json.as_str().or_else(|| json.get("Type")?.as_str())
+}
+
 /// Parse the "Inputs" array from a node object.
 fn parse_inputs(
     obj: &serde_json::Map<String, Value>,
+    path: &str,
+    ctx: &mut ParseContext,
 ) -> Result<Vec<Box<dyn NodeEval>>, String> {
     let inputs_arr = obj
         .get("Inputs")
@@ -101,16 +280,22 @@ fn parse_inputs(
         .unwrap_or_default();
 
     let mut nodes = Vec::new();
-    for input_json in &inputs_arr {
-        nodes.push(parse_node(input_json)?);
+    for (i, input_json) in inputs_arr.iter().enumerate() {
+        nodes.push(parse_node(input_json, &format!("{}.Inputs[{}]", path, i), ctx)?);
     }
     Ok(nodes)
 }
 
-/// Parse the first element of "Inputs" as a single input.
+/// Parse the single "Input" of a node, falling back to the first element of "Inputs".
 fn parse_single_input(
     obj: &serde_json::Map<String, Value>,
+    path: &str,
+    ctx: &mut ParseContext,
 ) -> Result<Box<dyn NodeEval>, String> {
+    if let Some(input) = obj.get("Input").filter(|v| v.is_object()) {
+        return parse_node(input, &format!("{}.Input", path), ctx);
+    }
+
     let inputs_arr = obj
         .get("Inputs")
         .and_then(|v| v.as_array())
@@ -118,7 +303,7 @@ fn parse_single_input(
         .unwrap_or_default();
 
     if let Some(first) = inputs_arr.first() {
-        parse_node(first)
+        parse_node(first, &format!("{}.Inputs[0]", path), ctx)
     } else {
         Ok(Box::new(super::nodes::ConstantNode { value: 0.0 }))
     }
@@ -0,0 +1,19 @@
+/// Integer seed for a string seed (Java-style `31 * h + byte` hash).
+pub fn seed_hash(seed: &str) -> i32 {
+    seed.bytes()
+        .fold(0i32, |acc, b| acc.wrapping_mul(31).wrapping_add(b as i32))
+}
+
+/// Deterministic pseudo-random value in [0, 1) for a block position and seed.
+pub fn position_random(x: i32, y: i32, z: i32, seed: i32) -> f64 {
+    let mut h = (x as u32)
+        .wrapping_mul(374_761_393)
+        .wrapping_add((y as u32).wrapping_mul(668_265_263))
+        .wrapping_add((z as u32).wrapping_mul(1_103_515_245))
+        ^ (seed as u32);
+    // Avalanche so neighbouring positions don't produce correlated values
+    h = (h ^ (h >> 15)).wrapping_mul(0x2c1b_3c6d);
+    h = (h ^ (h >> 12)).wrapping_mul(0x297a_2d39);
+    h ^= h >> 15;
+    h as f64 / 4_294_967_296.0
+}
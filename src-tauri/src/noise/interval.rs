@@ -0,0 +1,791 @@
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+use crate::schema::curves::CurveType;
+use crate::schema::density::DensityType;
+
+use super::evaluator::{DEFAULT_CELL_DISTANCE, ROOT_PATH};
+use super::nodes::CellReturn;
+
+/// A closed range of values; either end may be infinite.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Interval {
+    pub min: f64,
+    pub max: f64,
+}
+
+impl Interval {
+    pub const UNBOUNDED: Interval = Interval {
+        min: f64::NEG_INFINITY,
+        max: f64::INFINITY,
+    };
+
+    /// Range of noise generators.
+    pub const UNIT: Interval = Interval {
+        min: -1.0,
+        max: 1.0,
+    };
+
+    pub fn new(a: f64, b: f64) -> Self {
+        Interval {
+            min: a.min(b),
+            max: a.max(b),
+        }
+    }
+
+    pub fn point(value: f64) -> Self {
+        Interval {
+            min: value,
+            max: value,
+        }
+    }
+
+    pub fn is_bounded(&self) -> bool {
+        self.min.is_finite() && self.max.is_finite()
+    }
+
+    pub fn contains(&self, other: &Interval) -> bool {
+        self.min <= other.min && other.max <= self.max
+    }
+
+    pub fn hull(self, other: Interval) -> Self {
+        Interval {
+            min: self.min.min(other.min),
+            max: self.max.max(other.max),
+        }
+    }
+
+    pub fn add(self, other: Interval) -> Self {
+        Interval {
+            min: self.min + other.min,
+            max: self.max + other.max,
+        }
+    }
+
+    pub fn neg(self) -> Self {
+        Interval {
+            min: -self.max,
+            max: -self.min,
+        }
+    }
+
+    pub fn mul(self, other: Interval) -> Self {
+        let products = [
+            safe_mul(self.min, other.min),
+            safe_mul(self.min, other.max),
+            safe_mul(self.max, other.min),
+            safe_mul(self.max, other.max),
+        ];
+        Interval {
+            min: products.iter().copied().fold(f64::INFINITY, f64::min),
+            max: products.iter().copied().fold(f64::NEG_INFINITY, f64::max),
+        }
+    }
+
+    pub fn abs(self) -> Self {
+        if self.min >= 0.0 {
+            self
+        } else if self.max <= 0.0 {
+            self.neg()
+        } else {
+            Interval {
+                min: 0.0,
+                max: (-self.min).max(self.max),
+            }
+        }
+    }
+
+    /// Largest absolute value in the interval.
+    pub fn magnitude(&self) -> f64 {
+        self.min.abs().max(self.max.abs())
+    }
+
+    pub fn max_with(self, other: Interval) -> Self {
+        Interval {
+            min: self.min.max(other.min),
+            max: self.max.max(other.max),
+        }
+    }
+
+    pub fn min_with(self, other: Interval) -> Self {
+        Interval {
+            min: self.min.min(other.min),
+            max: self.max.min(other.max),
+        }
+    }
+
+    pub fn clamp(self, a: f64, b: f64) -> Self {
+        let (lo, hi) = (a.min(b), a.max(b));
+        Interval {
+            min: self.min.clamp(lo, hi),
+            max: self.max.clamp(lo, hi),
+        }
+    }
+
+    /// Widen both ends by `amount` (used for smooth min/max variants).
+    pub fn widen(self, amount: f64) -> Self {
+        let amount = amount.abs();
+        Interval {
+            min: self.min - amount,
+            max: self.max + amount,
+        }
+    }
+
+    /// Map through the linear function taking `from` onto `to`.
+    pub fn remap(self, from_min: f64, from_max: f64, to_min: f64, to_max: f64) -> Self {
+        let from_range = from_max - from_min;
+        if from_range.abs() < f64::EPSILON {
+            return Interval::point(to_min);
+        }
+        let scale = (to_max - to_min) / from_range;
+        self.add(Interval::point(-from_min))
+            .mul(Interval::point(scale))
+            .add(Interval::point(to_min))
+    }
+}
+
+/// Multiplication where 0 × ∞ is 0 rather than NaN.
+fn safe_mul(a: f64, b: f64) -> f64 {
+    if a == 0.0 || b == 0.0 {
+        0.0
+    } else {
+        a * b
+    }
+}
+
+/// World-space box the graph is analysed over. Coordinate accessors read
+/// their range from here; transformed subtrees fall back to unbounded.
+#[derive(Debug, Clone, Copy)]
+pub struct CoordBounds {
+    pub x: Interval,
+    pub y: Interval,
+    pub z: Interval,
+}
+
+impl Default for CoordBounds {
+    fn default() -> Self {
+        CoordBounds {
+            x: Interval::UNBOUNDED,
+            y: Interval::UNBOUNDED,
+            z: Interval::UNBOUNDED,
+        }
+    }
+}
+
+/// Guaranteed output range of one node. `None` means unbounded on that side.
+#[derive(Debug, Clone, Serialize)]
+pub struct NodeRange {
+    pub path: String,
+    pub node_type: String,
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+}
+
+/// A node whose configured bounds don't match the analysed input range.
+#[derive(Debug, Clone, Serialize)]
+pub struct RangeWarning {
+    pub path: String,
+    pub node_type: String,
+    pub message: String,
+}
+
+/// Result of a static range analysis over a density graph.
+#[derive(Debug, Clone, Serialize)]
+pub struct RangeReport {
+    /// Guaranteed range of the graph root
+    pub root: NodeRange,
+    /// Guaranteed range of every node, in graph order
+    pub nodes: Vec<NodeRange>,
+    pub warnings: Vec<RangeWarning>,
+}
+
+/// Propagate conservative value intervals through a density graph.
+pub fn analyze(json: &Value, bounds: CoordBounds) -> RangeReport {
+    let mut analyzer = RangeAnalyzer::default();
+    let root = analyzer.node(json, ROOT_PATH, bounds);
+    let root = NodeRange {
+        path: ROOT_PATH.to_string(),
+        node_type: json
+            .get("Type")
+            .and_then(|v| v.as_str())
+            .unwrap_or_default()
+            .to_string(),
+        min: finite(root.min),
+        max: finite(root.max),
+    };
+    RangeReport {
+        root,
+        nodes: analyzer.nodes,
+        warnings: analyzer.warnings,
+    }
+}
+
+fn finite(v: f64) -> Option<f64> {
+    v.is_finite().then_some(v)
+}
+
+/// Keys whose values are child densities, analysed on their own.
+const CHILD_KEYS: [&str; 5] = ["Input", "Offset", "Amplitude", "Override", "Density"];
+
+/// A node with its child densities replaced by empty objects, so typing a
+/// node doesn't deserialize the subtree below it again.
+fn shallow(obj: &Map<String, Value>) -> Value {
+    let placeholder = || Value::Object(Map::new());
+    let node = obj
+        .iter()
+        .map(|(key, value)| {
+            let value = match (key.as_str(), value) {
+                (key, Value::Object(_)) if CHILD_KEYS.contains(&key) => placeholder(),
+                ("Inputs", Value::Array(items)) => {
+                    Value::Array(items.iter().map(|_| placeholder()).collect())
+                }
+                ("SwitchCases", Value::Array(cases)) => Value::Array(
+                    cases
+                        .iter()
+                        .map(|case| match case {
+                            Value::Object(case) => Value::Object(
+                                case.iter()
+                                    .map(|(k, v)| match (k.as_str(), v) {
+                                        ("Density", Value::Object(_)) => (k.clone(), placeholder()),
+                                        _ => (k.clone(), v.clone()),
+                                    })
+                                    .collect(),
+                            ),
+                            other => other.clone(),
+                        })
+                        .collect(),
+                ),
+                _ => value.clone(),
+            };
+            (key.clone(), value)
+        })
+        .collect();
+    Value::Object(node)
+}
+
+#[derive(Default)]
+struct RangeAnalyzer {
+    nodes: Vec<NodeRange>,
+    warnings: Vec<RangeWarning>,
+}
+
+impl RangeAnalyzer {
+    /// Analyse a node and record its range.
+    fn node(&mut self, json: &Value, path: &str, bounds: CoordBounds) -> Interval {
+        let Some(obj) = json.as_object() else {
+            return Interval::UNBOUNDED;
+        };
+        let node_type = obj
+            .get("Type")
+            .and_then(|v| v.as_str())
+            .unwrap_or_default()
+            .to_string();
+
+        // Reserve the slot first so nodes are listed parent-before-child
+        let index = self.nodes.len();
+        self.nodes.push(NodeRange {
+            path: path.to_string(),
+            node_type: node_type.clone(),
+            min: None,
+            max: None,
+        });
+
+        let range = match serde_json::from_value::<DensityType>(shallow(obj)) {
+            Ok(density) => self.typed(&density, obj, &node_type, path, bounds),
+            // Fields that don't match the schema (e.g. numeric seeds) still
+            // leave noise generators within their documented range
+            Err(_) if node_type.contains("Noise") => Interval::UNIT,
+            Err(_) => Interval::UNBOUNDED,
+        };
+
+        self.nodes[index].min = finite(range.min);
+        self.nodes[index].max = finite(range.max);
+        range
+    }
+
+    /// Analyse the single input of a node ("Input", falling back to "Inputs"[0]).
+    fn input(&mut self, obj: &Map<String, Value>, path: &str, bounds: CoordBounds) -> Interval {
+        if let Some(input) = obj.get("Input").filter(|v| v.is_object()) {
+            return self.node(input, &format!("{}.Input", path), bounds);
+        }
+        match obj
+            .get("Inputs")
+            .and_then(|v| v.as_array())
+            .and_then(|a| a.first())
+        {
+            Some(first) => self.node(first, &format!("{}.Inputs[0]", path), bounds),
+            // A missing input evaluates as zero
+            None => Interval::point(0.0),
+        }
+    }
+
+    fn inputs(
+        &mut self,
+        obj: &Map<String, Value>,
+        path: &str,
+        bounds: CoordBounds,
+    ) -> Vec<Interval> {
+        let inputs = obj.get("Inputs").and_then(|v| v.as_array());
+        inputs
+            .into_iter()
+            .flatten()
+            .enumerate()
+            .map(|(i, v)| self.node(v, &format!("{}.Inputs[{}]", path, i), bounds))
+            .collect()
+    }
+
+    fn field(
+        &mut self,
+        obj: &Map<String, Value>,
+        path: &str,
+        key: &str,
+        bounds: CoordBounds,
+    ) -> Interval {
+        match obj.get(key) {
+            Some(v) if v.is_object() => self.node(v, &format!("{}.{}", path, key), bounds),
+            _ => Interval::UNBOUNDED,
+        }
+    }
+
+    fn typed(
+        &mut self,
+        density: &DensityType,
+        obj: &Map<String, Value>,
+        node_type: &str,
+        path: &str,
+        bounds: CoordBounds,
+    ) -> Interval {
+        use DensityType as D;
+
+        // Subtrees under a coordinate transform see different coordinates
+        let transformed = CoordBounds::default();
+
+        match density {
+            D::SimplexNoise2D { .. } | D::SimplexNoise3D { .. } => Interval::UNIT,
+            D::CellNoise2D { return_type, .. } | D::CellNoise3D { return_type, .. } => {
+                match return_type.as_deref() {
+                    None | Some("CellValue") => Interval::UNIT,
+                    Some(_) => Interval::new(0.0, f64::INFINITY),
+                }
+            }
+
+            D::Constant { value } => Interval::point(value.unwrap_or(0.0)),
+            D::Sum { .. } => self
+                .inputs(obj, path, bounds)
+                .into_iter()
+                .fold(Interval::point(0.0), Interval::add),
+            D::Multiplier { .. } => self
+                .inputs(obj, path, bounds)
+                .into_iter()
+                .fold(Interval::point(1.0), Interval::mul),
+            D::Abs { .. } => self.input(obj, path, bounds).abs(),
+            D::Inverter { .. } => self.input(obj, path, bounds).neg(),
+            D::Sqrt { .. } => {
+                let i = self.input(obj, path, bounds);
+                if i.min >= 0.0 {
+                    Interval::new(i.min.sqrt(), i.max.sqrt())
+                } else {
+                    // Negative inputs are mirrored, so bound by the magnitude
+                    let m = i.magnitude().sqrt();
+                    Interval::new(-m, m)
+                }
+            }
+            D::Pow { exponent, .. } => {
+                let i = self.input(obj, path, bounds);
+                pow_range(i, exponent.unwrap_or(1.0))
+            }
+            D::OffsetConstant { offset, .. } => self
+                .input(obj, path, bounds)
+                .add(Interval::point(offset.unwrap_or(0.0))),
+            D::AmplitudeConstant { amplitude, .. } => self
+                .input(obj, path, bounds)
+                .mul(Interval::point(amplitude.unwrap_or(1.0))),
+
+            D::Clamp { wall_a, wall_b, .. } => self
+                .input(obj, path, bounds)
+                .clamp(wall_a.unwrap_or(0.0), wall_b.unwrap_or(1.0)),
+            D::SmoothClamp {
+                wall_a,
+                wall_b,
+                range,
+                ..
+            } => {
+                // Smoothing bends values near the walls by at most Range
+                self.input(obj, path, bounds)
+                    .clamp(wall_a.unwrap_or(0.0), wall_b.unwrap_or(1.0))
+                    .widen(range.unwrap_or(0.0))
+            }
+            D::Floor { floor, .. } => self
+                .input(obj, path, bounds)
+                .max_with(Interval::point(floor.unwrap_or(0.0))),
+            D::SmoothFloor { floor, range, .. } => {
+                let hard = self
+                    .input(obj, path, bounds)
+                    .max_with(Interval::point(floor.unwrap_or(0.0)));
+                hard.widen(range.unwrap_or(0.0))
+            }
+            D::Ceiling { ceiling, .. } => self
+                .input(obj, path, bounds)
+                .min_with(Interval::point(ceiling.unwrap_or(0.0))),
+            D::SmoothCeiling { ceiling, range, .. } => {
+                let hard = self
+                    .input(obj, path, bounds)
+                    .min_with(Interval::point(ceiling.unwrap_or(0.0)));
+                hard.widen(range.unwrap_or(0.0))
+            }
+
+            D::Min { .. } => fold_or_zero(self.inputs(obj, path, bounds), Interval::min_with),
+            D::Max { .. } => fold_or_zero(self.inputs(obj, path, bounds), Interval::max_with),
+            D::SmoothMin { range, .. } => {
+                fold_or_zero(self.inputs(obj, path, bounds), Interval::min_with)
+                    .widen(range.unwrap_or(0.0))
+            }
+            D::SmoothMax { range, .. } => {
+                fold_or_zero(self.inputs(obj, path, bounds), Interval::max_with)
+                    .widen(range.unwrap_or(0.0))
+            }
+
+            D::Normalizer {
+                from_min,
+                from_max,
+                to_min,
+                to_max,
+                ..
+            } => {
+                let i = self.input(obj, path, bounds);
+                let from = Interval::new(from_min.unwrap_or(-1.0), from_max.unwrap_or(1.0));
+                if !from.contains(&i) {
+                    self.warn(path, node_type, normalizer_message(i, from));
+                }
+                i.remap(
+                    from_min.unwrap_or(-1.0),
+                    from_max.unwrap_or(1.0),
+                    to_min.unwrap_or(0.0),
+                    to_max.unwrap_or(1.0),
+                )
+            }
+            D::CurveMapper { curve, .. } => {
+                self.input(obj, path, bounds);
+                curve
+                    .as_ref()
+                    .map(curve_range)
+                    .unwrap_or(Interval::UNBOUNDED)
+            }
+            D::Offset { .. } => {
+                let i = self.input(obj, path, bounds);
+                i.add(self.field(obj, path, "Offset", bounds))
+            }
+            D::Amplitude { .. } => {
+                let i = self.input(obj, path, bounds);
+                i.mul(self.field(obj, path, "Amplitude", bounds))
+            }
+
+            D::Mix { .. } => {
+                let r = self.inputs(obj, path, bounds);
+                match r.as_slice() {
+                    [a, b, gauge, ..] => {
+                        // a * (1 - g) + b * g
+                        let g = *gauge;
+                        a.mul(Interval::point(1.0).add(g.neg())).add(b.mul(g))
+                    }
+                    _ => fold_or_zero(r, Interval::hull),
+                }
+            }
+            D::MultiMix { .. } => fold_or_zero(self.inputs(obj, path, bounds), Interval::hull),
+
+            D::Scale { .. }
+            | D::Slider { .. }
+            | D::Rotator { .. }
+            | D::Anchor { .. }
+            | D::FastGradientWarp { .. }
+            | D::PositionsPinch { .. }
+            | D::PositionsTwist { .. } => self.input(obj, path, transformed),
+            D::XOverride { .. } => {
+                let x = self.field(obj, path, "Override", bounds);
+                self.input(obj, path, CoordBounds { x, ..bounds })
+            }
+            D::YOverride { .. } => {
+                let y = self.field(obj, path, "Override", bounds);
+                self.input(obj, path, CoordBounds { y, ..bounds })
+            }
+            D::ZOverride { .. } => {
+                let z = self.field(obj, path, "Override", bounds);
+                self.input(obj, path, CoordBounds { z, ..bounds })
+            }
+            D::GradientWarp { .. } | D::VectorWarp { .. } => {
+                // The first input is warped, the rest drive the warp
+                let inputs = obj.get("Inputs").and_then(|v| v.as_array());
+                let mut ranges = Vec::new();
+                for (i, v) in inputs.into_iter().flatten().enumerate() {
+                    let b = if i == 0 { transformed } else { bounds };
+                    ranges.push(self.node(v, &format!("{}.Inputs[{}]", path, i), b));
+                }
+                ranges.first().copied().unwrap_or(Interval::point(0.0))
+            }
+
+            D::Distance { curve } | D::Cube { curve } => curve
+                .as_ref()
+                .map(curve_range)
+                .unwrap_or(Interval::UNBOUNDED),
+            D::Ellipsoid { curve, .. }
+            | D::Cuboid { curve, .. }
+            | D::Plane { curve, .. }
+            | D::Axis { curve, .. } => curve
+                .as_ref()
+                .map(curve_range)
+                .unwrap_or(Interval::UNBOUNDED),
+            D::Cylinder {
+                axial_curve,
+                radial_curve,
+                ..
+            } => optional_curve(axial_curve).mul(optional_curve(radial_curve)),
+            D::Shell {
+                angle_curve,
+                distance_curve,
+                ..
+            } => optional_curve(angle_curve).mul(optional_curve(distance_curve)),
+            D::Angle { .. } => Interval::new(0.0, 180.0),
+
+            D::XValue {} => bounds.x,
+            D::YValue {} => bounds.y,
+            D::ZValue {} => bounds.z,
+
+            D::Terrain {} | D::BaseHeight { .. } => Interval::UNBOUNDED,
+            D::CellWallDistance { max_distance, .. } => {
+                Interval::new(0.0, max_distance.unwrap_or(f64::INFINITY))
+            }
+            D::DistanceToBiomeEdge {} => Interval::new(0.0, f64::INFINITY),
+            D::Gradient {
+                from,
+                to,
+                from_y,
+                to_y,
+            } => {
+                let (from, to) = (from.unwrap_or(0.0), to.unwrap_or(1.0));
+                let (from_y, to_y) = (from_y.unwrap_or(0.0), to_y.unwrap_or(1.0));
+                bounds.y.remap(from_y, to_y, from, to)
+            }
+
+            D::Cache { .. } | D::Cache2D { .. } | D::SwitchState { .. } => {
+                self.input(obj, path, bounds)
+            }
+            D::YSampled { y, .. } => {
+                let y = y.map(Interval::point).unwrap_or(bounds.y);
+                self.input(obj, path, CoordBounds { y, ..bounds })
+            }
+            D::Switch { .. } => {
+                let mut range = None;
+                let cases = obj.get("SwitchCases").and_then(|v| v.as_array());
+                for (i, case) in cases.into_iter().flatten().enumerate() {
+                    if let Some(density) = case.get("Density").filter(|v| v.is_object()) {
+                        let r = self.node(
+                            density,
+                            &format!("{}.SwitchCases[{}].Density", path, i),
+                            bounds,
+                        );
+                        range = Some(range.map_or(r, |acc: Interval| acc.hull(r)));
+                    }
+                }
+                if obj.get("Input").is_some_and(|v| !v.is_null()) {
+                    let r = self.input(obj, path, bounds);
+                    range = Some(range.map_or(r, |acc: Interval| acc.hull(r)));
+                }
+                range.unwrap_or(Interval::point(0.0))
+            }
+
+            D::PositionsCellNoise {
+                max_distance,
+                return_type,
+                ..
+            } => {
+                // Distances are capped at MaxDistance, as the evaluator reads them
+                let d = max_distance
+                    .filter(|d| *d > 0.0)
+                    .unwrap_or(DEFAULT_CELL_DISTANCE);
+                let name = return_type
+                    .as_ref()
+                    .and_then(|v| v.as_str().or_else(|| v.get("Type")?.as_str()));
+                match CellReturn::from_name(name.unwrap_or("")) {
+                    CellReturn::Distance | CellReturn::Distance2 | CellReturn::Distance2Sub => {
+                        Interval::new(0.0, d)
+                    }
+                    CellReturn::Distance2Add => Interval::new(0.0, 2.0 * d),
+                    CellReturn::Distance2Mul => Interval::new(0.0, d * d),
+                    // F1 never exceeds F2
+                    CellReturn::Distance2Div => Interval::new(0.0, 1.0),
+                    CellReturn::WallDistance => Interval::new(0.0, d / 2.0),
+                }
+            }
+            D::Positions3D { .. } => Interval::UNBOUNDED,
+
+            D::Exported { .. } => {
+                if obj.get("Input").is_some_and(|v| v.is_object()) {
+                    self.input(obj, path, bounds)
+                } else {
+                    self.field(obj, path, "Density", bounds)
+                }
+            }
+            D::Imported { .. } | D::Pipeline { .. } => Interval::UNBOUNDED,
+        }
+    }
+
+    fn warn(&mut self, path: &str, node_type: &str, message: String) {
+        self.warnings.push(RangeWarning {
+            path: path.to_string(),
+            node_type: node_type.to_string(),
+            message,
+        });
+    }
+}
+
+fn fold_or_zero(ranges: Vec<Interval>, f: impl Fn(Interval, Interval) -> Interval) -> Interval {
+    ranges.into_iter().reduce(f).unwrap_or(Interval::point(0.0))
+}
+
+fn optional_curve(curve: &Option<Value>) -> Interval {
+    curve
+        .as_ref()
+        .map(curve_range)
+        .unwrap_or(Interval::UNBOUNDED)
+}
+
+fn normalizer_message(input: Interval, from: Interval) -> String {
+    if !input.is_bounded() {
+        return format!(
+            "Input range is unbounded; FromMin/FromMax [{}, {}] cannot be verified",
+            from.min, from.max
+        );
+    }
+    format!(
+        "Input range [{:.4}, {:.4}] is not covered by FromMin/FromMax [{}, {}]; output will leave [ToMin, ToMax]",
+        input.min, input.max, from.min, from.max
+    )
+}
+
+fn pow_range(i: Interval, exponent: f64) -> Interval {
+    if exponent == 0.0 {
+        return Interval::point(1.0);
+    }
+    if exponent < 0.0 {
+        // Blows up near zero
+        if i.min > 0.0 {
+            return Interval::new(i.min.powf(exponent), i.max.powf(exponent));
+        }
+        return Interval::UNBOUNDED;
+    }
+    if i.min >= 0.0 {
+        return Interval::new(i.min.powf(exponent), i.max.powf(exponent));
+    }
+    let m = i.magnitude().powf(exponent);
+    let is_even = exponent.fract() == 0.0 && (exponent as i64) % 2 == 0;
+    if is_even {
+        let low = if i.max >= 0.0 {
+            0.0
+        } else {
+            i.max.abs().powf(exponent)
+        };
+        Interval::new(low, m)
+    } else {
+        Interval::new(-m, m)
+    }
+}
+
+/// Guaranteed output range of a curve asset over any input.
+pub fn curve_range(json: &Value) -> Interval {
+    use CurveType as C;
+
+    let Ok(curve) = serde_json::from_value::<CurveType>(json.clone()) else {
+        return Interval::UNBOUNDED;
+    };
+    let child = |c: &Option<Value>| c.as_ref().map(curve_range).unwrap_or(Interval::UNBOUNDED);
+    let children = |cs: &[Value]| cs.iter().map(curve_range).collect::<Vec<_>>();
+
+    match curve {
+        C::Manual { points } => {
+            let ys: Vec<f64> = points.iter().filter_map(point_y).collect();
+            if ys.is_empty() {
+                Interval::point(0.0)
+            } else {
+                ys.iter().fold(Interval::point(ys[0]), |acc, &y| {
+                    acc.hull(Interval::point(y))
+                })
+            }
+        }
+        C::DistanceExponential { .. } | C::DistanceS { .. } => Interval::new(0.0, 1.0),
+        C::Ceiling { ceiling, curve } => {
+            child(&curve).min_with(Interval::point(ceiling.unwrap_or(0.0)))
+        }
+        C::Floor { floor, curve } => child(&curve).max_with(Interval::point(floor.unwrap_or(0.0))),
+        C::SmoothCeiling {
+            ceiling,
+            range,
+            curve,
+        } => child(&curve)
+            .min_with(Interval::point(ceiling.unwrap_or(0.0)))
+            .widen(range.unwrap_or(0.0)),
+        C::SmoothFloor {
+            floor,
+            range,
+            curve,
+        } => child(&curve)
+            .max_with(Interval::point(floor.unwrap_or(0.0)))
+            .widen(range.unwrap_or(0.0)),
+        C::SmoothClamp {
+            wall_a,
+            wall_b,
+            range,
+            curve,
+        } => child(&curve)
+            .clamp(wall_a.unwrap_or(0.0), wall_b.unwrap_or(1.0))
+            .widen(range.unwrap_or(0.0)),
+        C::Clamp {
+            wall_a,
+            wall_b,
+            curve,
+        } => child(&curve).clamp(wall_a.unwrap_or(0.0), wall_b.unwrap_or(1.0)),
+        C::SmoothMax {
+            range,
+            curve_a,
+            curve_b,
+        } => child(&curve_a)
+            .max_with(child(&curve_b))
+            .widen(range.unwrap_or(0.0)),
+        C::SmoothMin {
+            range,
+            curve_a,
+            curve_b,
+        } => child(&curve_a)
+            .min_with(child(&curve_b))
+            .widen(range.unwrap_or(0.0)),
+        C::Inverter { curve } => child(&curve).neg(),
+        C::Max { curves } => fold_or_zero(children(&curves), Interval::max_with),
+        C::Min { curves } => fold_or_zero(children(&curves), Interval::min_with),
+        C::Multiplier { curves } => children(&curves)
+            .into_iter()
+            .fold(Interval::point(1.0), Interval::mul),
+        C::Sum { curves } => children(&curves)
+            .into_iter()
+            .fold(Interval::point(0.0), Interval::add),
+        C::Not { curve } => Interval::point(1.0).add(child(&curve).neg()),
+        C::Exported { curve, .. } => child(&curve),
+        C::Imported { .. } => Interval::UNBOUNDED,
+    }
+}
+
+/// Read the X of a curve point given as `[x, y]`, `{x, y}` or `{X, Y}`.
+pub fn point_x(point: &Value) -> Option<f64> {
+    match point {
+        Value::Array(a) => a.first().and_then(|v| v.as_f64()),
+        Value::Object(o) => o.get("x").or_else(|| o.get("X")).and_then(|v| v.as_f64()),
+        _ => None,
+    }
+}
+
+/// Read the Y of a curve point given as `[x, y]`, `{x, y}` or `{X, Y}`.
+pub fn point_y(point: &Value) -> Option<f64> {
+    match point {
+        Value::Array(a) => a.get(1).and_then(|v| v.as_f64()),
+        Value::Object(o) => o.get("y").or_else(|| o.get("Y")).and_then(|v| v.as_f64()),
+        _ => None,
+    }
+}
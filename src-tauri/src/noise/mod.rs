@@ -1,2 +1,10 @@
 pub mod evaluator;
+pub mod hash;
+pub mod interval;
 pub mod nodes;
+pub mod profiler;
+pub mod statistics;
+pub mod tiles;
+
+#[cfg(test)]
+mod tests;
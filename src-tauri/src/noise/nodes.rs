@@ -1,8 +1,25 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
 use fastnoise_lite::FastNoiseLite;
 
+use crate::placement::positions::{PositionProvider, Region};
+
 /// Trait for evaluable density function nodes.
 pub trait NodeEval: Send + Sync {
     fn eval(&self, x: f64, y: f64, z: f64) -> f64;
+
+    /// Value and gradient (∂d/∂x, ∂d/∂y, ∂d/∂z) at a position.
+    ///
+    /// Nodes override this with analytic derivatives where they can; the
+    /// default uses central differences with step `h`.
+    fn eval_gradient(&self, x: f64, y: f64, z: f64, h: f64) -> (f64, [f64; 3]) {
+        let value = self.eval(x, y, z);
+        let dx = (self.eval(x + h, y, z) - self.eval(x - h, y, z)) / (2.0 * h);
+        let dy = (self.eval(x, y + h, z) - self.eval(x, y - h, z)) / (2.0 * h);
+        let dz = (self.eval(x, y, z + h) - self.eval(x, y, z - h)) / (2.0 * h);
+        (value, [dx, dy, dz])
+    }
 }
 
 /// Constant value node.
@@ -14,6 +31,10 @@ impl NodeEval for ConstantNode {
     fn eval(&self, _x: f64, _y: f64, _z: f64) -> f64 {
         self.value
     }
+
+    fn eval_gradient(&self, _x: f64, _y: f64, _z: f64, _h: f64) -> (f64, [f64; 3]) {
+        (self.value, [0.0; 3])
+    }
 }
 
 /// SimplexNoise2D node using fastnoise-lite.
@@ -37,12 +58,7 @@ impl SimplexNoise2DNode {
         noise.set_noise_type(Some(fastnoise_lite::NoiseType::OpenSimplex2));
 
         // Use seed string hash as integer seed
-        let seed_int: i32 = if seed.is_empty() {
-            0
-        } else {
-            seed.bytes().fold(0i32, |acc, b| acc.wrapping_mul(31).wrapping_add(b as i32))
-        };
-        noise.set_seed(Some(seed_int));
+        noise.set_seed(Some(super::hash::seed_hash(&seed)));
         noise.set_frequency(Some((1.0 / scale.max(0.001)) as f32));
 
         SimplexNoise2DNode {
@@ -77,6 +93,14 @@ impl NodeEval for SimplexNoise2DNode {
             0.0
         }
     }
+
+    fn eval_gradient(&self, x: f64, y: f64, z: f64, h: f64) -> (f64, [f64; 3]) {
+        // Constant along Y, so only X and Z need differencing
+        let value = self.eval(x, y, z);
+        let dx = (self.eval(x + h, y, z) - self.eval(x - h, y, z)) / (2.0 * h);
+        let dz = (self.eval(x, y, z + h) - self.eval(x, y, z - h)) / (2.0 * h);
+        (value, [dx, 0.0, dz])
+    }
 }
 
 /// Sum of multiple inputs.
@@ -88,6 +112,77 @@ impl NodeEval for SumNode {
     fn eval(&self, x: f64, y: f64, z: f64) -> f64 {
         self.inputs.iter().map(|input| input.eval(x, y, z)).sum()
     }
+
+    fn eval_gradient(&self, x: f64, y: f64, z: f64, h: f64) -> (f64, [f64; 3]) {
+        let mut value = 0.0;
+        let mut grad = [0.0; 3];
+        for input in &self.inputs {
+            let (v, g) = input.eval_gradient(x, y, z, h);
+            value += v;
+            for (acc, d) in grad.iter_mut().zip(g) {
+                *acc += d;
+            }
+        }
+        (value, grad)
+    }
+}
+
+/// Product of multiple inputs.
+pub struct ProductNode {
+    pub inputs: Vec<Box<dyn NodeEval>>,
+}
+
+impl NodeEval for ProductNode {
+    fn eval(&self, x: f64, y: f64, z: f64) -> f64 {
+        self.inputs.iter().map(|input| input.eval(x, y, z)).product()
+    }
+
+    fn eval_gradient(&self, x: f64, y: f64, z: f64, h: f64) -> (f64, [f64; 3]) {
+        // Product rule, one input at a time
+        let mut value = 1.0;
+        let mut grad = [0.0; 3];
+        for input in &self.inputs {
+            let (v, g) = input.eval_gradient(x, y, z, h);
+            for (acc, d) in grad.iter_mut().zip(g) {
+                *acc = *acc * v + value * d;
+            }
+            value *= v;
+        }
+        (value, grad)
+    }
+}
+
+/// Negated input.
+pub struct NegateNode {
+    pub input: Box<dyn NodeEval>,
+}
+
+impl NodeEval for NegateNode {
+    fn eval(&self, x: f64, y: f64, z: f64) -> f64 {
+        -self.input.eval(x, y, z)
+    }
+
+    fn eval_gradient(&self, x: f64, y: f64, z: f64, h: f64) -> (f64, [f64; 3]) {
+        let (v, g) = self.input.eval_gradient(x, y, z, h);
+        (-v, g.map(|d| -d))
+    }
+}
+
+/// One coordinate of the sample position: 0 is X, 1 is Y and 2 is Z.
+pub struct CoordinateNode {
+    pub axis: usize,
+}
+
+impl NodeEval for CoordinateNode {
+    fn eval(&self, x: f64, y: f64, z: f64) -> f64 {
+        [x, y, z][self.axis]
+    }
+
+    fn eval_gradient(&self, x: f64, y: f64, z: f64, _h: f64) -> (f64, [f64; 3]) {
+        let mut grad = [0.0; 3];
+        grad[self.axis] = 1.0;
+        ([x, y, z][self.axis], grad)
+    }
 }
 
 /// Clamp node: clamps input between min and max.
@@ -101,6 +196,15 @@ impl NodeEval for ClampNode {
     fn eval(&self, x: f64, y: f64, z: f64) -> f64 {
         self.input.eval(x, y, z).clamp(self.min, self.max)
     }
+
+    fn eval_gradient(&self, x: f64, y: f64, z: f64, h: f64) -> (f64, [f64; 3]) {
+        let (v, g) = self.input.eval_gradient(x, y, z, h);
+        if v < self.min || v > self.max {
+            (v.clamp(self.min, self.max), [0.0; 3])
+        } else {
+            (v, g)
+        }
+    }
 }
 
 /// Normalizer node: remaps input from source range to target range.
@@ -122,4 +226,146 @@ impl NodeEval for NormalizerNode {
         let normalized = (val - self.from_min) / from_range;
         self.to_min + normalized * (self.to_max - self.to_min)
     }
+
+    fn eval_gradient(&self, x: f64, y: f64, z: f64, h: f64) -> (f64, [f64; 3]) {
+        let from_range = self.from_max - self.from_min;
+        if from_range.abs() < f64::EPSILON {
+            return (self.to_min, [0.0; 3]);
+        }
+        let scale = (self.to_max - self.to_min) / from_range;
+        let (v, g) = self.input.eval_gradient(x, y, z, h);
+        (
+            self.to_min + (v - self.from_min) * scale,
+            g.map(|d| d * scale),
+        )
+    }
+}
+
+/// Value a positions-driven cell node derives from the distances to the
+/// nearest (F1) and second nearest (F2) points.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CellReturn {
+    Distance,
+    Distance2,
+    Distance2Add,
+    Distance2Sub,
+    Distance2Mul,
+    Distance2Div,
+    /// Half of F2 - F1: the distance to the wall between two cells
+    WallDistance,
+}
+
+impl CellReturn {
+    /// A `ReturnType` name; unknown names return F1.
+    pub fn from_name(name: &str) -> Self {
+        match name {
+            "Distance2" => CellReturn::Distance2,
+            "Distance2Add" => CellReturn::Distance2Add,
+            "Distance2Sub" => CellReturn::Distance2Sub,
+            "Distance2Mul" => CellReturn::Distance2Mul,
+            "Distance2Div" => CellReturn::Distance2Div,
+            _ => CellReturn::Distance,
+        }
+    }
+}
+
+/// Cell noise over the points of a position provider. Only points within
+/// `max_distance` are considered, and missing distances read as
+/// `max_distance`.
+///
+/// Points are generated once per cube of space with an edge of twice
+/// `max_distance` and kept, so a sample reads at most eight cubes instead of
+/// regenerating its neighbourhood.
+pub struct PositionsCellNode {
+    pub positions: PositionProvider,
+    pub max_distance: f64,
+    pub manhattan: bool,
+    pub return_type: CellReturn,
+    cells: Mutex<HashMap<[i64; 3], CellPoints>>,
+}
+
+/// Points generated for one cube of space.
+type CellPoints = Arc<Vec<[f64; 3]>>;
+
+/// Cubes of points a `PositionsCellNode` keeps before starting over.
+const MAX_POINT_CELLS: usize = 4096;
+
+impl PositionsCellNode {
+    pub fn new(
+        positions: PositionProvider,
+        max_distance: f64,
+        manhattan: bool,
+        return_type: CellReturn,
+    ) -> Self {
+        PositionsCellNode {
+            positions,
+            max_distance,
+            manhattan,
+            return_type,
+            cells: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Points of the provider inside one cube, generated on first use.
+    fn cell_points(&self, cell: [i64; 3]) -> Arc<Vec<[f64; 3]>> {
+        let mut cells = self.cells.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(points) = cells.get(&cell) {
+            return points.clone();
+        }
+        let size = 2.0 * self.max_distance;
+        let region = Region {
+            min: cell.map(|c| c as f64 * size),
+            max: cell.map(|c| (c + 1) as f64 * size),
+        };
+        let points = Arc::new(self.positions.positions(&region));
+        if cells.len() >= MAX_POINT_CELLS {
+            cells.clear();
+        }
+        cells.insert(cell, points.clone());
+        points
+    }
+}
+
+impl NodeEval for PositionsCellNode {
+    fn eval(&self, x: f64, y: f64, z: f64) -> f64 {
+        let r = self.max_distance;
+        let cell = |v: f64| (v / (2.0 * r)).floor() as i64;
+        let (lo, hi) = ([x - r, y - r, z - r].map(cell), [x + r, y + r, z + r].map(cell));
+        let (mut f1, mut f2) = (r, r);
+        for cx in lo[0]..=hi[0] {
+            for cy in lo[1]..=hi[1] {
+                for cz in lo[2]..=hi[2] {
+                    for p in self.cell_points([cx, cy, cz]).iter() {
+                        let d = [p[0] - x, p[1] - y, p[2] - z];
+                        let distance = if self.manhattan {
+                            d.iter().map(|c| c.abs()).sum()
+                        } else {
+                            d.iter().map(|c| c * c).sum::<f64>().sqrt()
+                        };
+                        if distance < f1 {
+                            f2 = f1;
+                            f1 = distance;
+                        } else if distance < f2 {
+                            f2 = distance;
+                        }
+                    }
+                }
+            }
+        }
+        match self.return_type {
+            CellReturn::Distance => f1,
+            CellReturn::Distance2 => f2,
+            CellReturn::Distance2Add => f1 + f2,
+            CellReturn::Distance2Sub => f2 - f1,
+            CellReturn::Distance2Mul => f1 * f2,
+            CellReturn::Distance2Div => {
+                if f2 > 0.0 {
+                    f1 / f2
+                } else {
+                    0.0
+                }
+            }
+            CellReturn::WallDistance => (f2 - f1) / 2.0,
+        }
+    }
 }
@@ -0,0 +1,125 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Instant;
+
+use serde::Serialize;
+
+use super::nodes::NodeEval;
+
+/// Per-node counters shared between a `ProfiledNode` and the evaluator.
+pub struct NodeSlot {
+    pub path: String,
+    pub node_type: String,
+    pub parent: Option<usize>,
+    calls: AtomicU64,
+    nanos: AtomicU64,
+}
+
+impl NodeSlot {
+    pub fn new(path: String, node_type: String, parent: Option<usize>) -> Self {
+        NodeSlot {
+            path,
+            node_type,
+            parent,
+            calls: AtomicU64::new(0),
+            nanos: AtomicU64::new(0),
+        }
+    }
+}
+
+/// Wraps a node and records its call count and inclusive evaluation time.
+pub struct ProfiledNode {
+    pub inner: Box<dyn NodeEval>,
+    pub slot: Arc<NodeSlot>,
+}
+
+impl NodeEval for ProfiledNode {
+    fn eval(&self, x: f64, y: f64, z: f64) -> f64 {
+        let start = Instant::now();
+        let value = self.inner.eval(x, y, z);
+        let elapsed = start.elapsed().as_nanos() as u64;
+        self.slot.calls.fetch_add(1, Ordering::Relaxed);
+        self.slot.nanos.fetch_add(elapsed, Ordering::Relaxed);
+        value
+    }
+
+    fn eval_gradient(&self, x: f64, y: f64, z: f64, h: f64) -> (f64, [f64; 3]) {
+        self.inner.eval_gradient(x, y, z, h)
+    }
+}
+
+/// Cost of a single node over one profiled evaluation.
+#[derive(Debug, Clone, Serialize)]
+pub struct NodeProfile {
+    /// JSON path of the node within the graph (e.g. `$.Inputs[0].Input`)
+    pub path: String,
+    pub node_type: String,
+    pub calls: u64,
+    /// Average number of calls per grid sample
+    pub calls_per_sample: f64,
+    /// Time spent in this node including its inputs
+    pub total_ms: f64,
+    /// Time spent in this node excluding its inputs
+    pub self_ms: f64,
+    /// Share of the root's total time spent in this node itself
+    pub self_percent: f64,
+}
+
+/// Collects the slots created while parsing a graph in profiling mode.
+#[derive(Default)]
+pub struct Profiler {
+    slots: Vec<Arc<NodeSlot>>,
+}
+
+impl Profiler {
+    /// Register a node and return its slot index.
+    pub fn register(&mut self, path: String, node_type: String, parent: Option<usize>) -> usize {
+        self.slots.push(Arc::new(NodeSlot::new(path, node_type, parent)));
+        self.slots.len() - 1
+    }
+
+    pub fn slot(&self, index: usize) -> Arc<NodeSlot> {
+        self.slots[index].clone()
+    }
+
+    /// Build a report ranked by self time, most expensive first.
+    pub fn report(&self, samples: usize) -> Vec<NodeProfile> {
+        let totals: Vec<u64> = self
+            .slots
+            .iter()
+            .map(|s| s.nanos.load(Ordering::Relaxed))
+            .collect();
+
+        // Self time = inclusive time minus the inclusive time of direct children
+        let mut self_nanos = totals.clone();
+        for (i, slot) in self.slots.iter().enumerate() {
+            if let Some(parent) = slot.parent {
+                self_nanos[parent] = self_nanos[parent].saturating_sub(totals[i]);
+            }
+        }
+
+        let root_total = totals.first().copied().unwrap_or(0).max(1) as f64;
+        let samples = samples.max(1) as f64;
+
+        let mut report: Vec<NodeProfile> = self
+            .slots
+            .iter()
+            .enumerate()
+            .map(|(i, slot)| {
+                let calls = slot.calls.load(Ordering::Relaxed);
+                NodeProfile {
+                    path: slot.path.clone(),
+                    node_type: slot.node_type.clone(),
+                    calls,
+                    calls_per_sample: calls as f64 / samples,
+                    total_ms: totals[i] as f64 / 1_000_000.0,
+                    self_ms: self_nanos[i] as f64 / 1_000_000.0,
+                    self_percent: self_nanos[i] as f64 / root_total * 100.0,
+                }
+            })
+            .collect();
+
+        report.sort_by(|a, b| b.self_ms.total_cmp(&a.self_ms));
+        report
+    }
+}
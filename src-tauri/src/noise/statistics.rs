@@ -0,0 +1,293 @@
+use serde::{Deserialize, Serialize};
+
+/// Options for the statistics block of a preview evaluation.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct StatisticsOptions {
+    /// Number of histogram bins
+    pub bins: u32,
+    /// Percentiles to report, in [0, 100]
+    pub percentiles: Vec<f64>,
+    /// Extra thresholds to report coverage for (fraction of samples above)
+    pub thresholds: Vec<f64>,
+    /// Fixed histogram range; grown to fit the samples when omitted
+    pub histogram_min: Option<f64>,
+    pub histogram_max: Option<f64>,
+}
+
+impl Default for StatisticsOptions {
+    fn default() -> Self {
+        StatisticsOptions {
+            bins: 64,
+            percentiles: vec![5.0, 25.0, 50.0, 75.0, 95.0],
+            thresholds: Vec::new(),
+            histogram_min: None,
+            histogram_max: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Histogram {
+    /// Sample count per bin
+    pub bins: Vec<u32>,
+    /// Bin edges (bins + 1 values)
+    pub bin_edges: Vec<f64>,
+    /// Samples below the first edge (counted in the first bin)
+    pub below_range: u32,
+    /// Samples above the last edge (counted in the last bin)
+    pub above_range: u32,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Percentile {
+    pub percentile: f64,
+    pub value: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ThresholdCoverage {
+    pub threshold: f64,
+    /// Fraction of samples strictly above the threshold
+    pub fraction: f64,
+}
+
+/// Summary statistics of an evaluated grid.
+#[derive(Debug, Clone, Serialize)]
+pub struct PreviewStatistics {
+    /// Finite samples; every other statistic covers only these
+    pub count: u32,
+    /// NaN and infinite samples
+    pub non_finite: u32,
+    pub mean: f64,
+    pub std_dev: f64,
+    pub histogram: Histogram,
+    /// Percentiles estimated from the histogram, within the observed range
+    pub percentiles: Vec<Percentile>,
+    /// Fraction of samples above 0 (solid terrain)
+    pub solid_fraction: f64,
+    pub threshold_coverage: Vec<ThresholdCoverage>,
+}
+
+/// Streaming statistics collected while a grid is evaluated.
+///
+/// Non-finite samples are only counted. Mean and variance use Welford's
+/// algorithm. Every sample is binned as it
+/// arrives: into the fixed range when the options give one, otherwise into a
+/// range that doubles, merging adjacent bins, whenever a sample falls
+/// outside it.
+pub struct StatsAccumulator {
+    options: StatisticsOptions,
+    fixed: bool,
+    /// Lower edge and bin width of the histogram. A zero width means every
+    /// sample so far had the value `lo`; they wait in `pending`.
+    lo: f64,
+    width: f64,
+    pending: u32,
+    bins: Vec<u32>,
+    below: u32,
+    above: u32,
+    count: u32,
+    non_finite: u32,
+    /// Smallest and largest sample
+    min: f64,
+    max: f64,
+    mean: f64,
+    m2: f64,
+    solid: u32,
+    threshold_counts: Vec<u32>,
+}
+
+impl StatsAccumulator {
+    pub fn new(options: StatisticsOptions) -> Self {
+        let bin_count = options.bins.max(1) as usize;
+        let fixed = match (options.histogram_min, options.histogram_max) {
+            (Some(lo), Some(hi)) if lo.is_finite() && hi.is_finite() && hi > lo => Some((lo, hi)),
+            _ => None,
+        };
+        let (lo, width) = fixed.map_or((0.0, 0.0), |(lo, hi)| (lo, (hi - lo) / bin_count as f64));
+        let threshold_counts = vec![0; options.thresholds.len()];
+
+        StatsAccumulator {
+            options,
+            fixed: fixed.is_some(),
+            lo,
+            width,
+            pending: 0,
+            bins: vec![0; bin_count],
+            below: 0,
+            above: 0,
+            count: 0,
+            non_finite: 0,
+            min: f64::INFINITY,
+            max: f64::NEG_INFINITY,
+            mean: 0.0,
+            m2: 0.0,
+            solid: 0,
+            threshold_counts,
+        }
+    }
+
+    fn hi(&self) -> f64 {
+        self.lo + self.bins.len() as f64 * self.width
+    }
+
+    pub fn push(&mut self, value: f64) {
+        if !value.is_finite() {
+            self.non_finite += 1;
+            return;
+        }
+        self.count += 1;
+        self.min = self.min.min(value);
+        self.max = self.max.max(value);
+        let delta = value - self.mean;
+        self.mean += delta / self.count as f64;
+        self.m2 += delta * (value - self.mean);
+
+        if value > 0.0 {
+            self.solid += 1;
+        }
+        for (count, threshold) in self
+            .threshold_counts
+            .iter_mut()
+            .zip(&self.options.thresholds)
+        {
+            if value > *threshold {
+                *count += 1;
+            }
+        }
+
+        if self.fixed {
+            if value < self.lo {
+                self.below += 1;
+            } else if value > self.hi() {
+                self.above += 1;
+            }
+        } else if !self.fit_range(value) {
+            return;
+        }
+        let index = bin_index(value, self.lo, self.hi(), self.bins.len());
+        self.bins[index] += 1;
+    }
+
+    /// Grow the streaming range until it holds `value`. Returns false when
+    /// the value was held back because no sample has differed yet.
+    fn fit_range(&mut self, value: f64) -> bool {
+        let n = self.bins.len();
+        if self.width == 0.0 {
+            if self.pending == 0 || value == self.lo {
+                self.lo = value;
+                self.pending += 1;
+                return false;
+            }
+            // The first distinct value spans the initial range
+            let first = self.lo;
+            self.lo = first.min(value);
+            self.width = ((first - value).abs() / n as f64).max(f64::MIN_POSITIVE);
+            let index = bin_index(first, self.lo, self.hi(), n);
+            self.bins[index] += std::mem::take(&mut self.pending);
+        }
+        while value < self.lo || value > self.hi() {
+            // Double the bin width, keeping the old range as one half of
+            // the new one so each new bin is exactly two old ones
+            let grow_down = value < self.lo;
+            let mut merged = vec![0; n];
+            for (i, &c) in self.bins.iter().enumerate() {
+                merged[if grow_down { (n + i) / 2 } else { i / 2 }] += c;
+            }
+            if grow_down {
+                self.lo -= n as f64 * self.width;
+            }
+            self.width *= 2.0;
+            self.bins = merged;
+        }
+        true
+    }
+
+    pub fn finish(mut self) -> PreviewStatistics {
+        let lo = self.lo;
+        let hi = if self.width > 0.0 {
+            self.hi()
+        } else {
+            // Flat data still gets a non-empty bin width
+            self.bins[0] += self.pending;
+            lo + 0.001
+        };
+
+        let n = self.bins.len();
+        let width = (hi - lo) / n as f64;
+        let bin_edges: Vec<f64> = (0..=n).map(|i| lo + i as f64 * width).collect();
+
+        let percentiles = self
+            .options
+            .percentiles
+            .iter()
+            .map(|&p| {
+                let mut value = histogram_percentile(&self.bins, &bin_edges, self.count, p);
+                // Interpolating inside a bin can land past the extreme samples
+                if self.count > 0 {
+                    value = value.clamp(self.min, self.max);
+                }
+                Percentile {
+                    percentile: p,
+                    value,
+                }
+            })
+            .collect();
+
+        let total = self.count.max(1) as f64;
+        let threshold_coverage = self
+            .options
+            .thresholds
+            .iter()
+            .zip(&self.threshold_counts)
+            .map(|(&threshold, &count)| ThresholdCoverage {
+                threshold,
+                fraction: count as f64 / total,
+            })
+            .collect();
+
+        PreviewStatistics {
+            count: self.count,
+            non_finite: self.non_finite,
+            mean: self.mean,
+            std_dev: if self.count > 0 {
+                (self.m2 / self.count as f64).sqrt()
+            } else {
+                0.0
+            },
+            histogram: Histogram {
+                bins: self.bins,
+                bin_edges,
+                below_range: self.below,
+                above_range: self.above,
+            },
+            percentiles,
+            solid_fraction: self.solid as f64 / total,
+            threshold_coverage,
+        }
+    }
+}
+
+fn bin_index(value: f64, lo: f64, hi: f64, bins: usize) -> usize {
+    let t = (value - lo) / (hi - lo);
+    ((t * bins as f64).floor().max(0.0) as usize).min(bins - 1)
+}
+
+/// Estimate a percentile by linear interpolation within its histogram bin.
+fn histogram_percentile(bins: &[u32], edges: &[f64], count: u32, percentile: f64) -> f64 {
+    if count == 0 {
+        return 0.0;
+    }
+    let target = percentile.clamp(0.0, 100.0) / 100.0 * count as f64;
+    let mut cumulative = 0.0;
+    for (i, &c) in bins.iter().enumerate() {
+        let next = cumulative + c as f64;
+        if next >= target && c > 0 {
+            let t = (target - cumulative) / c as f64;
+            return edges[i] + t.clamp(0.0, 1.0) * (edges[i + 1] - edges[i]);
+        }
+        cumulative = next;
+    }
+    edges[edges.len() - 1]
+}
@@ -0,0 +1,442 @@
+use crate::noise::evaluator::DensityEvaluator;
+use crate::noise::interval::{analyze, CoordBounds, Interval};
+use crate::noise::statistics::{StatisticsOptions, StatsAccumulator};
+use crate::noise::tiles::{self, TileCache, TileKey, TileSource, MAX_ZOOM, TILE_SIZE};
+use serde_json::json;
+use std::sync::Mutex;
+
+fn sample_graph() -> serde_json::Value {
+    json!({
+        "Type": "Clamp",
+        "WallA": -1.0,
+        "WallB": 1.0,
+        "Input": {
+            "Type": "Sum",
+            "Inputs": [
+                { "Type": "SimplexNoise2D", "Scale": 64.0, "Octaves": 2, "Seed": "a" },
+                { "Type": "Constant", "Value": 0.25 }
+            ]
+        }
+    })
+}
+
+// ── Evaluator ────────────────────────────────────────────────────
+
+#[test]
+fn single_input_reads_input_field() {
+    let graph = json!({
+        "Type": "Clamp",
+        "WallA": 0.0,
+        "WallB": 0.5,
+        "Input": { "Type": "Constant", "Value": 2.0 }
+    });
+    let evaluator = DensityEvaluator::from_json(&graph).unwrap();
+    assert_eq!(evaluator.evaluate(0.0, 0.0, 0.0), 0.5);
+}
+
+// ── Position cells ───────────────────────────────────────────────
+
+#[test]
+fn cell_nodes_measure_distance_to_positions() {
+    let points = json!({
+        "Type": "List",
+        "Positions": [{ "x": 0, "y": 0, "z": 0 }, { "x": 10, "y": 0, "z": 0 }]
+    });
+    let cells = DensityEvaluator::from_json(&json!({
+        "Type": "PositionsCellNoise",
+        "Positions": points,
+        "ReturnType": "Distance2Sub",
+        "MaxDistance": 20
+    }))
+    .unwrap();
+    assert!((cells.evaluate(2.0, 0.0, 0.0) - 6.0).abs() < 1e-9);
+
+    let wall = DensityEvaluator::from_json(&json!({
+        "Type": "CellWallDistance",
+        "Positions": points,
+        "MaxDistance": 20
+    }))
+    .unwrap();
+    assert!((wall.evaluate(2.0, 0.0, 0.0) - 3.0).abs() < 1e-9);
+    assert!(wall.evaluate(5.0, 0.0, 0.0).abs() < 1e-9);
+
+    // Nothing in range reads as MaxDistance
+    let far = DensityEvaluator::from_json(&json!({
+        "Type": "PositionsCellNoise",
+        "Positions": points,
+        "MaxDistance": 4
+    }))
+    .unwrap();
+    assert_eq!(far.evaluate(100.0, 0.0, 0.0), 4.0);
+    // Points are cached in 8-block cubes here; the nearest one sits
+    // across a cube edge from the sample
+    assert!((far.evaluate(8.5, 0.0, 0.0) - 1.5).abs() < 1e-9);
+    assert!((far.evaluate(8.5, 0.0, 0.0) - 1.5).abs() < 1e-9);
+
+    let unknown = json!({
+        "Type": "PositionsCellNoise",
+        "Positions": { "Type": "Framework", "Name": "Villages" }
+    });
+    let err = DensityEvaluator::from_json(&unknown).err().unwrap();
+    assert!(err.contains("Unknown framework positions 'Villages' at $.Positions"));
+}
+
+// ── Profiler ─────────────────────────────────────────────────────
+
+#[test]
+fn profiled_matches_unprofiled() {
+    let plain = DensityEvaluator::from_json(&sample_graph()).unwrap();
+    let profiled = DensityEvaluator::from_json_profiled(&sample_graph()).unwrap();
+    for i in 0..16 {
+        let x = i as f64 * 13.0;
+        assert_eq!(plain.evaluate(x, 0.0, -x), profiled.evaluate(x, 0.0, -x));
+    }
+    assert!(plain.profile_report(16).is_none());
+}
+
+#[test]
+fn profile_report_maps_paths_and_counts() {
+    let evaluator = DensityEvaluator::from_json_profiled(&sample_graph()).unwrap();
+    for i in 0..10 {
+        evaluator.evaluate(i as f64, 0.0, 0.0);
+    }
+
+    let report = evaluator.profile_report(10).unwrap();
+    let mut paths: Vec<&str> = report.iter().map(|n| n.path.as_str()).collect();
+    paths.sort();
+    assert_eq!(
+        paths,
+        vec!["$", "$.Input", "$.Input.Inputs[0]", "$.Input.Inputs[1]"]
+    );
+
+    for node in &report {
+        assert_eq!(node.calls, 10);
+        assert_eq!(node.calls_per_sample, 1.0);
+        assert!(node.self_ms <= node.total_ms);
+    }
+    let noise = report
+        .iter()
+        .find(|n| n.node_type == "SimplexNoise2D")
+        .unwrap();
+    assert_eq!(noise.path, "$.Input.Inputs[0]");
+}
+
+// ── Interval analysis ────────────────────────────────────────────
+
+#[test]
+fn interval_propagates_through_math() {
+    let graph = json!({
+        "Type": "Sum",
+        "Inputs": [
+            { "Type": "SimplexNoise2D", "Scale": 64.0 },
+            {
+                "Type": "AmplitudeConstant",
+                "Amplitude": -2.0,
+                "Input": { "Type": "Abs", "Input": { "Type": "SimplexNoise3D" } }
+            },
+            { "Type": "Clamp", "WallA": 0.5, "WallB": 0.0, "Input": { "Type": "YValue" } }
+        ]
+    });
+    let report = analyze(&graph, CoordBounds::default());
+    assert_eq!(report.root.min, Some(-3.0));
+    assert_eq!(report.root.max, Some(1.5));
+    assert_eq!(report.nodes.len(), 7);
+    assert_eq!(report.nodes[0].path, "$");
+    assert!(report.warnings.is_empty());
+}
+
+#[test]
+fn interval_flags_uncovered_normalizer() {
+    let graph = json!({
+        "Type": "Normalizer",
+        "FromMin": -1.0,
+        "FromMax": 1.0,
+        "ToMin": 0.0,
+        "ToMax": 10.0,
+        "Input": {
+            "Type": "Sum",
+            "Inputs": [
+                { "Type": "SimplexNoise2D" },
+                { "Type": "Constant", "Value": 0.5 }
+            ]
+        }
+    });
+    let report = analyze(&graph, CoordBounds::default());
+    assert_eq!(report.warnings.len(), 1);
+    assert_eq!(report.warnings[0].path, "$");
+    assert_eq!(report.root.min, Some(2.5));
+    assert_eq!(report.root.max, Some(12.5));
+}
+
+#[test]
+fn interval_uses_coordinate_bounds() {
+    let graph = json!({
+        "Type": "Gradient",
+        "From": 1.0,
+        "To": -1.0,
+        "FromY": 0.0,
+        "ToY": 100.0
+    });
+    let unbounded = analyze(&graph, CoordBounds::default());
+    assert_eq!(unbounded.root.min, None);
+
+    let bounds = CoordBounds {
+        y: Interval::new(0.0, 50.0),
+        ..Default::default()
+    };
+    let bounded = analyze(&graph, bounds);
+    assert_eq!(bounded.root.min, Some(0.0));
+    assert_eq!(bounded.root.max, Some(1.0));
+}
+
+#[test]
+fn interval_bounds_positions_cell_noise_by_return_type() {
+    let expected = [
+        ("Distance", 8.0),
+        ("Distance2", 8.0),
+        ("Distance2Add", 16.0),
+        ("Distance2Sub", 8.0),
+        ("Distance2Mul", 64.0),
+        ("Distance2Div", 1.0),
+    ];
+    for (return_type, max) in expected {
+        let graph = json!({
+            "Type": "PositionsCellNoise",
+            "MaxDistance": 8.0,
+            "ReturnType": { "Type": return_type },
+            "Positions": { "Type": "List", "Positions": [
+                { "x": 0, "y": 0, "z": 0 }, { "x": 3, "y": 0, "z": 4 }
+            ] }
+        });
+        let report = analyze(&graph, CoordBounds::default());
+        assert_eq!(report.root.min, Some(0.0), "{}", return_type);
+        assert_eq!(report.root.max, Some(max), "{}", return_type);
+
+        let evaluator = DensityEvaluator::from_json(&graph).unwrap();
+        for x in -10..10 {
+            let value = evaluator.evaluate(x as f64 * 0.7, 0.5, 1.0);
+            assert!((0.0..=max).contains(&value), "{} {}", return_type, value);
+        }
+    }
+
+    // No MaxDistance: the evaluator's default search radius
+    let graph = json!({ "Type": "PositionsCellNoise", "ReturnType": "Distance2Add" });
+    let report = analyze(&graph, CoordBounds::default());
+    assert_eq!(report.root.max, Some(128.0));
+}
+
+// ── Statistics ───────────────────────────────────────────────────
+
+#[test]
+fn statistics_single_pass_with_known_range() {
+    let options = StatisticsOptions {
+        bins: 4,
+        percentiles: vec![50.0],
+        thresholds: vec![0.5],
+        histogram_min: Some(-1.0),
+        histogram_max: Some(1.0),
+    };
+    let mut stats = StatsAccumulator::new(options);
+    let values = [-1.0f32, -0.25, 0.25, 0.75];
+    for v in values {
+        stats.push(v as f64);
+    }
+    let result = stats.finish();
+
+    assert_eq!(result.count, 4);
+    assert!((result.mean - (-0.0625)).abs() < 1e-9);
+    assert_eq!(result.histogram.bins, vec![1, 1, 1, 1]);
+    assert_eq!(result.histogram.bin_edges, vec![-1.0, -0.5, 0.0, 0.5, 1.0]);
+    assert_eq!(result.percentiles[0].value, 0.0);
+    assert_eq!(result.solid_fraction, 0.5);
+    assert_eq!(result.threshold_coverage[0].fraction, 0.25);
+}
+
+#[test]
+fn statistics_streaming_binning_grows_range() {
+    let options = StatisticsOptions {
+        bins: 2,
+        ..Default::default()
+    };
+    let mut stats = StatsAccumulator::new(options);
+    for v in [0.0, 1.0, 3.5] {
+        stats.push(v);
+    }
+    // Doubled twice upwards: [0, 1] -> [0, 2] -> [0, 4]
+    let upward = stats.finish();
+    assert_eq!(upward.histogram.bins, vec![2, 1]);
+    assert_eq!(upward.histogram.bin_edges, vec![0.0, 2.0, 4.0]);
+    assert_eq!(upward.histogram.above_range, 0);
+
+    let mut stats = StatsAccumulator::new(StatisticsOptions {
+        bins: 2,
+        ..Default::default()
+    });
+    for v in [0.0, 1.0, 3.5, -3.0] {
+        stats.push(v);
+    }
+    // Then doubled downwards, keeping [0, 4] as the upper bin
+    let result = stats.finish();
+    assert_eq!(result.histogram.bins, vec![1, 3]);
+    assert_eq!(result.histogram.bin_edges, vec![-4.0, 0.0, 4.0]);
+    assert_eq!(result.solid_fraction, 0.5);
+    assert!((result.std_dev - 2.328492001274645).abs() < 1e-9);
+}
+
+#[test]
+fn statistics_flat_data_fills_first_bin() {
+    let mut stats = StatsAccumulator::new(StatisticsOptions {
+        bins: 4,
+        ..Default::default()
+    });
+    for _ in 0..3 {
+        stats.push(2.0);
+    }
+    let result = stats.finish();
+    assert_eq!(result.histogram.bins, vec![3, 0, 0, 0]);
+    assert_eq!(result.histogram.bin_edges[0], 2.0);
+    assert!(result.percentiles.iter().all(|p| p.value == 2.0));
+}
+
+#[test]
+fn statistics_count_non_finite_samples_apart() {
+    let mut stats = StatsAccumulator::new(StatisticsOptions {
+        bins: 4,
+        thresholds: vec![0.5],
+        ..Default::default()
+    });
+    for value in [1.0, f64::NAN, 3.0, f64::INFINITY, f64::NEG_INFINITY] {
+        stats.push(value);
+    }
+    let result = stats.finish();
+    assert_eq!(result.count, 2);
+    assert_eq!(result.non_finite, 3);
+    assert_eq!(result.mean, 2.0);
+    assert_eq!(result.std_dev, 1.0);
+    assert_eq!(result.solid_fraction, 1.0);
+    assert_eq!(result.threshold_coverage[0].fraction, 1.0);
+    assert_eq!(result.histogram.bins.iter().sum::<u32>(), 2);
+    assert!(result
+        .percentiles
+        .iter()
+        .all(|p| (1.0..=3.0).contains(&p.value)));
+}
+
+// ── Gradients ────────────────────────────────────────────────────
+
+#[test]
+fn gradient_analytic_matches_finite_difference() {
+    let graph = json!({
+        "Type": "Normalizer",
+        "FromMin": -1.0,
+        "FromMax": 1.0,
+        "ToMin": 0.0,
+        "ToMax": 4.0,
+        "Input": {
+            "Type": "Sum",
+            "Inputs": [
+                { "Type": "SimplexNoise2D", "Scale": 32.0, "Seed": "g" },
+                { "Type": "Constant", "Value": 0.1 }
+            ]
+        }
+    });
+    let evaluator = DensityEvaluator::from_json(&graph).unwrap();
+    let h = 0.01;
+    for i in 0..8 {
+        let (x, z) = (i as f64 * 7.3, i as f64 * -3.1);
+        let (value, grad) = evaluator.evaluate_gradient(x, 64.0, z, h);
+        assert_eq!(value, evaluator.evaluate(x, 64.0, z));
+
+        let fd_x =
+            (evaluator.evaluate(x + h, 64.0, z) - evaluator.evaluate(x - h, 64.0, z)) / (2.0 * h);
+        assert!((grad[0] - fd_x).abs() < 1e-6);
+        assert_eq!(grad[1], 0.0);
+    }
+}
+
+#[test]
+fn gradient_is_zero_where_clamped() {
+    let graph = json!({
+        "Type": "Clamp",
+        "WallA": -0.5,
+        "WallB": -0.25,
+        "Input": { "Type": "Constant", "Value": 3.0 }
+    });
+    let evaluator = DensityEvaluator::from_json(&graph).unwrap();
+    assert_eq!(
+        evaluator.evaluate_gradient(1.0, 2.0, 3.0, 0.5),
+        (-0.25, [0.0; 3])
+    );
+}
+
+// ── Tiles ────────────────────────────────────────────────────────
+
+#[test]
+fn tile_keys_cover_world_space() {
+    let finest = TileKey {
+        graph_hash: 0,
+        z: MAX_ZOOM,
+        x: -1,
+        y: 2,
+    };
+    assert_eq!(finest.span(), TILE_SIZE as f64);
+    assert_eq!(finest.origin(), (-256.0, 512.0));
+
+    let coarsest = TileKey {
+        graph_hash: 0,
+        z: 0,
+        x: 0,
+        y: 0,
+    };
+    assert_eq!(coarsest.span(), 16384.0);
+}
+
+#[test]
+fn tile_contents_do_not_depend_on_request_order() {
+    let graph = json!({ "Type": "SimplexNoise2D", "Scale": 128.0, "Seed": "t" });
+    let evaluator = DensityEvaluator::from_json(&graph).unwrap();
+    let dir = std::env::temp_dir().join(format!("terranova-tiles-{}", std::process::id()));
+    let hash = tiles::graph_hash(&graph, 0.0);
+
+    let cache = Mutex::new(TileCache::default());
+    cache.lock().unwrap().set_disk_root(dir.clone());
+
+    // Cache the four finest children of a coarser tile first
+    let parent = TileKey {
+        graph_hash: hash,
+        z: MAX_ZOOM - 2,
+        x: 0,
+        y: 0,
+    };
+    for (x, y) in [(0, 0), (1, 0), (0, 1), (1, 1)] {
+        let key = TileKey {
+            graph_hash: hash,
+            z: MAX_ZOOM - 1,
+            x,
+            y,
+        };
+        let (_, source) = tiles::resolve_tile(&cache, &evaluator, &key, 0.0);
+        assert_eq!(source, TileSource::Evaluated);
+    }
+
+    let (tile, source) = tiles::resolve_tile(&cache, &evaluator, &parent, 0.0);
+    assert_eq!(source, TileSource::Evaluated);
+    assert_eq!(tile.values.len(), TILE_SIZE * TILE_SIZE);
+    // Same contents as with nothing else cached
+    let fresh = Mutex::new(TileCache::default());
+    let (alone, _) = tiles::resolve_tile(&fresh, &evaluator, &parent, 0.0);
+    assert_eq!(alone.values, tile.values);
+
+    let (_, source) = tiles::resolve_tile(&cache, &evaluator, &parent, 0.0);
+    assert_eq!(source, TileSource::Memory);
+
+    // A fresh cache over the same directory reads the tile back from disk
+    let reopened = Mutex::new(TileCache::default());
+    reopened.lock().unwrap().set_disk_root(dir.clone());
+    let (from_disk, source) = tiles::resolve_tile(&reopened, &evaluator, &parent, 0.0);
+    assert_eq!(source, TileSource::Disk);
+    assert_eq!(from_disk.values, tile.values);
+
+    reopened.lock().unwrap().clear().unwrap();
+    assert!(!dir.exists());
+}
@@ -0,0 +1,324 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+use serde_json::Value;
+
+use super::evaluator::DensityEvaluator;
+
+/// Samples along each edge of a tile.
+pub const TILE_SIZE: usize = 256;
+
+/// Finest zoom level: one sample per block. Zoom 0 spans 16384 blocks per tile.
+pub const MAX_ZOOM: u32 = 6;
+
+/// Version of the cached tile contents, mixed into every graph hash. Bump it
+/// whenever evaluator output or the tile encoding changes so tiles cached on
+/// disk by an older build are never served.
+pub const TILE_CACHE_VERSION: u32 = 1;
+
+/// Encoding of tile files on disk.
+const TILE_FORMAT: &[u8] = b"f32le";
+
+/// Tiles kept in memory.
+const MEMORY_CAPACITY: usize = 256;
+
+/// Upper bound on the on-disk cache size.
+const DISK_CAPACITY_BYTES: u64 = 512 * 1024 * 1024;
+
+/// Address of a tile in the pyramid for one graph.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TileKey {
+    pub graph_hash: u64,
+    pub z: u32,
+    pub x: i32,
+    pub y: i32,
+}
+
+impl TileKey {
+    /// Blocks covered by one tile edge at this zoom.
+    pub fn span(&self) -> f64 {
+        (TILE_SIZE as u64 * (1u64 << (MAX_ZOOM - self.z))) as f64
+    }
+
+    /// World X/Z of the tile's minimum corner.
+    pub fn origin(&self) -> (f64, f64) {
+        let span = self.span();
+        (self.x as f64 * span, self.y as f64 * span)
+    }
+
+    fn relative_path(&self) -> PathBuf {
+        PathBuf::from(format!("{:016x}", self.graph_hash))
+            .join(self.z.to_string())
+            .join(format!("{}_{}.bin", self.x, self.y))
+    }
+}
+
+/// A square block of density samples, row-major with Z as the row axis.
+pub struct Tile {
+    pub values: Vec<f32>,
+    pub min_value: f32,
+    pub max_value: f32,
+}
+
+impl Tile {
+    fn from_values(values: Vec<f32>) -> Self {
+        let (min_value, max_value) = values
+            .iter()
+            .fold((f32::MAX, f32::MIN), |(lo, hi), &v| (lo.min(v), hi.max(v)));
+        Tile {
+            values,
+            min_value,
+            max_value,
+        }
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        self.values.iter().flat_map(|v| v.to_le_bytes()).collect()
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        if bytes.len() != TILE_SIZE * TILE_SIZE * 4 {
+            return None;
+        }
+        let values = bytes
+            .chunks_exact(4)
+            .map(|c| f32::from_le_bytes([c[0], c[1], c[2], c[3]]))
+            .collect();
+        Some(Tile::from_values(values))
+    }
+}
+
+/// Where a tile was served from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+pub enum TileSource {
+    Memory,
+    Disk,
+    Evaluated,
+}
+
+/// Stable 64-bit FNV-1a hash of a graph and the Y level it is sampled at,
+/// salted with the cache version, tile size and tile format.
+pub fn graph_hash(graph: &Value, y_level: f64) -> u64 {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    let text = graph.to_string();
+    let salt = TILE_CACHE_VERSION
+        .to_le_bytes()
+        .into_iter()
+        .chain((TILE_SIZE as u32).to_le_bytes())
+        .chain(TILE_FORMAT.iter().copied());
+    for byte in salt.chain(text.bytes()).chain(y_level.to_le_bytes()) {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x0000_0100_0000_01b3);
+    }
+    hash
+}
+
+/// Two-level LRU cache of density tiles (memory, then disk).
+#[derive(Default)]
+pub struct TileCache {
+    memory: HashMap<TileKey, (Arc<Tile>, u64)>,
+    tick: u64,
+    disk_root: Option<PathBuf>,
+    /// Disk entries with their size and last use, loaded on first disk access
+    disk_index: Option<HashMap<PathBuf, (u64, SystemTime)>>,
+}
+
+impl TileCache {
+    /// Enable the on-disk cache under `root`.
+    pub fn set_disk_root(&mut self, root: PathBuf) {
+        if self.disk_root.as_ref() != Some(&root) {
+            self.disk_root = Some(root);
+            self.disk_index = None;
+        }
+    }
+
+    /// Look a tile up in memory, then on disk (promoting it to memory).
+    pub fn get(&mut self, key: &TileKey) -> Option<(Arc<Tile>, TileSource)> {
+        self.tick += 1;
+        if let Some((tile, used)) = self.memory.get_mut(key) {
+            *used = self.tick;
+            return Some((tile.clone(), TileSource::Memory));
+        }
+
+        let path = self.disk_root.as_ref()?.join(key.relative_path());
+        let tile = Arc::new(Tile::from_bytes(&fs::read(&path).ok()?)?);
+        self.touch_disk(&path);
+        self.insert_memory(*key, tile.clone());
+        Some((tile, TileSource::Disk))
+    }
+
+    /// Store a tile in memory and on disk.
+    pub fn insert(&mut self, key: TileKey, tile: Arc<Tile>) {
+        self.insert_memory(key, tile.clone());
+        if let Some(root) = self.disk_root.clone() {
+            // Disk caching is best-effort; a failed write only costs a re-evaluation
+            let _ = self.write_disk(&root, &key, &tile);
+        }
+    }
+
+    /// Drop every cached tile, in memory and on disk.
+    pub fn clear(&mut self) -> Result<(), String> {
+        self.memory.clear();
+        self.disk_index = None;
+        if let Some(root) = &self.disk_root {
+            if root.is_dir() {
+                fs::remove_dir_all(root)
+                    .map_err(|e| format!("Failed to clear tile cache: {}", e))?;
+            }
+        }
+        Ok(())
+    }
+
+    fn insert_memory(&mut self, key: TileKey, tile: Arc<Tile>) {
+        self.tick += 1;
+        self.memory.insert(key, (tile, self.tick));
+        while self.memory.len() > MEMORY_CAPACITY {
+            let oldest = self
+                .memory
+                .iter()
+                .min_by_key(|(_, (_, used))| *used)
+                .map(|(k, _)| *k);
+            match oldest {
+                Some(k) => self.memory.remove(&k),
+                None => break,
+            };
+        }
+    }
+
+    fn write_disk(&mut self, root: &Path, key: &TileKey, tile: &Tile) -> std::io::Result<()> {
+        let path = root.join(key.relative_path());
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let bytes = tile.to_bytes();
+        let temp_path = path.with_extension("tmp");
+        fs::write(&temp_path, &bytes)?;
+        fs::rename(&temp_path, &path)?;
+
+        let index = self.disk_index(root);
+        index.insert(path, (bytes.len() as u64, SystemTime::now()));
+        self.evict_disk();
+        Ok(())
+    }
+
+    fn touch_disk(&mut self, path: &Path) {
+        let Some(root) = self.disk_root.clone() else {
+            return;
+        };
+        let now = SystemTime::now();
+        if let Ok(file) = fs::File::options().write(true).open(path) {
+            let _ = file.set_modified(now);
+        }
+        if let Some(entry) = self.disk_index(&root).get_mut(path) {
+            entry.1 = now;
+        }
+    }
+
+    fn disk_index(&mut self, root: &Path) -> &mut HashMap<PathBuf, (u64, SystemTime)> {
+        self.disk_index.get_or_insert_with(|| {
+            let mut index = HashMap::new();
+            scan_tiles(root, &mut index);
+            index
+        })
+    }
+
+    /// Remove least recently used tiles until the disk cache fits its budget.
+    fn evict_disk(&mut self) {
+        let Some(index) = self.disk_index.as_mut() else {
+            return;
+        };
+        let mut total: u64 = index.values().map(|(size, _)| size).sum();
+        if total <= DISK_CAPACITY_BYTES {
+            return;
+        }
+        let mut entries: Vec<(PathBuf, u64, SystemTime)> = index
+            .iter()
+            .map(|(p, (size, used))| (p.clone(), *size, *used))
+            .collect();
+        entries.sort_by_key(|(_, _, used)| *used);
+        for (path, size, _) in entries {
+            if total <= DISK_CAPACITY_BYTES {
+                break;
+            }
+            let _ = fs::remove_file(&path);
+            index.remove(&path);
+            total = total.saturating_sub(size);
+        }
+    }
+}
+
+fn scan_tiles(dir: &Path, index: &mut HashMap<PathBuf, (u64, SystemTime)>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            scan_tiles(&path, index);
+        } else if path.extension().is_some_and(|ext| ext == "bin") {
+            if let Ok(meta) = entry.metadata() {
+                let used = meta.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+                index.insert(path, (meta.len(), used));
+            }
+        }
+    }
+}
+
+/// Evaluate a tile. Coarse tiles are evaluated at twice the resolution and
+/// box-filtered down so they don't alias; every tile is built this way, so
+/// its contents never depend on what else happens to be cached.
+pub fn evaluate_tile(evaluator: &DensityEvaluator, key: &TileKey, y_level: f64) -> Tile {
+    let (origin_x, origin_z) = key.origin();
+    let supersample = if key.z < MAX_ZOOM { 2 } else { 1 };
+    let n = TILE_SIZE * supersample;
+    let step = key.span() / n as f64;
+
+    let mut samples = Vec::with_capacity(n * n);
+    for z_idx in 0..n {
+        let z = origin_z + (z_idx as f64 + 0.5) * step;
+        for x_idx in 0..n {
+            let x = origin_x + (x_idx as f64 + 0.5) * step;
+            samples.push(evaluator.evaluate(x, y_level, z) as f32);
+        }
+    }
+
+    if supersample == 1 {
+        Tile::from_values(samples)
+    } else {
+        Tile::from_values(downsample(&samples, n))
+    }
+}
+
+/// 2×2 box filter of an n×n grid.
+fn downsample(samples: &[f32], n: usize) -> Vec<f32> {
+    let half = n / 2;
+    let mut out = Vec::with_capacity(half * half);
+    for row in 0..half {
+        for col in 0..half {
+            let i = row * 2 * n + col * 2;
+            out.push((samples[i] + samples[i + 1] + samples[i + n] + samples[i + n + 1]) * 0.25);
+        }
+    }
+    out
+}
+
+/// Fetch a tile from the cache, or evaluate and cache it. The cache is only
+/// locked for lookups and inserts, never while a tile is evaluated.
+pub fn resolve_tile(
+    cache: &Mutex<TileCache>,
+    evaluator: &DensityEvaluator,
+    key: &TileKey,
+    y_level: f64,
+) -> (Arc<Tile>, TileSource) {
+    let lock = || cache.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(hit) = lock().get(key) {
+        return hit;
+    }
+
+    let tile = Arc::new(evaluate_tile(evaluator, key, y_level));
+    lock().insert(*key, tile.clone());
+    (tile, TileSource::Evaluated)
+}
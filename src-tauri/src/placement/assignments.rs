@@ -0,0 +1,309 @@
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::material::evaluator::parse_density_with_positions;
+use crate::noise::evaluator::DensityEvaluator;
+use crate::noise::hash::{position_random, seed_hash};
+use crate::schema::assignments::AssignmentType;
+
+use super::props::{parse_prop, PlacementScope, Prop, PropParser};
+
+/// A prop an assignment can hand out, labelled by type and JSON path.
+pub struct AssignedProp {
+    pub label: String,
+    pub prop: Box<Prop>,
+}
+
+/// A range of values mapped to a nested assignment.
+pub struct AssignmentRange {
+    /// Half-open [min, max)
+    pub min: f64,
+    pub max: f64,
+    pub assignment: Assignment,
+}
+
+/// A compiled prop assignment: which prop, if any, a position gets.
+pub enum Assignment {
+    Constant(Option<AssignedProp>),
+    /// Picks a range by a density value at the position
+    FieldFunction {
+        field: DensityEvaluator,
+        ranges: Vec<AssignmentRange>,
+    },
+    /// Picks a range by the position's Y
+    Sandwich(Vec<AssignmentRange>),
+    Weighted {
+        seed: i32,
+        skip_chance: f64,
+        entries: Vec<(f64, Assignment)>,
+    },
+}
+
+/// The outcome of assigning one position, with each decision on the way.
+pub struct AssignmentTrace<'a> {
+    pub prop: Option<&'a AssignedProp>,
+    pub steps: Vec<String>,
+}
+
+/// Seed offset for the SkipChance roll, so it doesn't track the entry roll.
+const SKIP_SALT: i32 = 0x5bd1_e995;
+
+impl Assignment {
+    /// Parse a V2 assignment JSON on its own, with exports from `scope`.
+    pub fn from_json(json: &Value, scope: &PlacementScope) -> Result<Self, String> {
+        let mut scope = scope.clone();
+        scope.collect_exports(json);
+        let mut parser = PropParser {
+            scope: &scope,
+            imports: Vec::new(),
+        };
+        parse_assignment(json, "$", &mut parser)
+    }
+
+    /// The prop assigned at a position.
+    pub fn pick(&self, position: [f64; 3]) -> Option<&Prop> {
+        self.trace(position).prop.map(|p| &*p.prop)
+    }
+
+    /// The prop assigned at a position and why.
+    pub fn trace(&self, position: [f64; 3]) -> AssignmentTrace<'_> {
+        let mut steps = Vec::new();
+        let prop = self.resolve(position, &mut steps);
+        AssignmentTrace { prop, steps }
+    }
+
+    fn resolve(&self, position: [f64; 3], steps: &mut Vec<String>) -> Option<&AssignedProp> {
+        let [x, y, z] = position;
+        match self {
+            Assignment::Constant(prop) => {
+                steps.push(match prop {
+                    Some(p) => format!("Constant: {}", p.label),
+                    None => "Constant: no prop".to_string(),
+                });
+                prop.as_ref()
+            }
+
+            Assignment::FieldFunction { field, ranges } => {
+                let value = field.evaluate(x, y, z);
+                resolve_range(ranges, value, "FieldFunction value", position, steps)
+            }
+
+            Assignment::Sandwich(ranges) => resolve_range(ranges, y, "Sandwich Y", position, steps),
+
+            Assignment::Weighted {
+                seed,
+                skip_chance,
+                entries,
+            } => {
+                let (bx, by, bz) = (x.floor() as i32, y.floor() as i32, z.floor() as i32);
+                let skip_roll = position_random(bx, by, bz, seed ^ SKIP_SALT);
+                if skip_roll < *skip_chance {
+                    steps.push(format!(
+                        "Weighted: skipped (roll {:.3} < SkipChance {:.3})",
+                        skip_roll, skip_chance
+                    ));
+                    return None;
+                }
+                let total: f64 = entries.iter().map(|(w, _)| w).sum();
+                if total <= 0.0 {
+                    steps.push("Weighted: no entries with weight".to_string());
+                    return None;
+                }
+                let target = position_random(bx, by, bz, *seed) * total;
+                let mut cumulative = 0.0;
+                for (i, (weight, assignment)) in entries.iter().enumerate() {
+                    cumulative += weight;
+                    if target < cumulative || i == entries.len() - 1 {
+                        steps.push(format!(
+                            "Weighted: entry {} (weight {} of {})",
+                            i, weight, total
+                        ));
+                        return assignment.resolve(position, steps);
+                    }
+                }
+                None
+            }
+        }
+    }
+}
+
+fn resolve_range<'a>(
+    ranges: &'a [AssignmentRange],
+    value: f64,
+    what: &str,
+    position: [f64; 3],
+    steps: &mut Vec<String>,
+) -> Option<&'a AssignedProp> {
+    match ranges.iter().position(|r| value >= r.min && value < r.max) {
+        Some(i) => {
+            let range = &ranges[i];
+            steps.push(format!(
+                "{} {:.3} in delimiter {} [{}, {})",
+                what, value, i, range.min, range.max
+            ));
+            range.assignment.resolve(position, steps)
+        }
+        None => {
+            steps.push(format!("{} {:.3} outside every delimiter", what, value));
+            None
+        }
+    }
+}
+
+/// How often each prop (or nothing) was assigned over a set of positions.
+#[derive(Debug, Clone, Serialize)]
+pub struct AssignmentHistogramEntry {
+    /// Prop label; `None` for skipped or unassigned positions
+    pub prop: Option<String>,
+    pub count: usize,
+    /// Share of all positions, 0–100
+    pub percent: f64,
+}
+
+pub fn assignment_histogram(
+    assignment: &Assignment,
+    positions: &[[f64; 3]],
+) -> Vec<AssignmentHistogramEntry> {
+    let mut counts: BTreeMap<Option<String>, usize> = BTreeMap::new();
+    for &position in positions {
+        let label = assignment.trace(position).prop.map(|p| p.label.clone());
+        *counts.entry(label).or_insert(0) += 1;
+    }
+    let total = positions.len().max(1) as f64;
+    counts
+        .into_iter()
+        .map(|(prop, count)| AssignmentHistogramEntry {
+            prop,
+            count,
+            percent: count as f64 / total * 100.0,
+        })
+        .collect()
+}
+
+/// The nested assignment of a delimiter or weighted entry.
+fn child_assignment(
+    item: &Value,
+    path: &str,
+    parser: &mut PropParser,
+) -> Result<Assignment, String> {
+    match item
+        .get("Assignments")
+        .or_else(|| item.get("Assignment"))
+        .filter(|v| v.is_object())
+    {
+        Some(child) => parse_assignment(child, &format!("{}.Assignments", path), parser),
+        None => Ok(Assignment::Constant(None)),
+    }
+}
+
+/// Parse delimiters with the first matching pair of bound keys.
+fn parse_ranges(
+    items: &[Value],
+    keys: &[(&str, &str)],
+    path: &str,
+    parser: &mut PropParser,
+) -> Result<Vec<AssignmentRange>, String> {
+    let mut ranges = Vec::new();
+    for (i, item) in items.iter().enumerate() {
+        let min = keys.iter().find_map(|(k, _)| item.get(*k)?.as_f64());
+        let max = keys.iter().find_map(|(_, k)| item.get(*k)?.as_f64());
+        let item_path = format!("{}[{}]", path, i);
+        ranges.push(AssignmentRange {
+            min: min.unwrap_or(f64::NEG_INFINITY),
+            max: max.unwrap_or(f64::INFINITY),
+            assignment: child_assignment(item, &item_path, parser)?,
+        });
+    }
+    Ok(ranges)
+}
+
+/// Parse a V2 assignment JSON. Skipped and unknown nodes assign nothing.
+pub(super) fn parse_assignment(
+    json: &Value,
+    path: &str,
+    parser: &mut PropParser,
+) -> Result<Assignment, String> {
+    let obj = json
+        .as_object()
+        .ok_or_else(|| format!("Assignment at {} must be a JSON object", path))?;
+    if obj.get("Type").and_then(|v| v.as_str()).is_none() {
+        return Err(format!("Missing 'Type' field at {}", path));
+    }
+    let none = Assignment::Constant(None);
+    if obj.get("Skip").and_then(|v| v.as_bool()) == Some(true) {
+        return Ok(none);
+    }
+    let Ok(assignment) = serde_json::from_value::<AssignmentType>(json.clone()) else {
+        return Ok(none);
+    };
+
+    use AssignmentType as A;
+    Ok(match assignment {
+        A::Constant { prop } => Assignment::Constant(match prop {
+            Some(prop) if prop.is_object() => {
+                let prop_path = format!("{}.Prop", path);
+                let prop = parse_prop(&prop, &prop_path, parser)?;
+                Some(AssignedProp {
+                    label: format!("{} at {}", prop.type_name(), prop_path),
+                    prop: Box::new(prop),
+                })
+            }
+            _ => None,
+        }),
+
+        A::FieldFunction {
+            field_function,
+            delimiters,
+        } => Assignment::FieldFunction {
+            field: parse_density_with_positions(field_function.as_ref(), &parser.scope.positions)
+                .map_err(|e| format!("{}.FieldFunction: {}", path, e))?,
+            ranges: parse_ranges(
+                &delimiters,
+                &[("Min", "Max"), ("From", "To")],
+                &format!("{}.Delimiters", path),
+                parser,
+            )?,
+        },
+
+        A::Sandwich { delimiters } => Assignment::Sandwich(parse_ranges(
+            &delimiters,
+            &[("MinY", "MaxY"), ("BottomY", "TopY"), ("Min", "Max")],
+            &format!("{}.Delimiters", path),
+            parser,
+        )?),
+
+        A::Weighted {
+            skip_chance,
+            seed,
+            weighted_assignments,
+        } => {
+            let mut entries = Vec::new();
+            for (i, item) in weighted_assignments.iter().enumerate() {
+                let weight = item.get("Weight").and_then(|v| v.as_f64()).unwrap_or(1.0);
+                let item_path = format!("{}.WeightedAssignments[{}]", path, i);
+                entries.push((weight.max(0.0), child_assignment(item, &item_path, parser)?));
+            }
+            Assignment::Weighted {
+                seed: seed_hash(&seed),
+                skip_chance: skip_chance.clamp(0.0, 1.0),
+                entries,
+            }
+        }
+
+        A::Imported { name } => {
+            let Some(export) = parser.scope.assignments.get(&name) else {
+                return Err(format!("Unknown assignment export '{}' at {}", name, path));
+            };
+            let key = format!("Assignment/{}", name);
+            if parser.imports.contains(&key) {
+                return Err(format!("Cyclic assignment import '{}' at {}", name, path));
+            }
+            parser.imports.push(key);
+            let assignment = parse_assignment(export, &format!("{}<{}>", path, name), parser);
+            parser.imports.pop();
+            assignment?
+        }
+    })
+}
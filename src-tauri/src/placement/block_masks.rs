@@ -0,0 +1,194 @@
+use std::collections::{BTreeMap, HashMap};
+
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::schema::block_masks::BlockMaskAsset;
+use crate::worldgen::chunk::EMPTY_BLOCK;
+
+use super::material_set::MaterialSet;
+use super::prefabs::PrefabBlock;
+use super::voxels::VoxelChunk;
+
+/// A per-source replacement rule from a mask's `Advanced` list.
+#[derive(Debug, Clone)]
+pub struct MaskEntry {
+    /// Prop blocks the rule applies to
+    pub source: MaterialSet,
+    /// Terrain blocks those prop blocks may replace
+    pub can_replace: MaterialSet,
+}
+
+/// A compiled block mask: which prop blocks are placed, and which terrain
+/// blocks they may replace.
+///
+/// Rules are checked in order for each prop block: `DontPlace`, then the
+/// first `Advanced` entry whose source matches, and `DontReplace` only when
+/// no entry does. Imported masks contribute their rules ahead of local ones.
+#[derive(Debug, Clone, Default)]
+pub struct BlockMask {
+    pub dont_place: Vec<MaterialSet>,
+    pub dont_replace: Vec<MaterialSet>,
+    pub advanced: Vec<MaskEntry>,
+}
+
+/// A prop block a mask kept out, and the rule that did.
+#[derive(Debug, Clone, Serialize)]
+pub struct MaskRejection {
+    /// `DontPlace`, `DontReplace` or `Advanced[i]`
+    pub rule: String,
+    pub x: i32,
+    pub y: i32,
+    pub z: i32,
+    pub block: String,
+    /// Terrain block that stayed
+    pub existing: String,
+}
+
+/// Terrain and prop blocks merged under a mask.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct MaskResult {
+    /// The final block at every prop block position
+    pub merged: Vec<PrefabBlock>,
+    /// Prop blocks that were written
+    pub placed: usize,
+    pub rejected: Vec<MaskRejection>,
+    /// Rejections by rule
+    pub counts: BTreeMap<String, usize>,
+}
+
+impl BlockMask {
+    /// Parse a `BlockMask` object, resolving `Import` against masks exported
+    /// by name. A missing mask places every block.
+    pub fn from_json(
+        json: Option<&Value>,
+        exports: &HashMap<String, Value>,
+    ) -> Result<Self, String> {
+        let mut mask = BlockMask::default();
+        if let Some(json) = json.filter(|v| v.is_object()) {
+            mask.extend(json, "$", exports, &mut Vec::new())?;
+        }
+        Ok(mask)
+    }
+
+    fn extend(
+        &mut self,
+        json: &Value,
+        path: &str,
+        exports: &HashMap<String, Value>,
+        imports: &mut Vec<String>,
+    ) -> Result<(), String> {
+        let asset: BlockMaskAsset = serde_json::from_value(json.clone())
+            .map_err(|e| format!("Invalid block mask at {}: {}", path, e))?;
+        if !asset.import.is_empty() {
+            let name = &asset.import;
+            let Some(export) = exports.get(name) else {
+                return Err(format!("Unknown block mask export '{}' at {}", name, path));
+            };
+            if imports.contains(name) {
+                return Err(format!("Cyclic block mask import '{}' at {}", name, path));
+            }
+            imports.push(name.clone());
+            let imported = self.extend(export, &format!("{}<{}>", path, name), exports, imports);
+            imports.pop();
+            imported?;
+        }
+        self.dont_place
+            .extend(asset.dont_place.as_ref().map(MaterialSet::from_json));
+        self.dont_replace
+            .extend(asset.dont_replace.as_ref().map(MaterialSet::from_json));
+        for entry in &asset.advanced {
+            // An entry without a source can't match any block
+            let Some(source) = &entry.source else {
+                continue;
+            };
+            self.advanced.push(MaskEntry {
+                source: MaterialSet::from_json(source),
+                can_replace: entry
+                    .can_replace
+                    .as_ref()
+                    .map(MaterialSet::from_json)
+                    .unwrap_or_default(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Whether a prop block passes `DontPlace`.
+    pub fn places(&self, block: &str) -> bool {
+        !self.dont_place.iter().any(|set| set.contains(block))
+    }
+
+    /// The rule that keeps `block` from replacing `existing`, if any.
+    pub fn rejects(&self, block: &str, existing: &str) -> Option<String> {
+        if !self.places(block) {
+            return Some("DontPlace".into());
+        }
+        if let Some(i) = self.advanced.iter().position(|e| e.source.contains(block)) {
+            return (!self.advanced[i].can_replace.contains(existing))
+                .then(|| format!("Advanced[{}]", i));
+        }
+        self.dont_replace
+            .iter()
+            .any(|set| set.contains(existing))
+            .then(|| "DontReplace".into())
+    }
+
+    /// Merge prop blocks in world positions into the terrain. Positions
+    /// outside the chunk count as air.
+    pub fn apply(&self, voxels: &VoxelChunk, blocks: &[PrefabBlock]) -> MaskResult {
+        let mut result = MaskResult::default();
+        for block in blocks {
+            let existing = voxels
+                .block(block.x, block.y, block.z)
+                .unwrap_or(EMPTY_BLOCK);
+            match self.rejects(&block.name, existing) {
+                None => {
+                    result.placed += 1;
+                    result.merged.push(block.clone());
+                }
+                Some(rule) => {
+                    *result.counts.entry(rule.clone()).or_insert(0) += 1;
+                    result.rejected.push(MaskRejection {
+                        rule,
+                        x: block.x,
+                        y: block.y,
+                        z: block.z,
+                        block: block.name.clone(),
+                        existing: existing.to_string(),
+                    });
+                    result.merged.push(PrefabBlock {
+                        name: existing.to_string(),
+                        rotation: 0,
+                        ..block.clone()
+                    });
+                }
+            }
+        }
+        result
+    }
+}
+
+/// Register every named block mask in `json`: objects without a `Type`
+/// that set `ExportAs`.
+pub fn collect_block_mask_exports(json: &Value, exports: &mut HashMap<String, Value>) {
+    match json {
+        Value::Object(obj) => {
+            if !obj.contains_key("Type") {
+                if let Some(name) = obj
+                    .get("ExportAs")
+                    .and_then(|v| v.as_str())
+                    .filter(|n| !n.is_empty())
+                {
+                    exports.insert(name.to_string(), json.clone());
+                }
+            }
+            obj.values()
+                .for_each(|v| collect_block_mask_exports(v, exports));
+        }
+        Value::Array(items) => items
+            .iter()
+            .for_each(|v| collect_block_mask_exports(v, exports)),
+        _ => {}
+    }
+}
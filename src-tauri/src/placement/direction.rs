@@ -0,0 +1,58 @@
+use serde::Serialize;
+
+/// An axis direction, as named by pattern Facings/Directions and
+/// directionality settings. North is -Z and East is +X.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum Direction {
+    Up,
+    Down,
+    North,
+    South,
+    East,
+    West,
+}
+
+impl Direction {
+    pub const HORIZONTAL: [Direction; 4] = [
+        Direction::North,
+        Direction::East,
+        Direction::South,
+        Direction::West,
+    ];
+
+    /// Parse a direction name, case-insensitively: `North`, `NORTH` or `N`.
+    pub fn from_name(name: &str) -> Option<Self> {
+        Some(match name.to_ascii_uppercase().as_str() {
+            "UP" | "U" => Direction::Up,
+            "DOWN" | "D" => Direction::Down,
+            "NORTH" | "N" => Direction::North,
+            "SOUTH" | "S" => Direction::South,
+            "EAST" | "E" => Direction::East,
+            "WEST" | "W" => Direction::West,
+            _ => return None,
+        })
+    }
+
+    /// Parse a list of direction names, failing on the first unknown one.
+    pub fn parse_list(names: &[String], path: &str) -> Result<Vec<Self>, String> {
+        names
+            .iter()
+            .map(|n| {
+                Direction::from_name(n)
+                    .ok_or_else(|| format!("Unknown direction '{}' at {}", n, path))
+            })
+            .collect()
+    }
+
+    /// Unit step as (dx, dy, dz).
+    pub fn offset(self) -> (i32, i32, i32) {
+        match self {
+            Direction::Up => (0, 1, 0),
+            Direction::Down => (0, -1, 0),
+            Direction::North => (0, 0, -1),
+            Direction::South => (0, 0, 1),
+            Direction::East => (1, 0, 0),
+            Direction::West => (-1, 0, 0),
+        }
+    }
+}
@@ -0,0 +1,218 @@
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::noise::hash::{position_random, seed_hash};
+use crate::schema::props::DirectionalityType;
+
+use super::direction::Direction;
+use super::patterns::Pattern;
+use super::prefabs::quarter_turns;
+use super::props::PlacementScope;
+use super::voxels::VoxelChunk;
+
+/// A compiled prefab directionality: which way a prefab faces at an origin.
+pub enum Directionality {
+    /// Always the same rotation
+    Static { turns: i32 },
+    /// One of the four rotations, picked per origin
+    Random { seed: i32 },
+    /// Faces a direction whose pattern matches; the prefab is authored facing
+    /// `initial`, so facing East from North is one clockwise turn
+    Pattern {
+        initial: Direction,
+        seed: i32,
+        patterns: Vec<(Direction, Pattern)>,
+    },
+}
+
+/// The rotation chosen at an origin.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Orientation {
+    /// Quarter turns clockwise seen from above
+    pub turns: i32,
+    /// Directional pattern the rotation came from, for Pattern directionality
+    pub direction: Option<Direction>,
+    /// Every directional pattern that matched, in North, East, South, West order
+    pub matched: Vec<Direction>,
+}
+
+impl Directionality {
+    /// Parse a V2 directionality JSON into the rotation rule and the pattern
+    /// an origin must match to be placed at all. A missing directionality
+    /// is unrotated and accepts every origin.
+    pub fn from_json(
+        json: Option<&Value>,
+        scope: &PlacementScope,
+    ) -> Result<(Self, Pattern), String> {
+        let unrotated = (Directionality::Static { turns: 0 }, Pattern::Constant(true));
+        let Some(json) = json.filter(|v| v.is_object()) else {
+            return Ok(unrotated);
+        };
+        let mut scope = scope.clone();
+        scope.collect_exports(json);
+        parse_directionality(json, "$", &scope, &mut Vec::new())
+    }
+
+    /// The rotation at an origin, or `None` when no directional pattern matches.
+    pub fn orient(&self, voxels: &VoxelChunk, [x, y, z]: [i32; 3]) -> Option<Orientation> {
+        match self {
+            Directionality::Static { turns } => Some(Orientation {
+                turns: *turns,
+                direction: None,
+                matched: Vec::new(),
+            }),
+
+            Directionality::Random { seed } => Some(Orientation {
+                turns: (position_random(x, y, z, *seed) * 4.0) as i32 % 4,
+                direction: None,
+                matched: Vec::new(),
+            }),
+
+            Directionality::Pattern {
+                initial,
+                seed,
+                patterns,
+            } => {
+                let matched: Vec<Direction> = patterns
+                    .iter()
+                    .filter(|(_, pattern)| pattern.matches(voxels, x, y, z))
+                    .map(|(direction, _)| *direction)
+                    .collect();
+                if matched.is_empty() {
+                    return None;
+                }
+                let roll = position_random(x, y, z, *seed);
+                let index = ((roll * matched.len() as f64) as usize).min(matched.len() - 1);
+                let direction = matched[index];
+                Some(Orientation {
+                    turns: (quarter_index(direction) - quarter_index(*initial)).rem_euclid(4),
+                    direction: Some(direction),
+                    matched,
+                })
+            }
+        }
+    }
+}
+
+/// Position of a horizontal direction in clockwise order from North.
+fn quarter_index(direction: Direction) -> i32 {
+    Direction::HORIZONTAL
+        .iter()
+        .position(|d| *d == direction)
+        .unwrap_or(0) as i32
+}
+
+fn parse_pattern(
+    json: Option<&Value>,
+    path: &str,
+    scope: &PlacementScope,
+) -> Result<Pattern, String> {
+    match json {
+        Some(p) if p.is_object() => {
+            Pattern::from_json_with_positions(p, &scope.patterns, &scope.positions)
+                .map_err(|e| format!("{}: {}", path, e))
+        }
+        _ => Ok(Pattern::Constant(true)),
+    }
+}
+
+fn parse_directionality(
+    json: &Value,
+    path: &str,
+    scope: &PlacementScope,
+    imports: &mut Vec<String>,
+) -> Result<(Directionality, Pattern), String> {
+    let obj = json
+        .as_object()
+        .ok_or_else(|| format!("Directionality at {} must be a JSON object", path))?;
+    if obj.get("Type").and_then(|v| v.as_str()).is_none() {
+        return Err(format!("Missing 'Type' field at {}", path));
+    }
+    let directionality = serde_json::from_value::<DirectionalityType>(json.clone())
+        .map_err(|e| format!("Invalid directionality at {}: {}", path, e))?;
+
+    use DirectionalityType as D;
+    Ok(match directionality {
+        D::Static { rotation, pattern } => (
+            Directionality::Static {
+                turns: quarter_turns(rotation),
+            },
+            parse_pattern(pattern.as_ref(), &format!("{}.Pattern", path), scope)?,
+        ),
+
+        D::Random { seed, pattern } => (
+            Directionality::Random {
+                seed: seed_hash(&seed),
+            },
+            parse_pattern(pattern.as_ref(), &format!("{}.Pattern", path), scope)?,
+        ),
+
+        D::Pattern {
+            initial_direction,
+            seed,
+            north_pattern,
+            south_pattern,
+            east_pattern,
+            west_pattern,
+        } => {
+            let initial = if initial_direction.is_empty() {
+                Direction::North
+            } else {
+                Direction::from_name(&initial_direction)
+                    .filter(|d| Direction::HORIZONTAL.contains(d))
+                    .ok_or_else(|| {
+                        format!(
+                            "Unknown direction '{}' at {}.InitialDirection",
+                            initial_direction, path
+                        )
+                    })?
+            };
+            let directional = [
+                (Direction::North, "NorthPattern", north_pattern),
+                (Direction::East, "EastPattern", east_pattern),
+                (Direction::South, "SouthPattern", south_pattern),
+                (Direction::West, "WestPattern", west_pattern),
+            ];
+            // Each pattern is compiled twice: once to pick the rotation and
+            // once for the scanner, which accepts an origin any of them match
+            let mut rotation = Vec::new();
+            let mut any = Vec::new();
+            for (direction, key, pattern) in directional {
+                let Some(pattern) = pattern.filter(|p| p.is_object()) else {
+                    continue;
+                };
+                let field = format!("{}.{}", path, key);
+                rotation.push((direction, parse_pattern(Some(&pattern), &field, scope)?));
+                any.push(parse_pattern(Some(&pattern), &field, scope)?);
+            }
+            (
+                Directionality::Pattern {
+                    initial,
+                    seed: seed_hash(&seed),
+                    patterns: rotation,
+                },
+                Pattern::Or(any),
+            )
+        }
+
+        D::Imported { name } => {
+            let Some(export) = scope.directionalities.get(&name) else {
+                return Err(format!(
+                    "Unknown directionality export '{}' at {}",
+                    name, path
+                ));
+            };
+            if imports.contains(&name) {
+                return Err(format!(
+                    "Cyclic directionality import '{}' at {}",
+                    name, path
+                ));
+            }
+            imports.push(name.clone());
+            let parsed =
+                parse_directionality(export, &format!("{}<{}>", path, name), scope, imports);
+            imports.pop();
+            parsed?
+        }
+    })
+}
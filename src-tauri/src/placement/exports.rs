@@ -0,0 +1,26 @@
+use std::collections::HashMap;
+
+use serde_json::Value;
+
+/// Register every `Exported` node found anywhere in `json` whose exported
+/// object lives under `field` (e.g. `Pattern` or `Scanner`).
+pub fn collect_exports(json: &Value, field: &str, exports: &mut HashMap<String, Value>) {
+    match json {
+        Value::Object(obj) => {
+            if obj.get("Type").and_then(|v| v.as_str()) == Some("Exported") {
+                if let (Some(name), Some(item)) = (
+                    obj.get("ExportAs").and_then(|v| v.as_str()),
+                    obj.get(field).filter(|v| v.is_object()),
+                ) {
+                    exports.insert(name.to_string(), item.clone());
+                }
+            }
+            obj.values()
+                .for_each(|v| collect_exports(v, field, exports));
+        }
+        Value::Array(items) => items
+            .iter()
+            .for_each(|v| collect_exports(v, field, exports)),
+        _ => {}
+    }
+}
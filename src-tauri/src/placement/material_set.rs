@@ -0,0 +1,40 @@
+use serde_json::Value;
+
+use crate::material::evaluator::block_name;
+
+/// A set of block names, as used by BlockSet patterns and block masks.
+///
+/// Accepts a single material, an array of materials, or an object with
+/// `Materials` and `Inclusive` (false turns the set into "everything but").
+#[derive(Debug, Clone, Default)]
+pub struct MaterialSet {
+    pub blocks: Vec<String>,
+    pub inclusive: bool,
+}
+
+impl MaterialSet {
+    pub fn from_json(json: &Value) -> Self {
+        match json {
+            Value::Array(items) => MaterialSet {
+                blocks: items.iter().filter_map(block_name).collect(),
+                inclusive: true,
+            },
+            Value::Object(obj) if obj.contains_key("Materials") => MaterialSet {
+                blocks: match obj.get("Materials") {
+                    Some(Value::Array(items)) => items.iter().filter_map(block_name).collect(),
+                    Some(item) => block_name(item).into_iter().collect(),
+                    None => Vec::new(),
+                },
+                inclusive: obj.get("Inclusive").and_then(|v| v.as_bool()) != Some(false),
+            },
+            other => MaterialSet {
+                blocks: block_name(other).into_iter().collect(),
+                inclusive: true,
+            },
+        }
+    }
+
+    pub fn contains(&self, block: &str) -> bool {
+        self.blocks.iter().any(|b| b == block) == self.inclusive
+    }
+}
@@ -0,0 +1,15 @@
+pub mod assignments;
+pub mod block_masks;
+pub mod direction;
+pub mod directionality;
+pub mod exports;
+pub mod material_set;
+pub mod patterns;
+pub mod positions;
+pub mod prefabs;
+pub mod props;
+pub mod scanners;
+pub mod voxels;
+
+#[cfg(test)]
+mod tests;
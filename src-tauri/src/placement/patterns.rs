@@ -0,0 +1,533 @@
+use std::collections::HashMap;
+
+use serde_json::Value;
+
+use crate::material::evaluator::parse_density_with_positions;
+use crate::noise::evaluator::DensityEvaluator;
+use crate::schema::patterns::PatternType;
+use crate::schema::Vector3i;
+
+use super::direction::Direction;
+use super::exports::collect_exports;
+use super::material_set::MaterialSet;
+use super::positions::PositionScope;
+use super::voxels::VoxelChunk;
+
+/// Pattern type names the schema defines; other names never match.
+const KNOWN_PATTERN_TYPES: &[&str] = &[
+    "BlockType",
+    "BlockSet",
+    "Offset",
+    "Floor",
+    "Ceiling",
+    "Wall",
+    "Surface",
+    "Gap",
+    "Cuboid",
+    "And",
+    "Or",
+    "Not",
+    "FieldFunction",
+    "Imported",
+    "Exported",
+    "Constant",
+];
+
+/// Exported patterns an `Imported` pattern may refer to, by `ExportAs` name.
+#[derive(Debug, Clone, Default)]
+pub struct PatternScope {
+    pub exports: HashMap<String, Value>,
+}
+
+impl PatternScope {
+    /// Register every `Exported` pattern found anywhere in `json`.
+    pub fn collect_exports(&mut self, json: &Value) {
+        collect_exports(json, "Pattern", &mut self.exports);
+    }
+}
+
+/// A compiled pattern, tested at block positions of a voxel chunk.
+///
+/// Positions outside the chunk never match a block test, so patterns that
+/// look past the chunk edge fail there rather than guessing.
+pub enum Pattern {
+    Constant(bool),
+    /// Default for unset children: solid (true) or air (false)
+    Solid(bool),
+    /// BlockType and BlockSet
+    Blocks(MaterialSet),
+    Offset {
+        pattern: Box<Pattern>,
+        offset: (i32, i32, i32),
+    },
+    /// Origin at the position and a neighbour pattern one step away
+    Adjacent {
+        origin: Box<Pattern>,
+        neighbour: Box<Pattern>,
+        directions: Vec<Direction>,
+        require_all: bool,
+    },
+    Surface {
+        surface: Box<Pattern>,
+        medium: Box<Pattern>,
+        surface_radius: f64,
+        medium_radius: f64,
+        surface_gap: i32,
+        medium_gap: i32,
+        facings: Vec<Direction>,
+        require_all: bool,
+    },
+    Gap {
+        gap: Box<Pattern>,
+        anchor: Box<Pattern>,
+        gap_size: i32,
+        anchor_size: i32,
+        roughness: i32,
+        depth_down: i32,
+        depth_up: i32,
+        /// Horizontal axes to try, in degrees from +X towards +Z
+        angles: Vec<f64>,
+    },
+    Cuboid {
+        min: (i32, i32, i32),
+        max: (i32, i32, i32),
+        pattern: Box<Pattern>,
+    },
+    And(Vec<Pattern>),
+    Or(Vec<Pattern>),
+    Not(Box<Pattern>),
+    FieldFunction {
+        field: DensityEvaluator,
+        /// Half-open [min, max) ranges; empty means positive density
+        delimiters: Vec<(f64, f64)>,
+    },
+}
+
+impl Pattern {
+    /// Parse a V2 pattern JSON. Exports declared inside the graph are visible
+    /// to its own `Imported` nodes alongside those in `scope`.
+    pub fn from_json(json: &Value, scope: &PatternScope) -> Result<Self, String> {
+        Self::from_json_with_positions(json, scope, &PositionScope::default())
+    }
+
+    /// Like `from_json`, resolving position providers inside FieldFunction
+    /// densities against `positions`.
+    pub fn from_json_with_positions(
+        json: &Value,
+        scope: &PatternScope,
+        positions: &PositionScope,
+    ) -> Result<Self, String> {
+        let mut scope = scope.clone();
+        scope.collect_exports(json);
+        let mut ctx = ParseContext {
+            scope: &scope,
+            positions,
+            imports: Vec::new(),
+        };
+        parse_pattern(json, "$", &mut ctx)
+    }
+
+    pub fn matches(&self, voxels: &VoxelChunk, x: i32, y: i32, z: i32) -> bool {
+        match self {
+            Pattern::Constant(value) => *value,
+            Pattern::Solid(solid) => voxels
+                .block(x, y, z)
+                .is_some_and(|_| voxels.is_solid(x, y, z) == *solid),
+            Pattern::Blocks(set) => voxels.block(x, y, z).is_some_and(|b| set.contains(b)),
+            Pattern::Offset { pattern, offset } => {
+                pattern.matches(voxels, x + offset.0, y + offset.1, z + offset.2)
+            }
+            Pattern::Adjacent {
+                origin,
+                neighbour,
+                directions,
+                require_all,
+            } => {
+                if !origin.matches(voxels, x, y, z) {
+                    return false;
+                }
+                let mut hits = directions.iter().map(|d| {
+                    let (dx, dy, dz) = d.offset();
+                    neighbour.matches(voxels, x + dx, y + dy, z + dz)
+                });
+                if *require_all {
+                    hits.all(|hit| hit)
+                } else {
+                    hits.any(|hit| hit)
+                }
+            }
+            Pattern::Surface {
+                surface,
+                medium,
+                surface_radius,
+                medium_radius,
+                surface_gap,
+                medium_gap,
+                facings,
+                require_all,
+            } => {
+                let mut hits = facings.iter().map(|&facing| {
+                    let (dx, dy, dz) = facing.offset();
+                    let below = -(1 + surface_gap);
+                    let surface_ok = disc(facing, *surface_radius).iter().all(|&(ox, oy, oz)| {
+                        surface.matches(
+                            voxels,
+                            x + dx * below + ox,
+                            y + dy * below + oy,
+                            z + dz * below + oz,
+                        )
+                    });
+                    surface_ok
+                        && disc(facing, *medium_radius).iter().all(|&(ox, oy, oz)| {
+                            medium.matches(
+                                voxels,
+                                x + dx * medium_gap + ox,
+                                y + dy * medium_gap + oy,
+                                z + dz * medium_gap + oz,
+                            )
+                        })
+                });
+                if *require_all {
+                    hits.all(|hit| hit)
+                } else {
+                    hits.any(|hit| hit)
+                }
+            }
+            Pattern::Gap {
+                gap,
+                anchor,
+                gap_size,
+                anchor_size,
+                roughness,
+                depth_down,
+                depth_up,
+                angles,
+            } => angles.iter().any(|angle| {
+                let (sin, cos) = angle.to_radians().sin_cos();
+                let cell = |t: i32, y: i32| {
+                    (
+                        x + (t as f64 * cos).round() as i32,
+                        y,
+                        z + (t as f64 * sin).round() as i32,
+                    )
+                };
+                let test = |p: &Pattern, t: i32, y: i32| {
+                    let (cx, cy, cz) = cell(t, y);
+                    p.matches(voxels, cx, cy, cz)
+                };
+                // The gap is centred on the origin; each side's anchor may
+                // start up to `roughness` further out if the cells between
+                // are still gap.
+                let lo = -(gap_size - 1) / 2;
+                let hi = lo + gap_size - 1;
+                let side = |step: i32, edge: i32, y: i32| {
+                    (0..=*roughness).any(|extra| {
+                        let start = edge + step * (extra + 1);
+                        (1..=extra).all(|k| test(gap, edge + step * k, y))
+                            && (0..*anchor_size).all(|k| test(anchor, start + step * k, y))
+                    })
+                };
+                (y - depth_down..=y + depth_up).all(|ly| {
+                    (lo..=hi).all(|t| test(gap, t, ly)) && side(-1, lo, ly) && side(1, hi, ly)
+                })
+            }),
+            Pattern::Cuboid { min, max, pattern } => (min.0..=max.0).all(|dx| {
+                (min.1..=max.1).all(|dy| {
+                    (min.2..=max.2).all(|dz| pattern.matches(voxels, x + dx, y + dy, z + dz))
+                })
+            }),
+            Pattern::And(patterns) => patterns.iter().all(|p| p.matches(voxels, x, y, z)),
+            Pattern::Or(patterns) => patterns.iter().any(|p| p.matches(voxels, x, y, z)),
+            Pattern::Not(pattern) => !pattern.matches(voxels, x, y, z),
+            Pattern::FieldFunction { field, delimiters } => {
+                let value = field.evaluate(x as f64, y as f64, z as f64);
+                if delimiters.is_empty() {
+                    value > 0.0
+                } else {
+                    delimiters
+                        .iter()
+                        .any(|&(min, max)| value >= min && value < max)
+                }
+            }
+        }
+    }
+}
+
+/// Match a pattern at every block of a chunk, in the chunk's block layout.
+pub fn pattern_mask(pattern: &Pattern, voxels: &VoxelChunk) -> Vec<bool> {
+    (0..voxels.len())
+        .map(|i| {
+            let (x, y, z) = voxels.position(i);
+            pattern.matches(voxels, x, y, z)
+        })
+        .collect()
+}
+
+/// Offsets within `radius` of the origin in the plane perpendicular to `facing`.
+fn disc(facing: Direction, radius: f64) -> Vec<(i32, i32, i32)> {
+    let r = radius.max(0.0);
+    let n = r.floor() as i32;
+    let mut offsets = Vec::new();
+    for a in -n..=n {
+        for b in -n..=n {
+            if ((a * a + b * b) as f64) > r * r {
+                continue;
+            }
+            offsets.push(match facing {
+                Direction::Up | Direction::Down => (a, 0, b),
+                Direction::North | Direction::South => (a, b, 0),
+                Direction::East | Direction::West => (0, a, b),
+            });
+        }
+    }
+    offsets
+}
+
+struct ParseContext<'s> {
+    scope: &'s PatternScope,
+    /// Position providers FieldFunction densities may refer to
+    positions: &'s PositionScope,
+    /// Names of the imports currently being expanded, to detect cycles
+    imports: Vec<String>,
+}
+
+/// Parse an optional child pattern, using `default` when it is absent.
+fn parse_child(
+    json: Option<&Value>,
+    default: Pattern,
+    path: &str,
+    ctx: &mut ParseContext,
+) -> Result<Box<Pattern>, String> {
+    match json {
+        Some(v) if v.is_object() => parse_pattern(v, path, ctx).map(Box::new),
+        _ => Ok(Box::new(default)),
+    }
+}
+
+fn parse_list(items: &[Value], path: &str, ctx: &mut ParseContext) -> Result<Vec<Pattern>, String> {
+    items
+        .iter()
+        .enumerate()
+        .map(|(i, item)| parse_pattern(item, &format!("{}[{}]", path, i), ctx))
+        .collect()
+}
+
+fn vector(json: Option<&Value>, default: (i32, i32, i32)) -> (i32, i32, i32) {
+    json.cloned()
+        .and_then(|v| serde_json::from_value::<Vector3i>(v).ok())
+        .map_or(default, |v| (v.x, v.y, v.z))
+}
+
+/// Recursively parse a JSON pattern. Skipped nodes always match; unknown
+/// types never do.
+fn parse_pattern(json: &Value, path: &str, ctx: &mut ParseContext) -> Result<Pattern, String> {
+    let obj = json
+        .as_object()
+        .ok_or_else(|| format!("Pattern at {} must be a JSON object", path))?;
+    let Some(type_name) = obj.get("Type").and_then(|v| v.as_str()) else {
+        return Err(format!("Missing 'Type' field at {}", path));
+    };
+    if obj.get("Skip").and_then(|v| v.as_bool()) == Some(true) {
+        return Ok(Pattern::Constant(true));
+    }
+    if !KNOWN_PATTERN_TYPES.contains(&type_name) {
+        return Ok(Pattern::Constant(false));
+    }
+    let pattern = serde_json::from_value::<PatternType>(json.clone())
+        .map_err(|e| format!("Invalid pattern at {}: {}", path, e))?;
+
+    use PatternType as P;
+    let solid = || Pattern::Solid(true);
+    let air = || Pattern::Solid(false);
+    Ok(match pattern {
+        P::Constant { value } => Pattern::Constant(value.unwrap_or(true)),
+
+        P::BlockType { material }
+        | P::BlockSet {
+            block_set: material,
+        } => Pattern::Blocks(
+            material
+                .as_ref()
+                .map(MaterialSet::from_json)
+                .unwrap_or_default(),
+        ),
+
+        P::Offset { pattern, offset } => Pattern::Offset {
+            pattern: parse_child(
+                pattern.as_ref(),
+                Pattern::Constant(true),
+                &format!("{}.Pattern", path),
+                ctx,
+            )?,
+            offset: vector(offset.as_ref(), (0, 0, 0)),
+        },
+
+        P::Floor { floor, origin } => Pattern::Adjacent {
+            origin: parse_child(origin.as_ref(), air(), &format!("{}.Origin", path), ctx)?,
+            neighbour: parse_child(floor.as_ref(), solid(), &format!("{}.Floor", path), ctx)?,
+            directions: vec![Direction::Down],
+            require_all: true,
+        },
+
+        P::Ceiling { ceiling, origin } => Pattern::Adjacent {
+            origin: parse_child(origin.as_ref(), air(), &format!("{}.Origin", path), ctx)?,
+            neighbour: parse_child(ceiling.as_ref(), solid(), &format!("{}.Ceiling", path), ctx)?,
+            directions: vec![Direction::Up],
+            require_all: true,
+        },
+
+        P::Wall {
+            wall,
+            origin,
+            directions,
+            require_all_directions,
+        } => {
+            let mut directions =
+                Direction::parse_list(&directions, &format!("{}.Directions", path))?;
+            if directions.is_empty() {
+                directions = Direction::HORIZONTAL.to_vec();
+            }
+            Pattern::Adjacent {
+                origin: parse_child(origin.as_ref(), air(), &format!("{}.Origin", path), ctx)?,
+                neighbour: parse_child(wall.as_ref(), solid(), &format!("{}.Wall", path), ctx)?,
+                directions,
+                require_all: require_all_directions.unwrap_or(false),
+            }
+        }
+
+        P::Surface {
+            surface,
+            medium,
+            surface_radius,
+            medium_radius,
+            surface_gap,
+            medium_gap,
+            facings,
+            require_all_facings,
+        } => {
+            let mut facings = Direction::parse_list(&facings, &format!("{}.Facings", path))?;
+            if facings.is_empty() {
+                facings = vec![Direction::Up];
+            }
+            Pattern::Surface {
+                surface: parse_child(surface.as_ref(), solid(), &format!("{}.Surface", path), ctx)?,
+                medium: parse_child(medium.as_ref(), air(), &format!("{}.Medium", path), ctx)?,
+                surface_radius: surface_radius.unwrap_or(0.0),
+                medium_radius: medium_radius.unwrap_or(0.0),
+                surface_gap: surface_gap.unwrap_or(0).max(0),
+                medium_gap: medium_gap.unwrap_or(0).max(0),
+                facings,
+                require_all: require_all_facings.unwrap_or(false),
+            }
+        }
+
+        P::Gap {
+            gap_size,
+            anchor_size,
+            anchor_roughness,
+            depth_down,
+            depth_up,
+            angles,
+            gap_pattern,
+            anchor_pattern,
+        } => Pattern::Gap {
+            gap: parse_child(
+                gap_pattern.as_ref(),
+                air(),
+                &format!("{}.GapPattern", path),
+                ctx,
+            )?,
+            anchor: parse_child(
+                anchor_pattern.as_ref(),
+                solid(),
+                &format!("{}.AnchorPattern", path),
+                ctx,
+            )?,
+            gap_size: gap_size.unwrap_or(1.0).round().max(1.0) as i32,
+            anchor_size: anchor_size.unwrap_or(1.0).round().max(1.0) as i32,
+            roughness: anchor_roughness.unwrap_or(0.0).round().max(0.0) as i32,
+            depth_down: depth_down.unwrap_or(0).max(0),
+            depth_up: depth_up.unwrap_or(0).max(0),
+            angles: if angles.is_empty() {
+                vec![0.0, 90.0]
+            } else {
+                angles
+            },
+        },
+
+        P::Cuboid {
+            min,
+            max,
+            sub_pattern,
+        } => {
+            let a = vector(min.as_ref(), (0, 0, 0));
+            let b = vector(max.as_ref(), (0, 0, 0));
+            Pattern::Cuboid {
+                min: (a.0.min(b.0), a.1.min(b.1), a.2.min(b.2)),
+                max: (a.0.max(b.0), a.1.max(b.1), a.2.max(b.2)),
+                pattern: parse_child(
+                    sub_pattern.as_ref(),
+                    Pattern::Constant(true),
+                    &format!("{}.SubPattern", path),
+                    ctx,
+                )?,
+            }
+        }
+
+        P::And { patterns } => {
+            Pattern::And(parse_list(&patterns, &format!("{}.Patterns", path), ctx)?)
+        }
+        P::Or { patterns } => {
+            Pattern::Or(parse_list(&patterns, &format!("{}.Patterns", path), ctx)?)
+        }
+        P::Not { pattern } => Pattern::Not(parse_child(
+            pattern.as_ref(),
+            Pattern::Constant(true),
+            &format!("{}.Pattern", path),
+            ctx,
+        )?),
+
+        P::FieldFunction {
+            field_function,
+            delimiters,
+        } => {
+            let field = parse_density_with_positions(field_function.as_ref(), ctx.positions)
+                .map_err(|e| format!("{}.FieldFunction: {}", path, e))?;
+            let delimiters = delimiters
+                .iter()
+                .map(|d| {
+                    let bound =
+                        |a: &str, b: &str| d.get(a).or_else(|| d.get(b)).and_then(|v| v.as_f64());
+                    (
+                        bound("Min", "From").unwrap_or(f64::NEG_INFINITY),
+                        bound("Max", "To").unwrap_or(f64::INFINITY),
+                    )
+                })
+                .collect();
+            Pattern::FieldFunction { field, delimiters }
+        }
+
+        P::Imported { name } => {
+            let name = name.unwrap_or_default();
+            if ctx.imports.contains(&name) {
+                return Err(format!("Cyclic pattern import '{}' at {}", name, path));
+            }
+            let export = ctx
+                .scope
+                .exports
+                .get(&name)
+                .ok_or_else(|| format!("Unknown pattern export '{}' at {}", name, path))?;
+            ctx.imports.push(name.clone());
+            let pattern = parse_pattern(export, &format!("{}<{}>", path, name), ctx);
+            ctx.imports.pop();
+            pattern?
+        }
+
+        P::Exported { pattern, .. } => *parse_child(
+            pattern.as_ref(),
+            Pattern::Constant(true),
+            &format!("{}.Pattern", path),
+            ctx,
+        )?,
+    })
+}
@@ -0,0 +1,488 @@
+use std::collections::HashMap;
+
+use serde_json::Value;
+
+use crate::noise::evaluator::DensityEvaluator;
+use crate::noise::hash::{position_random, seed_hash};
+use crate::schema::positions::{PointGenerator, PositionProviderType};
+use crate::schema::Vector3d;
+
+use super::exports::collect_exports;
+
+/// Axis-aligned region positions are generated in, min inclusive and max
+/// exclusive on every axis.
+#[derive(Debug, Clone, Copy)]
+pub struct Region {
+    pub min: [f64; 3],
+    pub max: [f64; 3],
+}
+
+impl Region {
+    pub fn contains(&self, p: [f64; 3]) -> bool {
+        (0..3).all(|i| p[i] >= self.min[i] && p[i] < self.max[i])
+    }
+}
+
+/// Named values position providers may refer to outside their own JSON.
+#[derive(Debug, Clone, Default)]
+pub struct PositionScope {
+    /// Exported position providers by `ExportAs` name
+    pub exports: HashMap<String, Value>,
+    /// BaseHeight values for `BedName`, by name
+    pub base_heights: HashMap<String, f64>,
+    /// World structure Framework `Positions` entries, by name
+    pub frameworks: HashMap<String, Value>,
+}
+
+impl PositionScope {
+    /// Register every `Exported` position provider found anywhere in `json`.
+    pub fn collect_exports(&mut self, json: &Value) {
+        collect_exports(json, "Positions", &mut self.exports);
+    }
+}
+
+/// A compiled position provider.
+pub enum PositionProvider {
+    List(Vec<[f64; 3]>),
+    /// Jittered grid points; `y` is fixed for 2D meshes
+    Mesh {
+        spacing: f64,
+        jitter: f64,
+        seed: i32,
+        y: Option<f64>,
+    },
+    FieldFunction {
+        field: DensityEvaluator,
+        /// Half-open [min, max) ranges; empty means positive density
+        delimiters: Vec<(f64, f64)>,
+        positions: Box<PositionProvider>,
+    },
+    /// Keeps each position with probability equal to the field's value
+    Occurrence {
+        seed: i32,
+        field: DensityEvaluator,
+        positions: Box<PositionProvider>,
+    },
+    Offset {
+        offset: [f64; 3],
+        positions: Box<PositionProvider>,
+    },
+    Union(Vec<PositionProvider>),
+    Bounds {
+        region: Region,
+        positions: Box<PositionProvider>,
+    },
+    /// Keeps positions whose Y read falls in [min, max) and shifts them by
+    /// a BaseHeight
+    BaseHeight {
+        min_y: f64,
+        max_y: f64,
+        base: f64,
+        positions: Box<PositionProvider>,
+    },
+}
+
+impl PositionProvider {
+    /// Parse a V2 position provider JSON. Exports declared inside the graph
+    /// are visible to its own `Imported` nodes alongside those in `scope`.
+    pub fn from_json(json: &Value, scope: &PositionScope) -> Result<Self, String> {
+        let mut scope = scope.clone();
+        scope.collect_exports(json);
+        parse_positions(json, "$", &scope, &mut Vec::new())
+    }
+
+    /// Positions inside `region`, in generation order.
+    pub fn positions(&self, region: &Region) -> Vec<[f64; 3]> {
+        let mut out = self.generate(region);
+        out.retain(|p| region.contains(*p));
+        out
+    }
+
+    fn generate(&self, region: &Region) -> Vec<[f64; 3]> {
+        match self {
+            PositionProvider::List(points) => points.clone(),
+
+            PositionProvider::Mesh {
+                spacing,
+                jitter,
+                seed,
+                y,
+            } => {
+                let cells = |axis: usize| {
+                    (region.min[axis] / spacing).floor() as i64
+                        ..(region.max[axis] / spacing).ceil() as i64
+                };
+                let ys: Vec<Option<i64>> = match y {
+                    Some(_) => vec![None],
+                    None => cells(1).map(Some).collect(),
+                };
+                let mut out = Vec::new();
+                for cz in cells(2) {
+                    for cy in &ys {
+                        for cx in cells(0) {
+                            let (ix, iy, iz) = (cx as i32, cy.unwrap_or(0) as i32, cz as i32);
+                            let offset = |axis_seed: i32| {
+                                0.5 + (position_random(ix, iy, iz, seed ^ axis_seed) - 0.5) * jitter
+                            };
+                            out.push([
+                                (cx as f64 + offset(0x1)) * spacing,
+                                match (y, cy) {
+                                    (Some(y), _) => *y,
+                                    (None, Some(cy)) => (*cy as f64 + offset(0x2)) * spacing,
+                                    (None, None) => 0.0,
+                                },
+                                (cz as f64 + offset(0x3)) * spacing,
+                            ]);
+                        }
+                    }
+                }
+                out
+            }
+
+            PositionProvider::FieldFunction {
+                field,
+                delimiters,
+                positions,
+            } => positions
+                .generate(region)
+                .into_iter()
+                .filter(|&[x, y, z]| {
+                    let value = field.evaluate(x, y, z);
+                    if delimiters.is_empty() {
+                        value > 0.0
+                    } else {
+                        delimiters
+                            .iter()
+                            .any(|&(min, max)| value >= min && value < max)
+                    }
+                })
+                .collect(),
+
+            PositionProvider::Occurrence {
+                seed,
+                field,
+                positions,
+            } => positions
+                .generate(region)
+                .into_iter()
+                .filter(|&[x, y, z]| {
+                    let chance = field.evaluate(x, y, z).clamp(0.0, 1.0);
+                    let roll = position_random(
+                        x.floor() as i32,
+                        y.floor() as i32,
+                        z.floor() as i32,
+                        *seed,
+                    );
+                    roll < chance
+                })
+                .collect(),
+
+            PositionProvider::Offset { offset, positions } => {
+                // Generate over the region the child would need to land inside it
+                let shifted = Region {
+                    min: [0, 1, 2].map(|i| region.min[i] - offset[i]),
+                    max: [0, 1, 2].map(|i| region.max[i] - offset[i]),
+                };
+                positions
+                    .generate(&shifted)
+                    .into_iter()
+                    .map(|p| [0, 1, 2].map(|i| p[i] + offset[i]))
+                    .collect()
+            }
+
+            PositionProvider::Union(children) => {
+                children.iter().flat_map(|c| c.generate(region)).collect()
+            }
+
+            PositionProvider::Bounds {
+                region: bounds,
+                positions,
+            } => positions
+                .generate(region)
+                .into_iter()
+                .filter(|p| bounds.contains(*p))
+                .collect(),
+
+            PositionProvider::BaseHeight {
+                min_y,
+                max_y,
+                base,
+                positions,
+            } => positions
+                .generate(region)
+                .into_iter()
+                .filter(|p| p[1] >= *min_y && p[1] < *max_y)
+                .map(|[x, y, z]| [x, y + base, z])
+                .collect(),
+        }
+    }
+}
+
+fn vector(json: &Value) -> Option<[f64; 3]> {
+    let v: Vector3d = serde_json::from_value(json.clone()).ok()?;
+    Some([v.x, v.y, v.z])
+}
+
+fn parse_child(
+    json: Option<&Value>,
+    path: &str,
+    scope: &PositionScope,
+    imports: &mut Vec<String>,
+) -> Result<Box<PositionProvider>, String> {
+    match json {
+        Some(v) if v.is_object() => parse_positions(v, path, scope, imports).map(Box::new),
+        _ => Ok(Box::new(PositionProvider::List(Vec::new()))),
+    }
+}
+
+fn mesh(generator: Option<PointGenerator>, y: Option<f64>) -> PositionProvider {
+    let generator = generator.unwrap_or_default();
+    PositionProvider::Mesh {
+        spacing: if generator.spacing > 0 {
+            generator.spacing as f64
+        } else {
+            16.0
+        },
+        jitter: generator.jitter.clamp(0.0, 1.0),
+        seed: seed_hash(&generator.seed),
+        y,
+    }
+}
+
+/// Parse a field density whose positions-driven nodes resolve against the
+/// same scope and import stack as the provider it sits in.
+fn parse_field(
+    json: Option<&Value>,
+    path: &str,
+    scope: &PositionScope,
+    imports: &mut Vec<String>,
+) -> Result<DensityEvaluator, String> {
+    let constant = serde_json::json!({ "Type": "Constant", "Value": 0.0 });
+    let json = json.filter(|v| v.is_object()).unwrap_or(&constant);
+    DensityEvaluator::from_json_in_scope(json, scope, imports)
+        .map_err(|e| format!("{}.FieldFunction: {}", path, e))
+}
+
+/// Recursively parse a JSON position provider. Skipped and unknown nodes
+/// produce no positions. `imports` holds the exports and framework entries
+/// being resolved, to catch cycles.
+pub(crate) fn parse_positions(
+    json: &Value,
+    path: &str,
+    scope: &PositionScope,
+    imports: &mut Vec<String>,
+) -> Result<PositionProvider, String> {
+    let obj = json
+        .as_object()
+        .ok_or_else(|| format!("Position provider at {} must be a JSON object", path))?;
+    if obj.get("Type").and_then(|v| v.as_str()).is_none() {
+        return Err(format!("Missing 'Type' field at {}", path));
+    }
+    let empty = PositionProvider::List(Vec::new());
+    if obj.get("Skip").and_then(|v| v.as_bool()) == Some(true) {
+        return Ok(empty);
+    }
+    let Ok(provider) = serde_json::from_value::<PositionProviderType>(json.clone()) else {
+        return Ok(empty);
+    };
+
+    use PositionProviderType as P;
+    Ok(match provider {
+        P::List { positions } => {
+            PositionProvider::List(positions.iter().filter_map(vector).collect())
+        }
+
+        P::Mesh2D {
+            point_generator,
+            points_y,
+        } => mesh(point_generator, Some(points_y as f64)),
+
+        P::Mesh3D { point_generator } => mesh(point_generator, None),
+
+        P::FieldFunction {
+            field_function,
+            positions,
+            delimiters,
+        } => PositionProvider::FieldFunction {
+            field: parse_field(field_function.as_ref(), path, scope, imports)?,
+            delimiters: delimiters
+                .iter()
+                .map(|d| {
+                    let bound =
+                        |a: &str, b: &str| d.get(a).or_else(|| d.get(b)).and_then(|v| v.as_f64());
+                    (
+                        bound("Min", "From").unwrap_or(f64::NEG_INFINITY),
+                        bound("Max", "To").unwrap_or(f64::INFINITY),
+                    )
+                })
+                .collect(),
+            positions: parse_child(
+                positions.as_ref(),
+                &format!("{}.Positions", path),
+                scope,
+                imports,
+            )?,
+        },
+
+        P::Occurrence {
+            seed,
+            field_function,
+            positions,
+        } => PositionProvider::Occurrence {
+            seed: seed_hash(&seed),
+            field: parse_field(field_function.as_ref(), path, scope, imports)?,
+            positions: parse_child(
+                positions.as_ref(),
+                &format!("{}.Positions", path),
+                scope,
+                imports,
+            )?,
+        },
+
+        P::Offset {
+            offset_x,
+            offset_y,
+            offset_z,
+            positions,
+        } => PositionProvider::Offset {
+            offset: [offset_x as f64, offset_y as f64, offset_z as f64],
+            positions: parse_child(
+                positions.as_ref(),
+                &format!("{}.Positions", path),
+                scope,
+                imports,
+            )?,
+        },
+
+        P::Union { positions } => PositionProvider::Union(
+            positions
+                .iter()
+                .enumerate()
+                .map(|(i, p)| {
+                    parse_positions(p, &format!("{}.Positions[{}]", path, i), scope, imports)
+                })
+                .collect::<Result<_, _>>()?,
+        ),
+
+        P::SimpleHorizontal { range_y, positions } => {
+            let bound = |key: &str| {
+                range_y
+                    .as_ref()
+                    .and_then(|r| r.get(key))
+                    .and_then(|v| v.as_f64())
+            };
+            PositionProvider::Bounds {
+                region: Region {
+                    min: [
+                        f64::NEG_INFINITY,
+                        bound("Min").unwrap_or(f64::NEG_INFINITY),
+                        f64::NEG_INFINITY,
+                    ],
+                    max: [
+                        f64::INFINITY,
+                        bound("Max").unwrap_or(f64::INFINITY),
+                        f64::INFINITY,
+                    ],
+                },
+                positions: parse_child(
+                    positions.as_ref(),
+                    &format!("{}.Positions", path),
+                    scope,
+                    imports,
+                )?,
+            }
+        }
+
+        P::Bound { bounds, positions } => {
+            let corner = |key: &str, default: f64| {
+                bounds
+                    .as_ref()
+                    .and_then(|b| b.get(key))
+                    .and_then(vector)
+                    .unwrap_or([default; 3])
+            };
+            PositionProvider::Bounds {
+                region: Region {
+                    min: corner("Min", f64::NEG_INFINITY),
+                    max: corner("Max", f64::INFINITY),
+                },
+                positions: parse_child(
+                    positions.as_ref(),
+                    &format!("{}.Positions", path),
+                    scope,
+                    imports,
+                )?,
+            }
+        }
+
+        P::BaseHeight {
+            min_y_read,
+            max_y_read,
+            bed_name,
+            positions,
+        } => {
+            let base = if bed_name.is_empty() {
+                0.0
+            } else {
+                *scope
+                    .base_heights
+                    .get(&bed_name)
+                    .ok_or_else(|| format!("Unknown BaseHeight '{}' at {}", bed_name, path))?
+            };
+            let (min_y, max_y) = if max_y_read > min_y_read {
+                (min_y_read, max_y_read)
+            } else {
+                (f64::NEG_INFINITY, f64::INFINITY)
+            };
+            PositionProvider::BaseHeight {
+                min_y,
+                max_y,
+                base,
+                positions: parse_child(
+                    positions.as_ref(),
+                    &format!("{}.Positions", path),
+                    scope,
+                    imports,
+                )?,
+            }
+        }
+
+        // Caching and anchoring don't change which positions are produced
+        P::Cache { positions, .. } | P::Anchor { positions, .. } => *parse_child(
+            positions.as_ref(),
+            &format!("{}.Positions", path),
+            scope,
+            imports,
+        )?,
+
+        P::Framework { name } => {
+            let Some(entry) = scope.frameworks.get(&name) else {
+                return Err(format!(
+                    "Unknown framework positions '{}' at {}",
+                    name, path
+                ));
+            };
+            let key = format!("Framework/{}", name);
+            if imports.contains(&key) {
+                return Err(format!("Cyclic framework positions '{}' at {}", name, path));
+            }
+            imports.push(key);
+            let provider = parse_positions(entry, &format!("{}<{}>", path, name), scope, imports);
+            imports.pop();
+            provider?
+        }
+
+        P::Imported { name } => {
+            let Some(export) = scope.exports.get(&name) else {
+                return Err(format!("Unknown position export '{}' at {}", name, path));
+            };
+            if imports.contains(&name) {
+                return Err(format!("Cyclic position import '{}' at {}", name, path));
+            }
+            imports.push(name.clone());
+            let provider = parse_positions(export, &format!("{}<{}>", path, name), scope, imports);
+            imports.pop();
+            provider?
+        }
+    })
+}
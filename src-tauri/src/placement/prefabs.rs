@@ -0,0 +1,257 @@
+use std::collections::{BTreeSet, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::block_masks::BlockMask;
+use super::props::BoundingBox;
+
+/// Directories under a game or asset directory that prefab paths are
+/// relative to, tried in order.
+const PREFAB_ROOTS: [&str; 3] = ["Server/Prefabs", "Prefabs", ""];
+
+const PREFAB_EXTENSION: &str = ".prefab.json";
+
+/// One block of a prefab.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PrefabBlock {
+    pub x: i32,
+    pub y: i32,
+    pub z: i32,
+    pub name: String,
+    /// Block rotation index; its lowest two bits are the yaw in quarter turns
+    #[serde(default)]
+    pub rotation: i32,
+}
+
+/// A prefab's blocks relative to its anchor, with their bounds.
+#[derive(Debug, Clone, Serialize)]
+pub struct Prefab {
+    /// File the prefab was read from, relative to the prefab directory
+    pub path: String,
+    pub blocks: Vec<PrefabBlock>,
+    pub bounds: BoundingBox,
+}
+
+impl Prefab {
+    /// Read a Hytale `.prefab.json`: `blocks` and `fluids` entries of
+    /// `{ x, y, z, name, rotation }`, offset so `anchorX/Y/Z` is the origin.
+    pub fn from_json(json: &Value, path: &str) -> Result<Self, String> {
+        let obj = json
+            .as_object()
+            .ok_or_else(|| format!("Prefab {} must be a JSON object", path))?;
+        let int =
+            |item: &Value, key: &str| item.get(key).and_then(|v| v.as_i64()).unwrap_or(0) as i32;
+        let anchor = ["anchorX", "anchorY", "anchorZ"].map(|key| int(json, key));
+
+        let mut blocks = Vec::new();
+        for key in ["blocks", "fluids"] {
+            let Some(items) = obj.get(key) else {
+                continue;
+            };
+            let items = items
+                .as_array()
+                .ok_or_else(|| format!("'{}' in prefab {} must be an array", key, path))?;
+            for (i, item) in items.iter().enumerate() {
+                let name = item
+                    .get("name")
+                    .and_then(|v| v.as_str())
+                    .ok_or_else(|| format!("Missing block name at {}[{}] in {}", key, i, path))?;
+                blocks.push(PrefabBlock {
+                    x: int(item, "x") - anchor[0],
+                    y: int(item, "y") - anchor[1],
+                    z: int(item, "z") - anchor[2],
+                    name: name.to_string(),
+                    rotation: int(item, "rotation"),
+                });
+            }
+        }
+        Ok(Prefab {
+            path: path.to_string(),
+            bounds: block_bounds(&blocks),
+            blocks,
+        })
+    }
+
+    /// Rotate about the anchor by quarter turns clockwise seen from above,
+    /// so a prefab facing North faces East after one turn.
+    pub fn rotated(&self, quarter_turns: i32) -> Prefab {
+        let turns = quarter_turns.rem_euclid(4);
+        let blocks: Vec<PrefabBlock> = self
+            .blocks
+            .iter()
+            .map(|b| {
+                let (x, z) = (0..turns).fold((b.x, b.z), |(x, z), _| (-z, x));
+                PrefabBlock {
+                    x,
+                    z,
+                    rotation: b.rotation - b.rotation.rem_euclid(4)
+                        + (b.rotation + turns).rem_euclid(4),
+                    ..b.clone()
+                }
+            })
+            .collect();
+        Prefab {
+            path: self.path.clone(),
+            bounds: block_bounds(&blocks),
+            blocks,
+        }
+    }
+
+    /// Drop the blocks a mask's `DontPlace` keeps out.
+    pub fn masked(mut self, mask: &BlockMask) -> Prefab {
+        self.blocks.retain(|b| mask.places(&b.name));
+        self.bounds = block_bounds(&self.blocks);
+        self
+    }
+}
+
+/// Bounds of a block list; a single block at the anchor when empty.
+pub(crate) fn block_bounds(blocks: &[PrefabBlock]) -> BoundingBox {
+    let mut bounds = BoundingBox {
+        min: [i32::MAX; 3],
+        max: [i32::MIN; 3],
+    };
+    for b in blocks {
+        for (axis, v) in [b.x, b.y, b.z].into_iter().enumerate() {
+            bounds.min[axis] = bounds.min[axis].min(v);
+            bounds.max[axis] = bounds.max[axis].max(v);
+        }
+    }
+    if blocks.is_empty() {
+        bounds = BoundingBox {
+            min: [0; 3],
+            max: [0; 3],
+        };
+    }
+    bounds
+}
+
+/// Convert a Directionality `Rotation` in degrees to quarter turns.
+pub fn quarter_turns(degrees: i32) -> i32 {
+    ((degrees as f64 / 90.0).round() as i32).rem_euclid(4)
+}
+
+/// Prefab files under a game or asset directory, read on first use.
+///
+/// Paths are as written in `WeightedPrefabPaths`: a `.prefab.json` file, or a
+/// directory whose prefab files are variants of one prop.
+#[derive(Debug, Default)]
+pub struct PrefabLibrary {
+    root: Option<PathBuf>,
+    cache: HashMap<PathBuf, Result<Prefab, String>>,
+    errors: BTreeSet<String>,
+}
+
+impl PrefabLibrary {
+    pub fn new(root: Option<&Path>) -> Self {
+        PrefabLibrary {
+            root: root.map(Path::to_path_buf),
+            cache: HashMap::new(),
+            errors: BTreeSet::new(),
+        }
+    }
+
+    pub fn has_root(&self) -> bool {
+        self.root.is_some()
+    }
+
+    /// The prefab files a path stands for, sorted so variant indices are stable.
+    pub fn variants(&self, path: &str) -> Result<Vec<PathBuf>, String> {
+        let root = self
+            .root
+            .as_ref()
+            .ok_or_else(|| "No prefab directory configured".to_string())?;
+        let relative = path.trim_start_matches(['/', '\\']);
+        for base in PREFAB_ROOTS {
+            let dir = root.join(base);
+            let target = dir.join(relative);
+            if target.is_file() {
+                return Ok(vec![target]);
+            }
+            let with_extension = dir.join(format!("{}{}", relative, PREFAB_EXTENSION));
+            if with_extension.is_file() {
+                return Ok(vec![with_extension]);
+            }
+            if target.is_dir() {
+                let mut files = Vec::new();
+                collect_prefab_files(&target, &mut files)?;
+                if files.is_empty() {
+                    return Err(format!("No prefab files in '{}'", path));
+                }
+                files.sort();
+                return Ok(files);
+            }
+        }
+        Err(format!(
+            "Prefab '{}' not found under {}",
+            path,
+            root.display()
+        ))
+    }
+
+    /// Load a prefab path, picking a variant with `roll` in [0, 1) when it
+    /// names a directory.
+    pub fn load(&mut self, path: &str, roll: f64) -> Result<&Prefab, String> {
+        let variants = match self.variants(path) {
+            Ok(variants) => variants,
+            Err(e) => {
+                self.errors.insert(e.clone());
+                return Err(e);
+            }
+        };
+        let index = ((roll * variants.len() as f64) as usize).min(variants.len() - 1);
+        self.load_file(&variants[index])
+    }
+
+    /// Load one prefab file, as returned by [`PrefabLibrary::variants`].
+    pub fn load_file(&mut self, file: &Path) -> Result<&Prefab, String> {
+        let name = self.relative_path(file);
+        let prefab = self
+            .cache
+            .entry(file.to_path_buf())
+            .or_insert_with(|| read_prefab(file, &name));
+        if let Err(e) = prefab {
+            self.errors.insert(e.clone());
+        }
+        prefab.as_ref().map_err(Clone::clone)
+    }
+
+    /// Every distinct error met while loading, sorted.
+    pub fn errors(&self) -> Vec<String> {
+        self.errors.iter().cloned().collect()
+    }
+
+    /// A variant's path relative to the game or asset directory.
+    pub fn relative_path(&self, file: &Path) -> String {
+        let root = self.root.as_deref().unwrap_or(Path::new(""));
+        file.strip_prefix(root)
+            .unwrap_or(file)
+            .to_string_lossy()
+            .replace('\\', "/")
+    }
+}
+
+fn collect_prefab_files(dir: &Path, files: &mut Vec<PathBuf>) -> Result<(), String> {
+    let entries =
+        fs::read_dir(dir).map_err(|e| format!("Failed to read {}: {}", dir.display(), e))?;
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            collect_prefab_files(&path, files)?;
+        } else if path.to_string_lossy().ends_with(PREFAB_EXTENSION) {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+fn read_prefab(file: &Path, name: &str) -> Result<Prefab, String> {
+    let content = fs::read_to_string(file)
+        .map_err(|e| format!("Failed to read {}: {}", file.display(), e))?;
+    let json: Value = serde_json::from_str(&content)
+        .map_err(|e| format!("Invalid JSON in {}: {}", file.display(), e))?;
+    Prefab::from_json(&json, name)
+}
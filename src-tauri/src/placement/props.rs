@@ -0,0 +1,792 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::material::evaluator::curve_points;
+use crate::material::providers::sample_curve;
+use crate::noise::hash::{position_random, seed_hash};
+use crate::schema::biome::PropRuntimeAsset;
+use crate::schema::props::PropType;
+use crate::schema::Vector3i;
+use crate::worldgen::chunk::EMPTY_BLOCK;
+
+use super::assignments::{parse_assignment, Assignment};
+use super::block_masks::{collect_block_mask_exports, BlockMask};
+use super::direction::Direction;
+use super::directionality::Directionality;
+use super::exports::collect_exports;
+use super::patterns::{Pattern, PatternScope};
+use super::positions::{PositionProvider, PositionScope, Region};
+use super::prefabs::{block_bounds, PrefabBlock, PrefabLibrary};
+use super::scanners::{Scanner, ScannerScope};
+use super::voxels::VoxelChunk;
+
+/// Prop type names the schema defines; other names place nothing.
+const KNOWN_PROP_TYPES: &[&str] = &[
+    "Box",
+    "Column",
+    "Cluster",
+    "Density",
+    "Prefab",
+    "PondFiller",
+    "Queue",
+    "Union",
+    "Offset",
+    "Weighted",
+    "Imported",
+];
+
+/// Exported placement nodes across an asset pack, plus the BaseHeights
+/// scanners and position providers read.
+#[derive(Debug, Clone, Default)]
+pub struct PlacementScope {
+    pub patterns: PatternScope,
+    pub scanners: ScannerScope,
+    pub positions: PositionScope,
+    /// Exported props by `ExportAs` name
+    pub props: HashMap<String, Value>,
+    /// Exported assignments by `ExportAs` name
+    pub assignments: HashMap<String, Value>,
+    /// Exported directionalities by `ExportAs` name
+    pub directionalities: HashMap<String, Value>,
+    /// Named block masks by `ExportAs` name
+    pub block_masks: HashMap<String, Value>,
+}
+
+impl PlacementScope {
+    /// Register every exported pattern, scanner, position provider, prop,
+    /// assignment, directionality and block mask found anywhere in `json`.
+    pub fn collect_exports(&mut self, json: &Value) {
+        self.patterns.collect_exports(json);
+        self.scanners.collect_exports(json);
+        self.positions.collect_exports(json);
+        collect_exports(json, "Prop", &mut self.props);
+        collect_exports(json, "Assignments", &mut self.assignments);
+        collect_exports(json, "Directionality", &mut self.directionalities);
+        collect_block_mask_exports(json, &mut self.block_masks);
+    }
+
+    pub fn set_base_heights(&mut self, heights: &HashMap<String, f64>) {
+        self.scanners.base_heights = heights.clone();
+        self.positions.base_heights = heights.clone();
+    }
+}
+
+/// Block-space bounds of a placed prop, both corners inclusive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct BoundingBox {
+    pub min: [i32; 3],
+    pub max: [i32; 3],
+}
+
+/// The blocks a leaf prop occupies relative to its origin.
+pub enum Footprint {
+    /// Fixed offsets from the origin, both corners inclusive
+    Box { min: [i32; 3], max: [i32; 3] },
+    /// A single column whose height is picked per origin
+    Column { min_height: i32, max_height: i32 },
+    /// A prefab picked per origin from weighted paths
+    Prefab {
+        paths: Vec<(f64, String)>,
+        directionality: Directionality,
+        mask: BlockMask,
+    },
+    /// The enclosed basin of air around the origin, as a PondFiller fills it
+    Basin,
+}
+
+/// Most air blocks a PondFiller basin may hold before it counts as open.
+const MAX_BASIN_BLOCKS: usize = 4096;
+
+impl Footprint {
+    fn bounds(&self, voxels: &VoxelChunk, [x, y, z]: [i32; 3]) -> BoundingBox {
+        match self {
+            Footprint::Prefab { .. } => BoundingBox {
+                min: [x, y, z],
+                max: [x, y, z],
+            },
+            Footprint::Basin => basin_bounds(voxels, [x, y, z]).unwrap_or(BoundingBox {
+                min: [x, y, z],
+                max: [x, y, z],
+            }),
+            Footprint::Box { min, max } => BoundingBox {
+                min: [x + min[0], y + min[1], z + min[2]],
+                max: [x + max[0], y + max[1], z + max[2]],
+            },
+            Footprint::Column {
+                min_height,
+                max_height,
+            } => {
+                let span = (max_height - min_height + 1) as f64;
+                let height = min_height + (position_random(x, y, z, 0) * span) as i32;
+                BoundingBox {
+                    min: [x, y, z],
+                    max: [x, y + height.max(1) - 1, z],
+                }
+            }
+        }
+    }
+}
+
+/// Bounds of the air connected to `origin` sideways and downwards, never
+/// rising above it. `None` when the origin is solid, or when the air leaks
+/// out of the chunk or passes `MAX_BASIN_BLOCKS`, as an open basin holds no
+/// pond.
+fn basin_bounds(voxels: &VoxelChunk, origin: [i32; 3]) -> Option<BoundingBox> {
+    let air = |[x, y, z]: [i32; 3]| voxels.block(x, y, z).map(|b| b == EMPTY_BLOCK);
+    if air(origin) != Some(true) {
+        return None;
+    }
+    let mut bounds = BoundingBox {
+        min: origin,
+        max: origin,
+    };
+    let mut seen = HashSet::from([origin]);
+    let mut stack = vec![origin];
+    while let Some([x, y, z]) = stack.pop() {
+        for next in [
+            [x + 1, y, z],
+            [x - 1, y, z],
+            [x, y, z + 1],
+            [x, y, z - 1],
+            [x, y - 1, z],
+        ] {
+            match air(next) {
+                // Leaving the chunk means the basin isn't closed
+                None => return None,
+                Some(true) if seen.insert(next) => {
+                    if seen.len() > MAX_BASIN_BLOCKS {
+                        return None;
+                    }
+                    for (i, &v) in next.iter().enumerate() {
+                        bounds.min[i] = bounds.min[i].min(v);
+                        bounds.max[i] = bounds.max[i].max(v);
+                    }
+                    stack.push(next);
+                }
+                _ => {}
+            }
+        }
+    }
+    Some(bounds)
+}
+
+/// A compiled prop.
+pub enum Prop {
+    /// Box, Column, Density, Prefab and PondFiller: placed at every origin
+    /// the scanner accepts
+    Leaf {
+        prop_type: &'static str,
+        scanner: Scanner,
+        pattern: Pattern,
+        footprint: Footprint,
+    },
+    /// Scatters weighted child props around each accepted origin
+    Cluster {
+        range: i32,
+        /// Chance of a child by distance; linear falloff when empty
+        curve: Vec<(f64, f64)>,
+        seed: i32,
+        props: Vec<(f64, Prop)>,
+        scanner: Scanner,
+        pattern: Pattern,
+    },
+    /// The first child that places anything
+    Queue(Vec<Prop>),
+    Union(Vec<Prop>),
+    Offset {
+        offset: [i32; 3],
+        prop: Box<Prop>,
+    },
+    Weighted {
+        seed: i32,
+        entries: Vec<(f64, Prop)>,
+    },
+    None,
+}
+
+/// One prop the simulator placed.
+#[derive(Debug, Clone, Serialize)]
+pub struct PlacedProp {
+    pub prop_type: String,
+    /// Block the scanner accepted
+    pub origin: [i32; 3],
+    /// Position the provider produced, before scanning
+    pub position: [f64; 3],
+    pub runtime: i32,
+    pub bbox: BoundingBox,
+    /// Chosen prefab path, for Prefab props
+    pub prefab: Option<String>,
+    /// Rotation in degrees clockwise, for Prefab props
+    pub rotation: i32,
+    /// Directional pattern the rotation came from, for Prefab props with
+    /// Pattern directionality
+    pub direction: Option<Direction>,
+    /// The prefab's blocks in world positions that its block mask let
+    /// replace the terrain, when its file could be read
+    pub blocks: Vec<PrefabBlock>,
+    /// Prefab blocks each block mask rule kept out
+    pub rejected: BTreeMap<String, usize>,
+    /// Why the chosen prefab's blocks are missing, when its file couldn't be loaded
+    pub prefab_error: Option<String>,
+}
+
+/// State shared by one placement pass.
+struct PlaceContext<'a> {
+    voxels: &'a VoxelChunk,
+    runtime: i32,
+    position: [f64; 3],
+    placed: Vec<PlacedProp>,
+    prefabs: &'a mut PrefabLibrary,
+}
+
+impl Prop {
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Prop::Leaf { prop_type, .. } => prop_type,
+            Prop::Cluster { .. } => "Cluster",
+            Prop::Queue(_) => "Queue",
+            Prop::Union(_) => "Union",
+            Prop::Offset { .. } => "Offset",
+            Prop::Weighted { .. } => "Weighted",
+            Prop::None => "None",
+        }
+    }
+
+    /// Place this prop for a position, returning whether anything was placed.
+    fn place(&self, ctx: &mut PlaceContext, at: [i32; 3]) -> bool {
+        match self {
+            Prop::Leaf {
+                prop_type,
+                scanner,
+                pattern,
+                footprint,
+            } => {
+                let before = ctx.placed.len();
+                for origin in scanner.scan(ctx.voxels, pattern, at).accepted {
+                    let orientation = match footprint {
+                        Footprint::Prefab { directionality, .. } => {
+                            match directionality.orient(ctx.voxels, origin) {
+                                Some(orientation) => Some(orientation),
+                                None => continue,
+                            }
+                        }
+                        _ => None,
+                    };
+                    let mut placed = PlacedProp {
+                        prop_type: prop_type.to_string(),
+                        origin,
+                        position: ctx.position,
+                        runtime: ctx.runtime,
+                        bbox: footprint.bounds(ctx.voxels, origin),
+                        prefab: None,
+                        rotation: 0,
+                        direction: None,
+                        blocks: Vec::new(),
+                        rejected: BTreeMap::new(),
+                        prefab_error: None,
+                    };
+                    if let (Footprint::Prefab { paths, mask, .. }, Some(orientation)) =
+                        (footprint, orientation)
+                    {
+                        placed.direction = orientation.direction;
+                        let turns = orientation.turns;
+                        stamp_prefab(&mut placed, paths, turns, mask, ctx.voxels, ctx.prefabs);
+                    }
+                    ctx.placed.push(placed);
+                }
+                ctx.placed.len() > before
+            }
+
+            Prop::Cluster {
+                range,
+                curve,
+                seed,
+                props,
+                scanner,
+                pattern,
+            } => {
+                let mut any = false;
+                for [x, y, z] in scanner.scan(ctx.voxels, pattern, at).accepted {
+                    for dz in -range..=*range {
+                        for dx in -range..=*range {
+                            let distance = ((dx * dx + dz * dz) as f64).sqrt();
+                            if distance > *range as f64 {
+                                continue;
+                            }
+                            let chance = if curve.is_empty() {
+                                1.0 - distance / (*range as f64 + 1.0)
+                            } else {
+                                sample_curve(curve, distance)
+                            };
+                            let (cx, cz) = (x + dx, z + dz);
+                            if position_random(cx, y, cz, *seed) >= chance {
+                                continue;
+                            }
+                            let roll = position_random(cx, y, cz, seed ^ 0x2545_f491);
+                            if let Some(prop) = pick_weighted(props, roll) {
+                                any |= prop.place(ctx, [cx, y, cz]);
+                            }
+                        }
+                    }
+                }
+                any
+            }
+
+            Prop::Queue(props) => props.iter().any(|p| p.place(ctx, at)),
+
+            Prop::Union(props) => props.iter().fold(false, |any, p| p.place(ctx, at) | any),
+
+            Prop::Offset { offset, prop } => prop.place(
+                ctx,
+                [at[0] + offset[0], at[1] + offset[1], at[2] + offset[2]],
+            ),
+
+            Prop::Weighted { seed, entries } => {
+                match pick_weighted(entries, position_random(at[0], at[1], at[2], *seed)) {
+                    Some(prop) => prop.place(ctx, at),
+                    None => false,
+                }
+            }
+
+            Prop::None => false,
+        }
+    }
+}
+
+/// Fill in a placed Prefab prop from the prefab picked for its origin. Its
+/// bounds cover the blocks the mask kept; when the file can't be read (the
+/// error is kept on the prop) or nothing is kept the prop keeps its
+/// single-block bounds.
+fn stamp_prefab(
+    placed: &mut PlacedProp,
+    paths: &[(f64, String)],
+    rotation: i32,
+    mask: &BlockMask,
+    voxels: &VoxelChunk,
+    prefabs: &mut PrefabLibrary,
+) {
+    let [x, y, z] = placed.origin;
+    let Some(path) = pick_weighted(paths, position_random(x, y, z, 0)) else {
+        return;
+    };
+    placed.prefab = Some(path.clone());
+    placed.rotation = rotation * 90;
+    if !prefabs.has_root() {
+        return;
+    }
+    let prefab = match prefabs.load(path, position_random(x, y, z, 1)) {
+        Ok(prefab) => prefab,
+        Err(e) => {
+            placed.prefab_error = Some(e);
+            return;
+        }
+    };
+    // Every block goes through the mask so each rule, DontPlace included,
+    // is counted
+    for block in prefab.rotated(rotation).blocks {
+        let block = PrefabBlock {
+            x: x + block.x,
+            y: y + block.y,
+            z: z + block.z,
+            ..block
+        };
+        let existing = voxels
+            .block(block.x, block.y, block.z)
+            .unwrap_or(EMPTY_BLOCK);
+        match mask.rejects(&block.name, existing) {
+            Some(rule) => *placed.rejected.entry(rule).or_insert(0) += 1,
+            None => placed.blocks.push(block),
+        }
+    }
+    if !placed.blocks.is_empty() {
+        placed.bbox = block_bounds(&placed.blocks);
+    }
+}
+
+/// Pick from weighted entries with a roll in [0, 1).
+fn pick_weighted<T>(entries: &[(f64, T)], roll: f64) -> Option<&T> {
+    let total: f64 = entries.iter().map(|(w, _)| w).sum();
+    if total <= 0.0 {
+        return None;
+    }
+    let target = roll * total;
+    let mut cumulative = 0.0;
+    for (weight, item) in entries {
+        cumulative += weight;
+        if target < cumulative {
+            return Some(item);
+        }
+    }
+    entries.last().map(|(_, item)| item)
+}
+
+/// One `Props` entry of a biome: positions, what they're assigned, and the
+/// runtime pass it runs in.
+pub struct PropRuntime {
+    pub runtime: i32,
+    positions: PositionProvider,
+    assignment: Assignment,
+}
+
+impl PropRuntime {
+    /// Compile a biome prop entry; skipped entries compile to `None`.
+    pub fn from_asset(
+        asset: &PropRuntimeAsset,
+        index: usize,
+        scope: &PlacementScope,
+    ) -> Result<Option<Self>, String> {
+        if asset.skip {
+            return Ok(None);
+        }
+        let path = format!("$.Props[{}]", index);
+        let positions = match &asset.positions {
+            Some(json) if json.is_object() => PositionProvider::from_json(json, &scope.positions)
+                .map_err(|e| format!("{}.Positions: {}", path, e))?,
+            _ => return Ok(None),
+        };
+        let mut parser = PropParser {
+            scope,
+            imports: Vec::new(),
+        };
+        let assignment = match &asset.assignments {
+            Some(json) if json.is_object() => {
+                parse_assignment(json, &format!("{}.Assignments", path), &mut parser)?
+            }
+            _ => return Ok(None),
+        };
+        Ok(Some(PropRuntime {
+            runtime: asset.runtime,
+            positions,
+            assignment,
+        }))
+    }
+}
+
+/// Run prop entries over a chunk in runtime order and return every prop
+/// placed. Scanners and patterns read the chunk as generated; props placed
+/// earlier don't change what later ones see. Prefab props read their blocks
+/// from `prefabs`.
+pub fn simulate_props(
+    props: &[PropRuntime],
+    voxels: &VoxelChunk,
+    prefabs: &mut PrefabLibrary,
+) -> Vec<PlacedProp> {
+    let region = Region {
+        min: [voxels.origin_x, voxels.y_min, voxels.origin_z].map(|v| v as f64),
+        max: [
+            voxels.origin_x + voxels.size_x,
+            voxels.y_max,
+            voxels.origin_z + voxels.size_z,
+        ]
+        .map(|v| v as f64),
+    };
+    let mut order: Vec<&PropRuntime> = props.iter().collect();
+    order.sort_by_key(|p| p.runtime);
+
+    let mut placed = Vec::new();
+    for entry in order {
+        for position in entry.positions.positions(&region) {
+            let Some(prop) = entry.assignment.pick(position) else {
+                continue;
+            };
+            let mut ctx = PlaceContext {
+                voxels,
+                runtime: entry.runtime,
+                position,
+                placed: Vec::new(),
+                prefabs: &mut *prefabs,
+            };
+            prop.place(&mut ctx, position.map(|v| v.floor() as i32));
+            placed.append(&mut ctx.placed);
+        }
+    }
+    placed
+}
+
+/// Number of placed props by type.
+pub fn count_by_type(placed: &[PlacedProp]) -> BTreeMap<String, usize> {
+    let mut counts = BTreeMap::new();
+    for prop in placed {
+        *counts.entry(prop.prop_type.clone()).or_insert(0) += 1;
+    }
+    counts
+}
+
+pub(super) struct PropParser<'s> {
+    pub scope: &'s PlacementScope,
+    /// Kind-prefixed names of the imports being expanded, to detect cycles
+    pub imports: Vec<String>,
+}
+
+fn vector(json: Option<&Value>) -> Option<[i32; 3]> {
+    let v: Vector3i = serde_json::from_value(json?.clone()).ok()?;
+    Some([v.x, v.y, v.z])
+}
+
+/// Footprint of a box of `Range` size with its origin at the bottom centre.
+fn box_footprint(range: Option<&Value>) -> Footprint {
+    let size = vector(range).unwrap_or([1, 1, 1]).map(|v| v.max(1));
+    let min = [-(size[0] / 2), 0, -(size[2] / 2)];
+    Footprint::Box {
+        min,
+        max: [min[0] + size[0] - 1, size[1] - 1, min[2] + size[2] - 1],
+    }
+}
+
+/// `WeightedPrefabPaths` as `[{ Path, Weight }]` or `{ path: weight }`.
+fn prefab_paths(json: Option<&Value>) -> Vec<(f64, String)> {
+    match json {
+        Some(Value::Array(items)) => items
+            .iter()
+            .filter_map(|item| match item {
+                Value::String(path) => Some((1.0, path.clone())),
+                _ => Some((
+                    item.get("Weight").and_then(|v| v.as_f64()).unwrap_or(1.0),
+                    item.get("Path")?.as_str()?.to_string(),
+                )),
+            })
+            .collect(),
+        Some(Value::Object(map)) => {
+            let mut paths: Vec<(f64, String)> = map
+                .iter()
+                .map(|(path, w)| (w.as_f64().unwrap_or(1.0), path.clone()))
+                .collect();
+            paths.sort_by(|a, b| a.1.cmp(&b.1));
+            paths
+        }
+        Some(Value::String(path)) => vec![(1.0, path.clone())],
+        _ => Vec::new(),
+    }
+}
+
+fn parse_list(items: &[Value], path: &str, parser: &mut PropParser) -> Result<Vec<Prop>, String> {
+    items
+        .iter()
+        .enumerate()
+        .map(|(i, item)| parse_prop(item, &format!("{}[{}]", path, i), parser))
+        .collect()
+}
+
+/// Parse `[{ Weight, Prop }]` entries.
+fn parse_weighted(
+    json: Option<&Value>,
+    path: &str,
+    parser: &mut PropParser,
+) -> Result<Vec<(f64, Prop)>, String> {
+    let items = json.and_then(|v| v.as_array()).cloned().unwrap_or_default();
+    let mut entries = Vec::new();
+    for (i, item) in items.iter().enumerate() {
+        let weight = item.get("Weight").and_then(|v| v.as_f64()).unwrap_or(1.0);
+        if let Some(prop) = item.get("Prop").filter(|p| p.is_object()) {
+            entries.push((
+                weight,
+                parse_prop(prop, &format!("{}[{}].Prop", path, i), parser)?,
+            ));
+        }
+    }
+    Ok(entries)
+}
+
+fn parse_checks(
+    scanner: Option<&Value>,
+    pattern: Option<&Value>,
+    path: &str,
+    parser: &PropParser,
+) -> Result<(Scanner, Pattern), String> {
+    let scanner = Scanner::from_json(scanner, &parser.scope.scanners)
+        .map_err(|e| format!("{}.Scanner: {}", path, e))?;
+    let pattern = match pattern {
+        Some(p) if p.is_object() => {
+            Pattern::from_json_with_positions(p, &parser.scope.patterns, &parser.scope.positions)
+                .map_err(|e| format!("{}.Pattern: {}", path, e))?
+        }
+        _ => Pattern::Constant(true),
+    };
+    Ok((scanner, pattern))
+}
+
+/// Recursively parse a JSON prop. Skipped and unknown props place nothing.
+pub(super) fn parse_prop(
+    json: &Value,
+    path: &str,
+    parser: &mut PropParser,
+) -> Result<Prop, String> {
+    let obj = json
+        .as_object()
+        .ok_or_else(|| format!("Prop at {} must be a JSON object", path))?;
+    let Some(type_name) = obj.get("Type").and_then(|v| v.as_str()) else {
+        return Err(format!("Missing 'Type' field at {}", path));
+    };
+    if obj.get("Skip").and_then(|v| v.as_bool()) == Some(true) {
+        return Ok(Prop::None);
+    }
+    if !KNOWN_PROP_TYPES.contains(&type_name) {
+        return Ok(Prop::None);
+    }
+    let prop = serde_json::from_value::<PropType>(json.clone())
+        .map_err(|e| format!("Invalid prop at {}: {}", path, e))?;
+
+    use PropType as P;
+    Ok(match prop {
+        P::Box {
+            range,
+            scanner,
+            pattern,
+            ..
+        } => {
+            let (scanner, pattern) =
+                parse_checks(scanner.as_ref(), pattern.as_ref(), path, parser)?;
+            Prop::Leaf {
+                prop_type: "Box",
+                scanner,
+                pattern,
+                footprint: box_footprint(range.as_ref()),
+            }
+        }
+
+        P::Density {
+            range,
+            scanner,
+            pattern,
+            ..
+        } => {
+            let (scanner, pattern) =
+                parse_checks(scanner.as_ref(), pattern.as_ref(), path, parser)?;
+            Prop::Leaf {
+                prop_type: "Density",
+                scanner,
+                pattern,
+                footprint: box_footprint(range.as_ref()),
+            }
+        }
+
+        P::Column {
+            range,
+            scanner,
+            pattern,
+            ..
+        } => {
+            let (scanner, pattern) =
+                parse_checks(scanner.as_ref(), pattern.as_ref(), path, parser)?;
+            let bound = |key: &str| {
+                range
+                    .as_ref()
+                    .and_then(|r| r.get(key))
+                    .and_then(|v| v.as_i64())
+                    .map(|v| v as i32)
+            };
+            let min_height = bound("Min").unwrap_or(1).max(1);
+            Prop::Leaf {
+                prop_type: "Column",
+                scanner,
+                pattern,
+                footprint: Footprint::Column {
+                    min_height,
+                    max_height: bound("Max").unwrap_or(min_height).max(min_height),
+                },
+            }
+        }
+
+        P::Prefab {
+            weighted_prefab_paths,
+            directionality,
+            scanner,
+            block_mask,
+            ..
+        } => {
+            let (scanner, _) = parse_checks(scanner.as_ref(), None, path, parser)?;
+            let (directionality, pattern) =
+                Directionality::from_json(directionality.as_ref(), parser.scope)
+                    .map_err(|e| format!("{}.Directionality: {}", path, e))?;
+            Prop::Leaf {
+                prop_type: "Prefab",
+                scanner,
+                pattern,
+                footprint: Footprint::Prefab {
+                    paths: prefab_paths(weighted_prefab_paths.as_ref()),
+                    directionality,
+                    mask: BlockMask::from_json(block_mask.as_ref(), &parser.scope.block_masks)
+                        .map_err(|e| format!("{}.BlockMask: {}", path, e))?,
+                },
+            }
+        }
+
+        P::PondFiller {
+            scanner, pattern, ..
+        } => {
+            let (scanner, pattern) =
+                parse_checks(scanner.as_ref(), pattern.as_ref(), path, parser)?;
+            Prop::Leaf {
+                prop_type: "PondFiller",
+                scanner,
+                pattern,
+                footprint: Footprint::Basin,
+            }
+        }
+
+        P::Cluster {
+            range,
+            distance_curve,
+            seed,
+            weighted_props,
+            pattern,
+            scanner,
+        } => {
+            let (scanner, pattern) =
+                parse_checks(scanner.as_ref(), pattern.as_ref(), path, parser)?;
+            Prop::Cluster {
+                range: range.max(0),
+                curve: distance_curve
+                    .as_ref()
+                    .map(curve_points)
+                    .unwrap_or_default(),
+                seed: seed_hash(&seed),
+                props: parse_weighted(
+                    weighted_props.as_ref(),
+                    &format!("{}.WeightedProps", path),
+                    parser,
+                )?,
+                scanner,
+                pattern,
+            }
+        }
+
+        P::Queue { props } => Prop::Queue(parse_list(&props, &format!("{}.Props", path), parser)?),
+
+        P::Union { props } => Prop::Union(parse_list(&props, &format!("{}.Props", path), parser)?),
+
+        P::Offset {
+            offset_x,
+            offset_y,
+            offset_z,
+            prop,
+        } => Prop::Offset {
+            offset: [offset_x, offset_y, offset_z],
+            prop: Box::new(match prop {
+                Some(p) if p.is_object() => parse_prop(&p, &format!("{}.Prop", path), parser)?,
+                _ => Prop::None,
+            }),
+        },
+
+        P::Weighted { entries, seed } => Prop::Weighted {
+            seed: seed_hash(&seed),
+            entries: parse_weighted(entries.as_ref(), &format!("{}.Entries", path), parser)?,
+        },
+
+        P::Imported { name } => {
+            let Some(export) = parser.scope.props.get(&name) else {
+                return Err(format!("Unknown prop export '{}' at {}", name, path));
+            };
+            let key = format!("Prop/{}", name);
+            if parser.imports.contains(&key) {
+                return Err(format!("Cyclic prop import '{}' at {}", name, path));
+            }
+            parser.imports.push(key);
+            let prop = parse_prop(export, &format!("{}<{}>", path, name), parser);
+            parser.imports.pop();
+            prop?
+        }
+    })
+}
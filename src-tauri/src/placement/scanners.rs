@@ -0,0 +1,303 @@
+use std::collections::HashMap;
+
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::noise::hash::{position_random, seed_hash};
+use crate::schema::scanners::ScannerType;
+
+use super::exports::collect_exports;
+use super::patterns::Pattern;
+use super::voxels::VoxelChunk;
+
+/// Named values a scanner may refer to outside its own JSON.
+#[derive(Debug, Clone, Default)]
+pub struct ScannerScope {
+    /// Exported scanners by `ExportAs` name
+    pub exports: HashMap<String, Value>,
+    /// BaseHeight values for `BaseHeightName`, by name
+    pub base_heights: HashMap<String, f64>,
+}
+
+impl ScannerScope {
+    /// Register every `Exported` scanner found anywhere in `json`.
+    pub fn collect_exports(&mut self, json: &Value) {
+        collect_exports(json, "Scanner", &mut self.exports);
+    }
+}
+
+/// Order in which a column scanner visits its Y range.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ColumnOrder {
+    BottomUp,
+    TopDown,
+    /// Every Y once, shuffled per column
+    PickValid(i32),
+    /// As many random draws as the range is tall, repeats allowed
+    DartThrow(i32),
+}
+
+/// A compiled scanner: which positions around an origin a pattern is tested
+/// at, in what order, and how many matches to keep.
+#[derive(Debug, Clone)]
+pub enum Scanner {
+    Origin,
+    Column {
+        /// Half-open [min_y, max_y), absolute or relative to the origin
+        min_y: i32,
+        max_y: i32,
+        relative: bool,
+        /// Accepted results to stop at; `None` is unlimited
+        cap: Option<usize>,
+        order: ColumnOrder,
+    },
+    Area {
+        cap: Option<usize>,
+        circle: bool,
+        range: i32,
+        child: Box<Scanner>,
+    },
+}
+
+/// Positions a scanner visited and the ones the pattern accepted, in order.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ScanResult {
+    pub scanned: Vec<[i32; 3]>,
+    pub accepted: Vec<[i32; 3]>,
+}
+
+impl ScanResult {
+    /// Test one position; positions outside the chunk are not scanned.
+    fn test(&mut self, voxels: &VoxelChunk, pattern: &Pattern, x: i32, y: i32, z: i32) {
+        if !voxels.contains(x, y, z) {
+            return;
+        }
+        self.scanned.push([x, y, z]);
+        if pattern.matches(voxels, x, y, z) {
+            self.accepted.push([x, y, z]);
+        }
+    }
+}
+
+impl Scanner {
+    /// Parse a V2 scanner JSON. A missing scanner scans only the origin;
+    /// exports declared inside the JSON are visible to its own imports.
+    pub fn from_json(json: Option<&Value>, scope: &ScannerScope) -> Result<Self, String> {
+        let Some(json) = json.filter(|v| v.is_object()) else {
+            return Ok(Scanner::Origin);
+        };
+        let mut scope = scope.clone();
+        scope.collect_exports(json);
+        parse_scanner(json, "$", &scope, &mut Vec::new())
+    }
+
+    /// Scan around `origin`, testing `pattern` at each visited position.
+    pub fn scan(&self, voxels: &VoxelChunk, pattern: &Pattern, origin: [i32; 3]) -> ScanResult {
+        let mut result = ScanResult::default();
+        self.scan_into(voxels, pattern, origin, &mut result);
+        result
+    }
+
+    fn scan_into(
+        &self,
+        voxels: &VoxelChunk,
+        pattern: &Pattern,
+        [x, y, z]: [i32; 3],
+        result: &mut ScanResult,
+    ) {
+        let start = result.accepted.len();
+        let full = |result: &ScanResult, cap: &Option<usize>| {
+            cap.is_some_and(|cap| result.accepted.len() - start >= cap)
+        };
+        match self {
+            Scanner::Origin => result.test(voxels, pattern, x, y, z),
+
+            Scanner::Column {
+                min_y,
+                max_y,
+                relative,
+                cap,
+                order,
+            } => {
+                let base = if *relative { y } else { 0 };
+                // Only the chunk's own rows can match, so never build a longer sequence
+                let lo = base.saturating_add(*min_y).max(voxels.y_min);
+                let hi = base.saturating_add(*max_y).min(voxels.y_max);
+                for ly in column_sequence(*order, lo, hi, x, z) {
+                    if full(result, cap) {
+                        break;
+                    }
+                    result.test(voxels, pattern, x, ly, z);
+                }
+            }
+
+            Scanner::Area {
+                cap,
+                circle,
+                range,
+                child,
+            } => {
+                for (dx, dz) in area_offsets(*range, *circle) {
+                    if full(result, cap) {
+                        break;
+                    }
+                    let mut sub = ScanResult::default();
+                    child.scan_into(voxels, pattern, [x + dx, y, z + dz], &mut sub);
+                    result.scanned.extend(sub.scanned);
+                    let room = cap.map_or(usize::MAX, |c| c - (result.accepted.len() - start));
+                    result.accepted.extend(sub.accepted.into_iter().take(room));
+                }
+            }
+        }
+    }
+}
+
+/// Y values a column scanner visits over [lo, hi) at column (x, z).
+fn column_sequence(order: ColumnOrder, lo: i32, hi: i32, x: i32, z: i32) -> Vec<i32> {
+    let mut ys: Vec<i32> = (lo..hi).collect();
+    match order {
+        ColumnOrder::BottomUp => {}
+        ColumnOrder::TopDown => ys.reverse(),
+        ColumnOrder::PickValid(seed) => {
+            // Fisher–Yates with a per-column stream
+            for i in (1..ys.len()).rev() {
+                let r = position_random(x, i as i32, z, seed);
+                ys.swap(i, (r * (i + 1) as f64) as usize);
+            }
+        }
+        ColumnOrder::DartThrow(seed) => {
+            let height = ys.len();
+            ys = (0..height)
+                .map(|i| lo + (position_random(x, i as i32, z, seed) * height as f64) as i32)
+                .collect();
+        }
+    }
+    ys
+}
+
+/// Horizontal offsets within `range`, nearest first.
+fn area_offsets(range: i32, circle: bool) -> Vec<(i32, i32)> {
+    let r = range.max(0);
+    let mut offsets: Vec<(i32, i32)> = (-r..=r)
+        .flat_map(|dz| (-r..=r).map(move |dx| (dx, dz)))
+        .filter(|(dx, dz)| !circle || dx * dx + dz * dz <= r * r)
+        .collect();
+    offsets.sort_by_key(|&(dx, dz)| (dx * dx + dz * dz, dz, dx));
+    offsets
+}
+
+/// `ResultCap` of zero or less means no cap.
+fn result_cap(cap: i32) -> Option<usize> {
+    (cap > 0).then_some(cap as usize)
+}
+
+fn parse_scanner(
+    json: &Value,
+    path: &str,
+    scope: &ScannerScope,
+    imports: &mut Vec<String>,
+) -> Result<Scanner, String> {
+    let obj = json
+        .as_object()
+        .ok_or_else(|| format!("Scanner at {} must be a JSON object", path))?;
+    if obj.get("Type").and_then(|v| v.as_str()).is_none() {
+        return Err(format!("Missing 'Type' field at {}", path));
+    }
+    let scanner = serde_json::from_value::<ScannerType>(json.clone())
+        .map_err(|e| format!("Invalid scanner at {}: {}", path, e))?;
+
+    let base_height = |name: &str| -> Result<i32, String> {
+        if name.is_empty() {
+            return Ok(0);
+        }
+        scope
+            .base_heights
+            .get(name)
+            .map(|h| h.round() as i32)
+            .ok_or_else(|| format!("Unknown BaseHeight '{}' at {}", name, path))
+    };
+
+    use ScannerType as S;
+    Ok(match scanner {
+        S::Origin => Scanner::Origin,
+
+        S::ColumnLinear {
+            min_y,
+            max_y,
+            result_cap: cap,
+            top_down_order,
+            relative_to_position,
+            base_height_name,
+        } => {
+            let base = base_height(&base_height_name)?;
+            Scanner::Column {
+                min_y: min_y.saturating_add(base),
+                max_y: max_y.saturating_add(base),
+                relative: relative_to_position,
+                cap: result_cap(cap),
+                order: if top_down_order {
+                    ColumnOrder::TopDown
+                } else {
+                    ColumnOrder::BottomUp
+                },
+            }
+        }
+
+        S::ColumnRandom {
+            min_y,
+            max_y,
+            result_cap: cap,
+            seed,
+            strategy,
+            relative_to_position,
+            base_height_name,
+        } => {
+            let base = base_height(&base_height_name)?;
+            let seed = seed_hash(&seed);
+            let order = match strategy.to_ascii_uppercase().as_str() {
+                "" | "PICK_VALID" => ColumnOrder::PickValid(seed),
+                "DART_THROW" => ColumnOrder::DartThrow(seed),
+                other => {
+                    return Err(format!("Unknown scanner strategy '{}' at {}", other, path));
+                }
+            };
+            Scanner::Column {
+                min_y: min_y.saturating_add(base),
+                max_y: max_y.saturating_add(base),
+                relative: relative_to_position,
+                cap: result_cap(cap),
+                order,
+            }
+        }
+
+        S::Area {
+            result_cap: cap,
+            scan_shape,
+            scan_range,
+            child_scanner,
+        } => Scanner::Area {
+            cap: result_cap(cap),
+            circle: scan_shape.eq_ignore_ascii_case("CIRCLE"),
+            range: scan_range,
+            child: Box::new(match child_scanner {
+                Some(child) if child.is_object() => {
+                    parse_scanner(&child, &format!("{}.ChildScanner", path), scope, imports)?
+                }
+                _ => Scanner::Origin,
+            }),
+        },
+
+        S::Imported { name } => {
+            let Some(export) = scope.exports.get(&name) else {
+                return Err(format!("Unknown scanner export '{}' at {}", name, path));
+            };
+            if imports.contains(&name) {
+                return Err(format!("Cyclic scanner import '{}' at {}", name, path));
+            }
+            imports.push(name.clone());
+            let scanner = parse_scanner(export, &format!("{}<{}>", path, name), scope, imports);
+            imports.pop();
+            scanner?
+        }
+    })
+}
@@ -0,0 +1,800 @@
+use serde_json::json;
+
+use super::assignments::{assignment_histogram, Assignment};
+use super::block_masks::BlockMask;
+use super::direction::Direction;
+use super::directionality::Directionality;
+use super::patterns::{pattern_mask, Pattern, PatternScope};
+use super::prefabs::{PrefabBlock, PrefabLibrary};
+use super::props::{count_by_type, simulate_props, BoundingBox, PlacementScope, PropRuntime};
+use super::scanners::{Scanner, ScannerScope};
+use super::voxels::VoxelChunk;
+use crate::bridge::types::ChunkDataResponse;
+use crate::schema::biome::PropRuntimeAsset;
+use crate::worldgen::chunk::{BlockPalette, EMPTY_BLOCK};
+
+/// An 8x8 chunk at the origin, 8 blocks tall, filled by `block(x, y, z)`.
+fn voxels(block: impl Fn(i32, i32, i32) -> &'static str) -> VoxelChunk {
+    let (size, height) = (8, 8);
+    let mut palette = BlockPalette::default();
+    let mut blocks = Vec::new();
+    for z in 0..size {
+        for x in 0..size {
+            for y in 0..height {
+                blocks.push(palette.id(block(x, y, z)));
+            }
+        }
+    }
+    let chunk = ChunkDataResponse {
+        chunk_x: 0,
+        chunk_z: 0,
+        y_min: 0,
+        y_max: height,
+        size_x: size,
+        size_z: size,
+        blocks,
+        heightmap: vec![0; (size * size) as usize],
+    };
+    VoxelChunk::new(&chunk, &palette.to_map()).unwrap()
+}
+
+/// Stone up to y=1, grass at y=2, and a wood pillar at (4, 3..=5, 4).
+fn terrain(x: i32, y: i32, z: i32) -> &'static str {
+    match y {
+        0 | 1 => "Rock_Stone",
+        2 => "Soil_Grass",
+        3..=5 if (x, z) == (4, 4) => "Wood_Log",
+        _ => EMPTY_BLOCK,
+    }
+}
+
+fn parse(json: serde_json::Value) -> Pattern {
+    Pattern::from_json(&json, &PatternScope::default()).unwrap()
+}
+
+#[test]
+fn voxel_chunk_round_trips_bridge_layout() {
+    let chunk = voxels(terrain);
+    assert_eq!(chunk.len(), 8 * 8 * 8);
+    assert_eq!(chunk.block(4, 4, 4), Some("Wood_Log"));
+    assert_eq!(chunk.block(4, 8, 4), None);
+    let index = chunk.index(3, 5, 6).unwrap();
+    assert_eq!(chunk.position(index), (3, 5, 6));
+}
+
+#[test]
+fn floor_pattern_finds_air_above_grass() {
+    let chunk = voxels(terrain);
+    let pattern = parse(json!({
+        "Type": "Floor",
+        "Floor": { "Type": "BlockType", "Material": "Soil_Grass" }
+    }));
+    let mask = pattern_mask(&pattern, &chunk);
+
+    // Every column except the pillar's has air at y=3 over grass
+    assert_eq!(mask.iter().filter(|m| **m).count(), 8 * 8 - 1);
+    assert!(mask[chunk.index(0, 3, 0).unwrap()]);
+    assert!(!mask[chunk.index(4, 3, 4).unwrap()]);
+    assert!(!mask[chunk.index(0, 4, 0).unwrap()]);
+}
+
+#[test]
+fn wall_surface_and_logic_patterns() {
+    let chunk = voxels(terrain);
+
+    let wall = parse(json!({
+        "Type": "Wall",
+        "Wall": { "Type": "BlockSet", "BlockSet": { "Materials": ["Wood_Log"] } },
+        "Directions": ["W"]
+    }));
+    assert!(wall.matches(&chunk, 5, 4, 4));
+    assert!(!wall.matches(&chunk, 3, 4, 4));
+
+    // Up-facing surface: grass below, a 1-block disc of air above
+    let surface = parse(json!({
+        "Type": "Surface",
+        "Surface": { "Type": "BlockType", "Material": { "Solid": "Soil_Grass" } },
+        "MediumRadius": 1.0,
+        "Facings": ["Up"]
+    }));
+    assert!(surface.matches(&chunk, 1, 3, 1));
+    assert!(!surface.matches(&chunk, 4, 3, 5));
+
+    let not_grass = parse(json!({
+        "Type": "And",
+        "Patterns": [
+            { "Type": "Not", "Pattern": { "Type": "BlockType", "Material": "Soil_Grass" } },
+            { "Type": "Cuboid", "Min": { "x": 0, "y": -1, "z": 0 }, "Max": { "x": 0, "y": 0, "z": 0 },
+              "SubPattern": { "Type": "BlockSet", "BlockSet": ["Rock_Stone", "Soil_Grass"] } }
+        ]
+    }));
+    assert!(not_grass.matches(&chunk, 2, 1, 2));
+    assert!(!not_grass.matches(&chunk, 2, 2, 2));
+    assert!(!not_grass.matches(&chunk, 2, 0, 2));
+}
+
+#[test]
+fn gap_pattern_needs_anchors_on_both_sides() {
+    // Two walls along X at x=1 and x=5 with a 3-block gap between
+    let chunk = voxels(|x, _, _| match x {
+        1 | 5 => "Rock_Stone",
+        _ => EMPTY_BLOCK,
+    });
+    let gap = parse(json!({ "Type": "Gap", "GapSize": 3, "Angles": [0] }));
+    assert!(gap.matches(&chunk, 3, 2, 3));
+    assert!(!gap.matches(&chunk, 2, 2, 3));
+
+    let rough = parse(json!({ "Type": "Gap", "GapSize": 1, "AnchorRoughness": 2, "Angles": [0] }));
+    assert!(rough.matches(&chunk, 3, 2, 3));
+    assert!(!parse(json!({ "Type": "Gap", "GapSize": 1, "Angles": [0] })).matches(&chunk, 3, 2, 3));
+}
+
+#[test]
+fn pattern_imports_resolve_and_reject_cycles() {
+    let chunk = voxels(terrain);
+    let mut scope = PatternScope::default();
+    scope.collect_exports(&json!({
+        "Type": "Exported",
+        "ExportAs": "OnGrass",
+        "Pattern": { "Type": "Floor", "Floor": { "Type": "BlockType", "Material": "Soil_Grass" } }
+    }));
+    let imported =
+        Pattern::from_json(&json!({ "Type": "Imported", "Name": "OnGrass" }), &scope).unwrap();
+    assert!(imported.matches(&chunk, 0, 3, 0));
+
+    scope.exports.insert(
+        "Loop".into(),
+        json!({ "Type": "Not", "Pattern": { "Type": "Imported", "Name": "Loop" } }),
+    );
+    let err = Pattern::from_json(&json!({ "Type": "Imported", "Name": "Loop" }), &scope)
+        .err()
+        .unwrap();
+    assert!(err.contains("Cyclic"));
+
+    let err = Pattern::from_json(
+        &json!({ "Type": "Wall", "Directions": ["Sideways"] }),
+        &scope,
+    )
+    .err()
+    .unwrap();
+    assert!(err.contains("Unknown direction"));
+}
+
+#[test]
+fn malformed_patterns_error_and_unknown_types_never_match() {
+    let chunk = voxels(terrain);
+    let err = Pattern::from_json(
+        &json!({ "Type": "Not", "Pattern": { "Type": "Constant", "Value": "yes" } }),
+        &PatternScope::default(),
+    )
+    .err()
+    .unwrap();
+    assert!(err.contains("Invalid pattern at $.Pattern"), "{}", err);
+
+    let unknown = parse(json!({ "Type": "FuturePattern", "Radius": 3 }));
+    assert!(!unknown.matches(&chunk, 0, 3, 0));
+}
+
+fn scanner(json: serde_json::Value) -> Scanner {
+    Scanner::from_json(Some(&json), &ScannerScope::default()).unwrap()
+}
+
+fn on_grass() -> Pattern {
+    parse(json!({ "Type": "Floor", "Floor": { "Type": "BlockType", "Material": "Soil_Grass" } }))
+}
+
+#[test]
+fn column_linear_scans_in_order_up_to_cap() {
+    let chunk = voxels(terrain);
+    let top_down = scanner(json!({
+        "Type": "ColumnLinear", "MinY": 0, "MaxY": 8, "ResultCap": 1, "TopDownOrder": true
+    }));
+    let result = top_down.scan(&chunk, &on_grass(), [1, 0, 1]);
+    let ys: Vec<i32> = result.scanned.iter().map(|p| p[1]).collect();
+    assert_eq!(ys, vec![7, 6, 5, 4, 3]);
+    assert_eq!(result.accepted, vec![[1, 3, 1]]);
+
+    // Relative to the origin, and with a BaseHeight shifting the range
+    let mut scope = ScannerScope::default();
+    scope.base_heights.insert("Ground".into(), 2.0);
+    let relative = Scanner::from_json(
+        Some(&json!({
+            "Type": "ColumnLinear", "MinY": -1, "MaxY": 1,
+            "RelativeToPosition": true, "BaseHeightName": "Ground"
+        })),
+        &scope,
+    )
+    .unwrap();
+    let result = relative.scan(&chunk, &Pattern::Constant(true), [1, 3, 1]);
+    assert_eq!(result.scanned, vec![[1, 4, 1], [1, 5, 1]]);
+    assert_eq!(result.accepted.len(), 2);
+
+    // Positions outside the chunk are never scanned
+    let result = scanner(json!({ "Type": "ColumnLinear", "MinY": -4, "MaxY": 12 })).scan(
+        &chunk,
+        &on_grass(),
+        [1, 0, 1],
+    );
+    assert_eq!(result.scanned.len(), 8);
+
+    // Extreme bounds are clamped to the chunk instead of overflowing
+    let result = scanner(json!({
+        "Type": "ColumnLinear", "MinY": i32::MIN, "MaxY": i32::MAX, "RelativeToPosition": true
+    }))
+    .scan(&chunk, &on_grass(), [1, 3, 1]);
+    assert_eq!(result.scanned.len(), 8);
+}
+
+#[test]
+fn column_random_strategies_are_seeded() {
+    let chunk = voxels(terrain);
+    let pick = json!({ "Type": "ColumnRandom", "MinY": 0, "MaxY": 8, "Seed": "rocks" });
+    let result = scanner(pick.clone()).scan(&chunk, &Pattern::Constant(true), [2, 0, 2]);
+    let mut ys: Vec<i32> = result.scanned.iter().map(|p| p[1]).collect();
+    assert_ne!(ys, (0..8).collect::<Vec<_>>());
+    ys.sort();
+    assert_eq!(ys, (0..8).collect::<Vec<_>>());
+    let again = scanner(pick).scan(&chunk, &Pattern::Constant(true), [2, 0, 2]);
+    assert_eq!(again.scanned, result.scanned);
+
+    let darts = scanner(json!({
+        "Type": "ColumnRandom", "MinY": 0, "MaxY": 8, "Strategy": "DART_THROW", "ResultCap": 3
+    }))
+    .scan(&chunk, &Pattern::Constant(true), [2, 0, 2]);
+    assert_eq!(darts.accepted.len(), 3);
+
+    let err = Scanner::from_json(
+        Some(&json!({ "Type": "ColumnRandom", "Strategy": "Sideways" })),
+        &ScannerScope::default(),
+    )
+    .err()
+    .unwrap();
+    assert!(err.contains("Unknown scanner strategy"));
+}
+
+#[test]
+fn area_scanner_visits_nearest_columns_first() {
+    let chunk = voxels(terrain);
+    let area = scanner(json!({
+        "Type": "Area",
+        "ScanShape": "CIRCLE",
+        "ScanRange": 1,
+        "ResultCap": 3,
+        "ChildScanner": { "Type": "ColumnLinear", "MinY": 0, "MaxY": 8, "ResultCap": 1 }
+    }));
+    // Centred on the pillar, whose own column has no air over grass
+    let result = area.scan(&chunk, &on_grass(), [4, 0, 4]);
+    assert_eq!(result.accepted, vec![[4, 3, 3], [3, 3, 4], [5, 3, 4]]);
+    assert_eq!(
+        result.scanned[..8]
+            .iter()
+            .filter(|p| p[0] == 4 && p[2] == 4)
+            .count(),
+        8
+    );
+}
+
+#[test]
+fn prop_simulation_places_by_runtime_with_bounds() {
+    let chunk = voxels(terrain);
+    let mut scope = PlacementScope::default();
+    scope.collect_exports(&json!({
+        "Type": "Exported",
+        "ExportAs": "OnGrass",
+        "Pattern": { "Type": "Floor", "Floor": { "Type": "BlockType", "Material": "Soil_Grass" } }
+    }));
+    let surface_scanner = json!({
+        "Type": "ColumnLinear", "MinY": 0, "MaxY": 8, "ResultCap": 1, "TopDownOrder": true
+    });
+
+    let trees = PropRuntimeAsset {
+        runtime: 0,
+        positions: Some(json!({
+            "Type": "Mesh2D",
+            "PointGenerator": { "Spacing": 4, "Jitter": 0.5, "Seed": "trees" },
+            "PointsY": 6
+        })),
+        assignments: Some(json!({
+            "Type": "Constant",
+            "Prop": {
+                "Type": "Union",
+                "Props": [
+                    { "Type": "Column", "Range": { "Min": 2, "Max": 2 },
+                      "Pattern": { "Type": "Imported", "Name": "OnGrass" }, "Scanner": surface_scanner },
+                    { "Type": "Offset", "OffsetY": 1, "Prop": {
+                        "Type": "Prefab", "WeightedPrefabPaths": [{ "Path": "trees/oak", "Weight": 1 }]
+                    } }
+                ]
+            }
+        })),
+        skip: false,
+    };
+    let rocks = PropRuntimeAsset {
+        runtime: -1,
+        positions: Some(json!({
+            "Type": "List",
+            "Positions": [{ "x": 1.5, "y": 0, "z": 1.5 }, { "x": 4.5, "y": 0, "z": 4.5 }]
+        })),
+        assignments: Some(json!({
+            "Type": "Constant",
+            "Prop": { "Type": "Box", "Range": { "x": 3, "y": 2, "z": 3 },
+                      "Pattern": { "Type": "Imported", "Name": "OnGrass" }, "Scanner": surface_scanner }
+        })),
+        skip: false,
+    };
+    let skipped = PropRuntimeAsset {
+        skip: true,
+        ..rocks.clone()
+    };
+
+    let props: Vec<PropRuntime> = [trees, rocks, skipped]
+        .iter()
+        .enumerate()
+        .filter_map(|(i, asset)| PropRuntime::from_asset(asset, i, &scope).unwrap())
+        .collect();
+    assert_eq!(props.len(), 2);
+    let placed = simulate_props(&props, &chunk, &mut PrefabLibrary::default());
+
+    // The pillar column has no air over grass, so only one rock lands
+    let rock = &placed[0];
+    assert_eq!((rock.prop_type.as_str(), rock.runtime), ("Box", -1));
+    assert_eq!(rock.origin, [1, 3, 1]);
+    assert_eq!(
+        rock.bbox,
+        BoundingBox {
+            min: [0, 3, 0],
+            max: [2, 4, 2]
+        }
+    );
+
+    let counts = count_by_type(&placed);
+    assert_eq!(counts["Box"], 1);
+    assert_eq!(counts["Column"], 4);
+    assert_eq!(counts["Prefab"], 4);
+    for prop in &placed[1..] {
+        match prop.prop_type.as_str() {
+            "Column" => assert_eq!(prop.bbox.max[1] - prop.bbox.min[1], 1),
+            _ => {
+                assert_eq!(prop.origin[1], 7);
+                assert_eq!(prop.prefab.as_deref(), Some("trees/oak"));
+            }
+        }
+    }
+}
+
+#[test]
+fn malformed_props_error_and_unknown_types_place_nothing() {
+    let asset = |prop: serde_json::Value| PropRuntimeAsset {
+        runtime: 0,
+        positions: Some(json!({ "Type": "List", "Positions": [{ "x": 2, "y": 3, "z": 2 }] })),
+        assignments: Some(json!({ "Type": "Constant", "Prop": prop })),
+        skip: false,
+    };
+    let scope = PlacementScope::default();
+
+    let err = PropRuntime::from_asset(&asset(json!({ "Type": "Weighted", "Seed": 5 })), 0, &scope)
+        .err()
+        .unwrap();
+    assert!(err.contains("Invalid prop at $.Props[0]"), "{}", err);
+
+    let unknown = PropRuntime::from_asset(&asset(json!({ "Type": "Fountain" })), 0, &scope)
+        .unwrap()
+        .unwrap();
+    let placed = simulate_props(&[unknown], &voxels(terrain), &mut PrefabLibrary::default());
+    assert!(placed.is_empty());
+}
+
+#[test]
+fn pond_filler_bounds_cover_the_enclosed_basin() {
+    // A 2x3 pit in the grass, one block deep
+    let chunk = voxels(|x, y, z| match y {
+        2 if (2..=3).contains(&x) && (2..=4).contains(&z) => EMPTY_BLOCK,
+        _ => terrain(x, y, z),
+    });
+    let floor = |material: &str| {
+        json!({
+            "Type": "PondFiller",
+            "Scanner": { "Type": "ColumnLinear", "MinY": 0, "MaxY": 8, "ResultCap": 1, "TopDownOrder": true },
+            "Pattern": { "Type": "Floor", "Floor": { "Type": "BlockType", "Material": material } },
+            "Material": { "Type": "Constant", "Material": "Water_Source" }
+        })
+    };
+    let ponds = PropRuntimeAsset {
+        runtime: 0,
+        positions: Some(json!({
+            "Type": "List",
+            "Positions": [{ "x": 2.5, "y": 0, "z": 3.5 }, { "x": 6.5, "y": 0, "z": 6.5 }]
+        })),
+        assignments: Some(json!({
+            "Type": "Constant",
+            "Prop": { "Type": "Union", "Props": [floor("Rock_Stone"), floor("Soil_Grass")] }
+        })),
+        skip: false,
+    };
+    let props: Vec<PropRuntime> = PropRuntime::from_asset(&ponds, 0, &PlacementScope::default())
+        .unwrap()
+        .into_iter()
+        .collect();
+    let placed = simulate_props(&props, &chunk, &mut PrefabLibrary::default());
+
+    let bounds: Vec<([i32; 3], BoundingBox)> = placed.iter().map(|p| (p.origin, p.bbox)).collect();
+    assert_eq!(
+        bounds,
+        vec![
+            (
+                [2, 2, 3],
+                BoundingBox {
+                    min: [2, 2, 2],
+                    max: [3, 2, 4]
+                }
+            ),
+            // Open ground holds no pond, so only the origin is covered
+            (
+                [6, 3, 6],
+                BoundingBox {
+                    min: [6, 3, 6],
+                    max: [6, 3, 6]
+                }
+            ),
+        ]
+    );
+}
+
+#[test]
+fn assignments_pick_by_field_height_and_weight() {
+    let scope = PlacementScope::default();
+    let boxed = |x: i32| json!({ "Type": "Constant", "Prop": { "Type": "Box", "Range": { "x": x, "y": 1, "z": 1 } } });
+
+    let field = Assignment::from_json(
+        &json!({
+            "Type": "FieldFunction",
+            "FieldFunction": { "Type": "Constant", "Value": 0.25 },
+            "Delimiters": [
+                { "Min": -1, "Max": 0, "Assignments": boxed(1) },
+                { "Min": 0, "Max": 0.5, "Assignments": boxed(2) }
+            ]
+        }),
+        &scope,
+    )
+    .unwrap();
+    let trace = field.trace([0.0, 0.0, 0.0]);
+    assert_eq!(
+        trace.prop.map(|p| p.label.as_str()),
+        Some("Box at $.Delimiters[1].Assignments.Prop")
+    );
+    assert!(trace.steps[0].contains("delimiter 1"), "{:?}", trace.steps);
+
+    let sandwich = Assignment::from_json(
+        &json!({
+            "Type": "Sandwich",
+            "Delimiters": [{ "MinY": 0, "MaxY": 64, "Assignments": boxed(1) }]
+        }),
+        &scope,
+    )
+    .unwrap();
+    assert!(sandwich.pick([0.0, 10.0, 0.0]).is_some());
+    let above = sandwich.trace([0.0, 80.0, 0.0]);
+    assert!(above.prop.is_none());
+    assert!(above.steps[0].contains("outside every delimiter"));
+
+    let weighted = Assignment::from_json(
+        &json!({
+            "Type": "Weighted",
+            "Seed": "grass",
+            "SkipChance": 0.5,
+            "WeightedAssignments": [
+                { "Weight": 3, "Assignments": boxed(1) },
+                { "Weight": 1, "Assignments": boxed(2) }
+            ]
+        }),
+        &scope,
+    )
+    .unwrap();
+    let grid: Vec<[f64; 3]> = (0..40)
+        .flat_map(|z| (0..40).map(move |x| [x as f64, 0.0, z as f64]))
+        .collect();
+    let histogram = assignment_histogram(&weighted, &grid);
+    assert_eq!(histogram.len(), 3);
+    assert_eq!(histogram.iter().map(|e| e.count).sum::<usize>(), grid.len());
+    let total: f64 = histogram.iter().map(|e| e.percent).sum();
+    assert!((total - 100.0).abs() < 1e-9);
+    let skipped = histogram.iter().find(|e| e.prop.is_none()).unwrap();
+    assert!(
+        (40.0..60.0).contains(&skipped.percent),
+        "{}",
+        skipped.percent
+    );
+    let heavy = &histogram
+        .iter()
+        .find(|e| e.prop.as_deref().is_some_and(|p| p.contains("[0]")))
+        .unwrap();
+    assert!(heavy.percent > 25.0, "{}", heavy.percent);
+}
+
+#[test]
+fn prefabs_load_rotate_and_mask_from_asset_dir() {
+    let dir = std::env::temp_dir().join(format!("terranova-prefabs-{}", std::process::id()));
+    let variants = dir.join("Server/Prefabs/Trees/Stump");
+    std::fs::create_dir_all(&variants).unwrap();
+    // A 3-tall trunk with a leaf one block north of the top, anchored at its base
+    let stump = json!({
+        "anchorX": 5, "anchorY": 10, "anchorZ": 5,
+        "blocks": [
+            { "x": 5, "y": 10, "z": 5, "name": "Wood_Oak_Trunk" },
+            { "x": 5, "y": 11, "z": 5, "name": "Wood_Oak_Trunk" },
+            { "x": 5, "y": 12, "z": 5, "name": "Wood_Oak_Trunk", "rotation": 1 },
+            { "x": 5, "y": 12, "z": 4, "name": "Plant_Leaves_Oak" }
+        ]
+    });
+    std::fs::write(variants.join("Stump_001.prefab.json"), stump.to_string()).unwrap();
+    std::fs::write(variants.join("Stump_002.prefab.json"), stump.to_string()).unwrap();
+
+    let mut library = PrefabLibrary::new(Some(&dir));
+    assert_eq!(library.variants("Trees/Stump/").unwrap().len(), 2);
+    let file = &library.variants("Trees/Stump/Stump_001").unwrap()[0];
+    let prefab = library.load_file(file).unwrap().clone();
+    assert_eq!(
+        prefab.path,
+        "Server/Prefabs/Trees/Stump/Stump_001.prefab.json"
+    );
+    assert_eq!(
+        prefab.bounds,
+        BoundingBox {
+            min: [0, 0, -1],
+            max: [0, 2, 0]
+        }
+    );
+
+    // One clockwise turn moves the north leaf east and turns the top trunk
+    let turned = prefab.rotated(1);
+    assert!(turned.blocks.contains(&PrefabBlock {
+        x: 1,
+        y: 2,
+        z: 0,
+        name: "Plant_Leaves_Oak".into(),
+        rotation: 1,
+    }));
+    assert_eq!(turned.blocks[2].rotation, 2);
+    assert_eq!(turned.bounds.max, [1, 2, 0]);
+
+    let mask = BlockMask::from_json(
+        Some(&json!({ "DontPlace": ["Plant_Leaves_Oak"] })),
+        &Default::default(),
+    )
+    .unwrap();
+    let bare = prefab.masked(&mask);
+    assert_eq!(bare.blocks.len(), 3);
+    assert_eq!(bare.bounds.min, [0, 0, 0]);
+
+    assert!(library.load("Trees/Missing", 0.0).is_err());
+    assert_eq!(library.errors().len(), 1);
+
+    // The simulator stamps the prefab's blocks at each origin
+    let chunk = voxels(terrain);
+    let asset = PropRuntimeAsset {
+        runtime: 0,
+        positions: Some(json!({ "Type": "List", "Positions": [{ "x": 2, "y": 4, "z": 2 }] })),
+        assignments: Some(json!({
+            "Type": "Constant",
+            "Prop": {
+                "Type": "Prefab",
+                "WeightedPrefabPaths": [{ "Path": "Trees/Stump/", "Weight": 1 }],
+                "Directionality": { "Type": "Static", "Rotation": 180 }
+            }
+        })),
+        skip: false,
+    };
+    let props: Vec<PropRuntime> = PropRuntime::from_asset(&asset, 0, &PlacementScope::default())
+        .unwrap()
+        .into_iter()
+        .collect();
+    let placed = simulate_props(&props, &chunk, &mut library);
+
+    // Blocks a DontPlace rule drops are reported and left out of the bounds
+    let mut bare_asset = asset.clone();
+    bare_asset.assignments = Some(json!({
+        "Type": "Constant",
+        "Prop": {
+            "Type": "Prefab",
+            "WeightedPrefabPaths": [{ "Path": "Trees/Stump/", "Weight": 1 }],
+            "Directionality": { "Type": "Static", "Rotation": 180 },
+            "BlockMask": { "DontPlace": ["Plant_Leaves_Oak"] }
+        }
+    }));
+    let bare_props: Vec<PropRuntime> =
+        PropRuntime::from_asset(&bare_asset, 0, &PlacementScope::default())
+            .unwrap()
+            .into_iter()
+            .collect();
+    let bare_placed = simulate_props(&bare_props, &chunk, &mut library);
+
+    // A prefab that fails to load keeps its load error on the placed prop
+    let mut missing_asset = asset.clone();
+    missing_asset.assignments = Some(json!({
+        "Type": "Constant",
+        "Prop": {
+            "Type": "Prefab",
+            "WeightedPrefabPaths": [{ "Path": "Trees/Missing", "Weight": 1 }]
+        }
+    }));
+    let missing_props: Vec<PropRuntime> =
+        PropRuntime::from_asset(&missing_asset, 0, &PlacementScope::default())
+            .unwrap()
+            .into_iter()
+            .collect();
+    let missing_placed = simulate_props(&missing_props, &chunk, &mut library);
+    std::fs::remove_dir_all(&dir).ok();
+
+    assert!(missing_placed[0].blocks.is_empty());
+    assert!(missing_placed[0].prefab_error.is_some());
+
+    assert_eq!(bare_placed[0].blocks.len(), 3);
+    assert_eq!(bare_placed[0].rejected["DontPlace"], 1);
+    assert_eq!(
+        bare_placed[0].bbox,
+        BoundingBox {
+            min: [2, 4, 2],
+            max: [2, 6, 2]
+        }
+    );
+
+    assert_eq!(placed.len(), 1);
+    let stump = &placed[0];
+    assert_eq!(stump.rotation, 180);
+    assert_eq!(stump.blocks.len(), 4);
+    assert_eq!(
+        stump.bbox,
+        BoundingBox {
+            min: [2, 4, 2],
+            max: [2, 6, 3]
+        }
+    );
+}
+
+#[test]
+fn directionality_turns_prefabs_towards_matching_patterns() {
+    let chunk = voxels(terrain);
+    let log_at = |x: i32, z: i32| {
+        json!({
+            "Type": "Offset",
+            "Offset": { "x": x, "y": 0, "z": z },
+            "Pattern": { "Type": "BlockType", "Material": "Wood_Log" }
+        })
+    };
+    let by_pattern = json!({
+        "Type": "Pattern",
+        "InitialDirection": "South",
+        "Seed": "facing",
+        "NorthPattern": log_at(0, -1),
+        "EastPattern": log_at(1, 0)
+    });
+    let mut scope = PlacementScope::default();
+    scope.collect_exports(&json!({
+        "Type": "Exported", "ExportAs": "FaceLog", "Directionality": by_pattern
+    }));
+    let imported = json!({ "Type": "Imported", "Name": "FaceLog" });
+    let (directionality, pattern) = Directionality::from_json(Some(&imported), &scope).unwrap();
+
+    // The pillar is east of (3, 3, 4): a quarter turn anticlockwise from South
+    let east = directionality.orient(&chunk, [3, 3, 4]).unwrap();
+    assert_eq!(east.direction, Some(Direction::East));
+    assert_eq!(east.turns, 3);
+    let north = directionality.orient(&chunk, [4, 3, 5]).unwrap();
+    assert_eq!((north.direction, north.turns), (Some(Direction::North), 2));
+    assert!(directionality.orient(&chunk, [0, 3, 0]).is_none());
+    assert!(pattern.matches(&chunk, 3, 3, 4));
+    assert!(!pattern.matches(&chunk, 0, 3, 0));
+
+    let (fixed, _) =
+        Directionality::from_json(Some(&json!({ "Type": "Static", "Rotation": 90 })), &scope)
+            .unwrap();
+    assert_eq!(fixed.orient(&chunk, [0, 3, 0]).unwrap().turns, 1);
+
+    let (random, _) =
+        Directionality::from_json(Some(&json!({ "Type": "Random", "Seed": "spin" })), &scope)
+            .unwrap();
+    let mut seen = [false; 4];
+    for z in 0..8 {
+        for x in 0..8 {
+            seen[random.orient(&chunk, [x, 3, z]).unwrap().turns as usize] = true;
+        }
+    }
+    assert_eq!(seen, [true; 4]);
+
+    assert!(Directionality::from_json(
+        Some(&json!({ "Type": "Pattern", "InitialDirection": "Up" })),
+        &scope
+    )
+    .is_err());
+
+    // Placed prefabs report the rotation and the pattern it came from
+    let asset = PropRuntimeAsset {
+        runtime: 0,
+        positions: Some(json!({
+            "Type": "List",
+            "Positions": [{ "x": 3, "y": 3, "z": 4 }, { "x": 0, "y": 3, "z": 0 }]
+        })),
+        assignments: Some(json!({
+            "Type": "Constant",
+            "Prop": {
+                "Type": "Prefab",
+                "WeightedPrefabPaths": [{ "Path": "Props/Sign", "Weight": 1 }],
+                "Directionality": imported
+            }
+        })),
+        skip: false,
+    };
+    let props: Vec<PropRuntime> = PropRuntime::from_asset(&asset, 0, &scope)
+        .unwrap()
+        .into_iter()
+        .collect();
+    let placed = simulate_props(&props, &chunk, &mut PrefabLibrary::default());
+    assert_eq!(placed.len(), 1);
+    assert_eq!(placed[0].origin, [3, 3, 4]);
+    assert_eq!(placed[0].rotation, 270);
+    assert_eq!(placed[0].direction, Some(Direction::East));
+}
+
+#[test]
+fn block_mask_merges_prop_blocks_and_reports_rules() {
+    let chunk = voxels(terrain);
+    let mut scope = PlacementScope::default();
+    scope.collect_exports(&json!({
+        "Type": "Prefab",
+        "BlockMask": { "ExportAs": "KeepStone", "DontReplace": ["Rock_Stone"] }
+    }));
+    let mask = BlockMask::from_json(
+        Some(&json!({
+            "Import": "KeepStone",
+            "DontPlace": ["Plant_Leaves_Oak"],
+            "Advanced": [{ "Source": ["Wood_Plank"], "CanReplace": [EMPTY_BLOCK] }]
+        })),
+        &scope.block_masks,
+    )
+    .unwrap();
+
+    let block = |x, y, z, name: &str| PrefabBlock {
+        x,
+        y,
+        z,
+        name: name.into(),
+        rotation: 0,
+    };
+    let result = mask.apply(
+        &chunk,
+        &[
+            block(0, 1, 0, "Soil_Dirt"),
+            block(0, 3, 0, "Plant_Leaves_Oak"),
+            block(4, 4, 4, "Wood_Plank"),
+            block(1, 3, 1, "Wood_Plank"),
+            block(1, 2, 1, "Soil_Dirt"),
+        ],
+    );
+    assert_eq!(result.placed, 2);
+    assert_eq!(result.counts["DontReplace"], 1);
+    assert_eq!(result.counts["DontPlace"], 1);
+    assert_eq!(result.counts["Advanced[0]"], 1);
+    let names: Vec<&str> = result.merged.iter().map(|b| b.name.as_str()).collect();
+    assert_eq!(
+        names,
+        [
+            "Rock_Stone",
+            EMPTY_BLOCK,
+            "Wood_Log",
+            "Wood_Plank",
+            "Soil_Dirt"
+        ]
+    );
+    assert_eq!(result.rejected[2].existing, "Wood_Log");
+
+    let looped = [
+        ("A", json!({ "ExportAs": "A", "Import": "B" })),
+        ("B", json!({ "Import": "A" })),
+    ]
+    .into_iter()
+    .map(|(name, mask)| (name.to_string(), mask))
+    .collect();
+    let err = BlockMask::from_json(Some(&json!({ "Import": "A" })), &looped).unwrap_err();
+    assert!(err.contains("Cyclic block mask import"), "{}", err);
+    assert!(BlockMask::from_json(Some(&json!({ "Import": "C" })), &looped).is_err());
+}
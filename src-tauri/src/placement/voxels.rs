@@ -0,0 +1,98 @@
+use std::collections::HashMap;
+
+use crate::bridge::types::ChunkDataResponse;
+use crate::worldgen::chunk::EMPTY_BLOCK;
+
+/// Block names over one chunk, addressed in world coordinates.
+///
+/// Built from a chunk in the bridge's layout, whether fetched from a server
+/// or generated offline, together with the palette its ids refer to.
+pub struct VoxelChunk {
+    pub origin_x: i32,
+    pub origin_z: i32,
+    pub y_min: i32,
+    pub y_max: i32,
+    pub size_x: i32,
+    pub size_z: i32,
+    blocks: Vec<i32>,
+    names: HashMap<i32, String>,
+}
+
+impl VoxelChunk {
+    pub fn new(
+        chunk: &ChunkDataResponse,
+        palette: &HashMap<String, String>,
+    ) -> Result<Self, String> {
+        let height = (chunk.y_max - chunk.y_min).max(0) as usize;
+        let expected = chunk.size_x.max(0) as usize * chunk.size_z.max(0) as usize * height;
+        if chunk.blocks.len() != expected {
+            return Err(format!(
+                "Chunk has {} blocks, expected {} for {}x{}x{}",
+                chunk.blocks.len(),
+                expected,
+                chunk.size_x,
+                height,
+                chunk.size_z
+            ));
+        }
+        let names = palette
+            .iter()
+            .filter_map(|(id, name)| Some((id.parse().ok()?, name.clone())))
+            .collect();
+        Ok(VoxelChunk {
+            origin_x: chunk.chunk_x * chunk.size_x,
+            origin_z: chunk.chunk_z * chunk.size_z,
+            y_min: chunk.y_min,
+            y_max: chunk.y_max,
+            size_x: chunk.size_x,
+            size_z: chunk.size_z,
+            blocks: chunk.blocks.clone(),
+            names,
+        })
+    }
+
+    pub fn height(&self) -> i32 {
+        self.y_max - self.y_min
+    }
+
+    /// Number of blocks, the length of any per-block buffer over this chunk.
+    pub fn len(&self) -> usize {
+        self.blocks.len()
+    }
+
+    pub fn contains(&self, x: i32, y: i32, z: i32) -> bool {
+        (self.origin_x..self.origin_x + self.size_x).contains(&x)
+            && (self.y_min..self.y_max).contains(&y)
+            && (self.origin_z..self.origin_z + self.size_z).contains(&z)
+    }
+
+    /// Index into the block buffer: `(lz * size_x + lx) * height + (y - y_min)`.
+    pub fn index(&self, x: i32, y: i32, z: i32) -> Option<usize> {
+        if !self.contains(x, y, z) {
+            return None;
+        }
+        let (lx, lz) = (x - self.origin_x, z - self.origin_z);
+        Some(((lz * self.size_x + lx) * self.height() + (y - self.y_min)) as usize)
+    }
+
+    /// World position of a block buffer index.
+    pub fn position(&self, index: usize) -> (i32, i32, i32) {
+        let height = self.height().max(1) as usize;
+        let column = index / height;
+        let y = self.y_min + (index % height) as i32;
+        let lx = (column % self.size_x.max(1) as usize) as i32;
+        let lz = (column / self.size_x.max(1) as usize) as i32;
+        (self.origin_x + lx, y, self.origin_z + lz)
+    }
+
+    /// Block name at a world position, or `None` outside the chunk.
+    /// Ids missing from the palette read as air.
+    pub fn block(&self, x: i32, y: i32, z: i32) -> Option<&str> {
+        let id = self.blocks[self.index(x, y, z)?];
+        Some(self.names.get(&id).map_or(EMPTY_BLOCK, String::as_str))
+    }
+
+    pub fn is_solid(&self, x: i32, y: i32, z: i32) -> bool {
+        self.block(x, y, z).is_some_and(|b| b != EMPTY_BLOCK)
+    }
+}
@@ -13,6 +13,9 @@ pub enum MaterialProviderType {
     Constant {
         #[serde(rename = "BlockType", default)]
         block_type: Option<String>,
+        /// Material as a block name or `{ "Solid": ... }` object.
+        #[serde(rename = "Material", default)]
+        material: Option<Value>,
     },
 
     /// Separates into Solid and Empty terrain and provides a provider for each.
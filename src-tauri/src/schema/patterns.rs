@@ -13,13 +13,13 @@ pub enum PatternType {
     /// Checks against a specific block material.
     BlockType {
         #[serde(rename = "Material", default)]
-        material: Option<String>,
+        material: Option<Value>,
     },
 
-    /// Checks if the block's material belongs to a named BlockSet.
+    /// Checks if the block's material belongs to a BlockSet.
     BlockSet {
         #[serde(rename = "BlockSet", default)]
-        block_set: Option<String>,
+        block_set: Option<Value>,
     },
 
     /// Offsets the child pattern by an integer vector.
@@ -0,0 +1,158 @@
+use serde::Serialize;
+
+use crate::noise::evaluator::DensityEvaluator;
+use crate::placement::positions::PositionScope;
+use crate::schema::world_structure::WorldStructureAsset;
+
+use super::blend::{BiomeBlender, BlendGrid};
+
+/// Picks a biome per column from a NoiseRange world structure.
+pub struct BiomeSelector {
+    density: DensityEvaluator,
+    /// (biome index, min, max) in declaration order
+    ranges: Vec<(usize, f64, f64)>,
+    /// Biome names; indices refer into this
+    pub names: Vec<String>,
+    /// Index of DefaultBiome
+    pub default_index: usize,
+}
+
+impl BiomeSelector {
+    /// `positions` resolves the position providers the structure's Density
+    /// imports, such as its Framework entries.
+    pub fn new(structure: &WorldStructureAsset, positions: &PositionScope) -> Result<Self, String> {
+        let density = structure
+            .density
+            .as_ref()
+            .ok_or("World structure has no Density")?;
+        let density = DensityEvaluator::from_json_with_positions(density, positions)
+            .map_err(|e| format!("World structure density: {}", e))?;
+
+        let mut names: Vec<String> = Vec::new();
+        let mut index_of = |name: &str| match names.iter().position(|n| n == name) {
+            Some(i) => i,
+            None => {
+                names.push(name.to_string());
+                names.len() - 1
+            }
+        };
+        let ranges = structure
+            .biomes
+            .iter()
+            .map(|b| (index_of(&b.biome), b.min.min(b.max), b.min.max(b.max)))
+            .collect();
+        let default_index = index_of(&structure.default_biome);
+
+        Ok(BiomeSelector {
+            density,
+            ranges,
+            names,
+            default_index,
+        })
+    }
+
+    /// World structure density at a column.
+    pub fn value_at(&self, x: f64, z: f64) -> f64 {
+        self.density.evaluate(x, 0.0, z)
+    }
+
+    /// Biome whose range contains a density value (the first, inclusive), if any.
+    pub fn range_biome(&self, value: f64) -> Option<usize> {
+        self.ranges
+            .iter()
+            .find(|(_, min, max)| value >= *min && value <= *max)
+            .map(|(index, _, _)| *index)
+    }
+}
+
+/// Legend row for one biome in a biome map.
+#[derive(Debug, Clone, Serialize)]
+pub struct BiomeLegendEntry {
+    pub index: usize,
+    pub name: String,
+    /// Density ranges that select this biome, as [min, max] pairs
+    pub ranges: Vec<[f64; 2]>,
+    pub is_default: bool,
+    pub samples: u32,
+    /// Share of the map covered by this biome, 0–100
+    pub area_percent: f64,
+}
+
+/// A grid of biome indices over a world region.
+#[derive(Debug, Clone, Serialize)]
+pub struct BiomeMap {
+    /// Row-major biome indices, Z as the row axis
+    pub biomes: Vec<u16>,
+    pub resolution: u32,
+    pub legend: Vec<BiomeLegendEntry>,
+    /// Samples that fell outside every range and used DefaultBiome
+    pub fallback_samples: u32,
+    /// Transition weights, when blending is enabled
+    pub blend: Option<BlendGrid>,
+}
+
+/// Sample biomes on an NxN grid of cell centres over the given region.
+pub fn biome_map(
+    blender: &BiomeBlender,
+    x_range: (f64, f64),
+    z_range: (f64, f64),
+    resolution: u32,
+) -> BiomeMap {
+    let selector = &blender.selector;
+    let n = resolution as usize;
+    let step_x = (x_range.1 - x_range.0) / n as f64;
+    let step_z = (z_range.1 - z_range.0) / n as f64;
+
+    let mut biomes = Vec::with_capacity(n * n);
+    let mut counts = vec![0u32; selector.names.len()];
+    let mut fallback_samples = 0;
+    let mut blend = blender
+        .is_blending()
+        .then(|| BlendGrid::new(blender.transition_distance));
+    for z_idx in 0..n {
+        let z = z_range.0 + (z_idx as f64 + 0.5) * step_z;
+        for x_idx in 0..n {
+            let x = x_range.0 + (x_idx as f64 + 0.5) * step_x;
+            let index = match selector.range_biome(selector.value_at(x, z)) {
+                Some(index) => index,
+                None => {
+                    fallback_samples += 1;
+                    selector.default_index
+                }
+            };
+            counts[index] += 1;
+            biomes.push(index as u16);
+            if let Some(grid) = blend.as_mut() {
+                grid.push(&blender.weights(x, z));
+            }
+        }
+    }
+
+    let total = (n * n).max(1) as f64;
+    let legend = selector
+        .names
+        .iter()
+        .enumerate()
+        .map(|(index, name)| BiomeLegendEntry {
+            index,
+            name: name.clone(),
+            ranges: selector
+                .ranges
+                .iter()
+                .filter(|(i, _, _)| *i == index)
+                .map(|(_, min, max)| [*min, *max])
+                .collect(),
+            is_default: index == selector.default_index,
+            samples: counts[index],
+            area_percent: counts[index] as f64 / total * 100.0,
+        })
+        .collect();
+
+    BiomeMap {
+        biomes,
+        resolution,
+        legend,
+        fallback_samples,
+        blend,
+    }
+}
@@ -0,0 +1,116 @@
+use serde::Serialize;
+
+use crate::noise::hash::position_random;
+
+use super::biome_map::BiomeSelector;
+
+/// Seed for dithering material selection across a transition.
+const DITHER_SEED: i32 = 0x6d2b_79f5;
+
+/// Blends neighbouring NoiseRange biomes within a transition distance.
+///
+/// The biome is sampled on a grid of points inside a disc around each
+/// column; each point votes for its biome with a weight that falls off
+/// linearly with distance. With a zero distance this is plain selection.
+pub struct BiomeBlender {
+    pub selector: BiomeSelector,
+    pub transition_distance: f64,
+    /// (dx, dz, weight) sample offsets
+    kernel: Vec<(f64, f64, f64)>,
+}
+
+impl BiomeBlender {
+    pub fn new(selector: BiomeSelector, transition_distance: f64) -> Self {
+        let radius = transition_distance.max(0.0);
+        let mut kernel = vec![(0.0, 0.0, 1.0)];
+        if radius > 0.0 {
+            let spacing = (radius / 3.0).max(1.0);
+            let steps = (radius / spacing).ceil() as i32;
+            for i in -steps..=steps {
+                for j in -steps..=steps {
+                    let (dx, dz) = (i as f64 * spacing, j as f64 * spacing);
+                    let d = (dx * dx + dz * dz).sqrt();
+                    if (i, j) != (0, 0) && d <= radius {
+                        kernel.push((dx, dz, 1.0 - d / (radius + spacing)));
+                    }
+                }
+            }
+        }
+        BiomeBlender {
+            selector,
+            transition_distance: radius,
+            kernel,
+        }
+    }
+
+    pub fn is_blending(&self) -> bool {
+        self.kernel.len() > 1
+    }
+
+    /// Normalised biome weights at a column, heaviest first.
+    pub fn weights(&self, x: f64, z: f64) -> Vec<(usize, f64)> {
+        let mut totals = vec![0.0; self.selector.names.len()];
+        let mut sum = 0.0;
+        for &(dx, dz, weight) in &self.kernel {
+            let value = self.selector.value_at(x + dx, z + dz);
+            let biome = self
+                .selector
+                .range_biome(value)
+                .unwrap_or(self.selector.default_index);
+            totals[biome] += weight;
+            sum += weight;
+        }
+
+        let mut weights: Vec<(usize, f64)> = totals
+            .into_iter()
+            .enumerate()
+            .filter(|(_, w)| *w > 0.0)
+            .map(|(i, w)| (i, w / sum))
+            .collect();
+        weights.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.0.cmp(&b.0)));
+        weights
+    }
+}
+
+/// Pick one biome from blend weights, dithered per column so transitions
+/// interleave instead of cutting along a line.
+pub fn dithered_biome(weights: &[(usize, f64)], x: i32, z: i32) -> usize {
+    let target = position_random(x, 0, z, DITHER_SEED);
+    let mut cumulative = 0.0;
+    for &(biome, weight) in weights {
+        cumulative += weight;
+        if target < cumulative {
+            return biome;
+        }
+    }
+    weights.last().map(|(b, _)| *b).unwrap_or(0)
+}
+
+/// Per-sample weights of the two heaviest biomes.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct BlendGrid {
+    pub transition_distance: f64,
+    pub dominant: Vec<u16>,
+    pub dominant_weight: Vec<f32>,
+    /// Equal to `dominant` with zero weight where only one biome contributes
+    pub secondary: Vec<u16>,
+    pub secondary_weight: Vec<f32>,
+}
+
+impl BlendGrid {
+    pub fn new(transition_distance: f64) -> Self {
+        BlendGrid {
+            transition_distance,
+            ..Default::default()
+        }
+    }
+
+    pub fn push(&mut self, weights: &[(usize, f64)]) {
+        let (dominant, dominant_weight) = weights.first().copied().unwrap_or((0, 1.0));
+        let (secondary, secondary_weight) = weights.get(1).copied().unwrap_or((dominant, 0.0));
+        self.dominant.push(dominant as u16);
+        self.dominant_weight.push(dominant_weight as f32);
+        self.secondary.push(secondary as u16);
+        self.secondary_weight.push(secondary_weight as f32);
+    }
+}
@@ -0,0 +1,372 @@
+use std::collections::HashMap;
+
+use serde::Serialize;
+
+use crate::bridge::types::ChunkDataResponse;
+use crate::material::column::SolidityColumn;
+use crate::material::evaluator::MaterialEvaluator;
+use crate::noise::evaluator::DensityEvaluator;
+use crate::schema::biome::BiomeAsset;
+use crate::schema::world_structure::WorldStructureAsset;
+
+use super::biome_map::BiomeSelector;
+use super::blend::{dithered_biome, BiomeBlender, BlendGrid};
+use super::pack::WorldgenPack;
+
+/// Blocks along each horizontal edge of a chunk.
+pub const CHUNK_SIZE: i32 = 32;
+
+/// Block name that generates as air.
+pub const EMPTY_BLOCK: &str = "Empty";
+
+/// Block name ↔ id table in the bridge's palette format (id 0 is air).
+pub struct BlockPalette {
+    ids: HashMap<String, i32>,
+    names: Vec<String>,
+}
+
+impl Default for BlockPalette {
+    fn default() -> Self {
+        BlockPalette {
+            ids: HashMap::from([(EMPTY_BLOCK.to_string(), 0)]),
+            names: vec![EMPTY_BLOCK.to_string()],
+        }
+    }
+}
+
+impl BlockPalette {
+    /// Id for a block name, assigning the next free id on first use.
+    pub fn id(&mut self, name: &str) -> i32 {
+        if let Some(&id) = self.ids.get(name) {
+            return id;
+        }
+        let id = self.names.len() as i32;
+        self.ids.insert(name.to_string(), id);
+        self.names.push(name.to_string());
+        id
+    }
+
+    /// Palette keyed by id string, matching `BlockPaletteResponse`.
+    pub fn to_map(&self) -> HashMap<String, String> {
+        self.names
+            .iter()
+            .enumerate()
+            .map(|(id, name)| (id.to_string(), name.clone()))
+            .collect()
+    }
+}
+
+/// An offline chunk plus the palette its block ids refer to.
+#[derive(Serialize)]
+pub struct OfflineChunk {
+    #[serde(flatten)]
+    pub chunk: ChunkDataResponse,
+    pub palette: HashMap<String, String>,
+    /// Per-column transition weights (indexed like the heightmap), when blending
+    pub blend: Option<BlendGrid>,
+}
+
+/// The generated top of one column and whatever fills the space above it.
+#[derive(Debug, Clone, Serialize)]
+pub struct ColumnSurface {
+    /// Biome the column's materials come from
+    pub biome: String,
+    /// Highest block with a material, as in the chunk heightmap
+    pub surface_y: Option<i32>,
+    pub surface_block: Option<String>,
+    /// Block the MaterialProvider places in the empty space directly above
+    /// the surface, such as water
+    pub fluid: Option<String>,
+    /// Consecutive fluid blocks above the surface
+    pub fluid_depth: i32,
+}
+
+/// One biome's compiled Terrain density and MaterialProvider.
+struct BiomeGenerator {
+    name: String,
+    terrain: DensityEvaluator,
+    materials: MaterialEvaluator,
+}
+
+impl BiomeGenerator {
+    fn new(biome: &BiomeAsset, pack: &WorldgenPack) -> Result<Self, String> {
+        let density = biome
+            .terrain
+            .as_ref()
+            .and_then(|t| t.density.as_ref())
+            .ok_or_else(|| format!("Biome '{}' has no Terrain density", biome.name))?;
+        let provider = biome
+            .material_provider
+            .as_ref()
+            .ok_or_else(|| format!("Biome '{}' has no MaterialProvider", biome.name))?;
+
+        let terrain =
+            DensityEvaluator::from_json_with_positions(density, &pack.placement_scope.positions)
+                .map_err(|e| format!("Terrain density of '{}': {}", biome.name, e))?;
+        // A node read as zero would silently flatten the whole biome
+        if !terrain.unsupported_nodes().is_empty() {
+            return Err(format!(
+                "Terrain density of '{}' uses node types the offline generator cannot evaluate: {}",
+                biome.name,
+                terrain.unsupported_nodes().join(", ")
+            ));
+        }
+
+        Ok(BiomeGenerator {
+            name: biome.name.clone(),
+            terrain,
+            materials: MaterialEvaluator::from_json(provider, &pack.material_scope)
+                .map_err(|e| format!("MaterialProvider of '{}': {}", biome.name, e))?,
+        })
+    }
+}
+
+/// Runs biome Terrain densities and MaterialProviders over chunk columns,
+/// either for a single biome or following a NoiseRange world structure.
+pub struct ChunkGenerator {
+    /// Indexed like the blender's biome names
+    biomes: Vec<BiomeGenerator>,
+    blender: Option<BiomeBlender>,
+}
+
+impl ChunkGenerator {
+    /// Generate everything with one biome, resolving its references against
+    /// the pack.
+    pub fn for_biome(biome: &BiomeAsset, pack: &WorldgenPack) -> Result<Self, String> {
+        Ok(ChunkGenerator {
+            biomes: vec![BiomeGenerator::new(biome, pack)?],
+            blender: None,
+        })
+    }
+
+    /// Select biomes per column from a world structure. With `blend`, terrain
+    /// density is mixed across DefaultTransitionDistance and materials are
+    /// dithered between neighbouring biomes.
+    pub fn for_world(
+        pack: &WorldgenPack,
+        structure: &WorldStructureAsset,
+        blend: bool,
+    ) -> Result<Self, String> {
+        let selector = BiomeSelector::new(structure, &pack.placement_scope.positions)?;
+        let biomes = selector
+            .names
+            .iter()
+            .map(|name| BiomeGenerator::new(pack.biome(Some(name))?, pack))
+            .collect::<Result<_, String>>()?;
+        let distance = if blend {
+            structure.default_transition_distance as f64
+        } else {
+            0.0
+        };
+        Ok(ChunkGenerator {
+            biomes,
+            blender: Some(BiomeBlender::new(selector, distance)),
+        })
+    }
+
+    /// The generator the offline tools use: one biome when `biome` is given,
+    /// otherwise the named world structure, or the pack's first one. Packs
+    /// without a world structure fall back to their default biome.
+    pub fn for_pack(
+        pack: &WorldgenPack,
+        biome: Option<&str>,
+        world_structure: Option<&str>,
+        blend: bool,
+    ) -> Result<Self, String> {
+        if biome.is_some() {
+            return Self::for_biome(pack.biome(biome)?, pack);
+        }
+        match (world_structure, pack.world_structure(world_structure)) {
+            (_, Ok(structure)) => Self::for_world(pack, structure, blend),
+            (Some(_), Err(e)) => Err(e),
+            (None, Err(_)) => Self::for_biome(pack.biome(None)?, pack),
+        }
+    }
+
+    /// Biome weights at a column, heaviest first.
+    fn weights(&self, x: i32, z: i32) -> Vec<(usize, f64)> {
+        match &self.blender {
+            Some(blender) => blender.weights(x as f64, z as f64),
+            None => vec![(0, 1.0)],
+        }
+    }
+
+    /// Terrain density of the column at (`x`, `z`) over `y_min..y_max`: the
+    /// weighted sum of the contributing biomes' densities.
+    fn densities(
+        &self,
+        weights: &[(usize, f64)],
+        x: i32,
+        z: i32,
+        y_min: i32,
+        y_max: i32,
+    ) -> Vec<f64> {
+        (y_min..y_max)
+            .map(|y| {
+                weights
+                    .iter()
+                    .map(|&(biome, w)| {
+                        w * self.biomes[biome]
+                            .terrain
+                            .evaluate(x as f64, y as f64, z as f64)
+                    })
+                    .sum()
+            })
+            .collect()
+    }
+
+    /// Solidity of a column: positive density is solid.
+    fn solidity(
+        &self,
+        weights: &[(usize, f64)],
+        x: i32,
+        z: i32,
+        y_min: i32,
+        y_max: i32,
+    ) -> SolidityColumn {
+        let solid = self
+            .densities(weights, x, z, y_min, y_max)
+            .into_iter()
+            .map(|density| density > 0.0)
+            .collect();
+        SolidityColumn::new(y_min, solid)
+    }
+
+    /// Blocks of a column bottom to top with the column's solidity; `None`
+    /// where the provider picks nothing. Empty space is resolved too, so
+    /// fluids the MaterialProvider puts there are kept.
+    fn blocks(
+        &self,
+        weights: &[(usize, f64)],
+        x: i32,
+        z: i32,
+        y_min: i32,
+        y_max: i32,
+    ) -> (SolidityColumn, Vec<Option<&str>>) {
+        let column = self.solidity(weights, x, z, y_min, y_max);
+        let biome = &self.biomes[dithered_biome(weights, x, z)];
+        let blocks = (y_min..y_max)
+            .map(|y| {
+                biome
+                    .materials
+                    .pick_in_column(&column, x, y, z, Some(&biome.name))
+                    .filter(|name| *name != EMPTY_BLOCK)
+            })
+            .collect();
+        (column, blocks)
+    }
+
+    /// Terrain density of the column at (`x`, `z`), bottom to top.
+    pub fn density_column(&self, x: i32, z: i32, y_min: i32, y_max: i32) -> Vec<f64> {
+        self.densities(&self.weights(x, z), x, z, y_min, y_max)
+    }
+
+    /// Generated blocks of the column at (`x`, `z`), bottom to top, as they
+    /// would appear in a chunk spanning the same heights.
+    pub fn column_blocks(&self, x: i32, z: i32, y_min: i32, y_max: i32) -> Vec<Option<&str>> {
+        self.blocks(&self.weights(x, z), x, z, y_min, y_max).1
+    }
+
+    /// Probe a single column over `y_min..y_max` without generating a chunk.
+    pub fn surface(&self, x: i32, z: i32, y_min: i32, y_max: i32) -> ColumnSurface {
+        let weights = self.weights(x, z);
+        let column = self.solidity(&weights, x, z, y_min, y_max);
+        let biome = &self.biomes[dithered_biome(&weights, x, z)];
+        let pick = |y: i32| {
+            biome
+                .materials
+                .pick_in_column(&column, x, y, z, Some(&biome.name))
+                .filter(|name| *name != EMPTY_BLOCK)
+        };
+
+        let surface = (y_min..y_max)
+            .rev()
+            .filter(|y| column.solid[(y - y_min) as usize])
+            .find_map(|y| pick(y).map(|name| (y, name.to_string())));
+        let mut fluid = None;
+        let mut fluid_depth = 0;
+        let start = surface.as_ref().map_or(y_min, |(y, _)| y + 1);
+        for y in start..y_max {
+            if column.solid[(y - y_min) as usize] {
+                break;
+            }
+            match pick(y) {
+                Some(name) if fluid.is_none() || fluid.as_deref() == Some(name) => {
+                    fluid = Some(name.to_string());
+                    fluid_depth += 1;
+                }
+                _ => break,
+            }
+        }
+
+        ColumnSurface {
+            biome: biome.name.clone(),
+            surface_y: surface.as_ref().map(|(y, _)| *y),
+            surface_block: surface.map(|(_, name)| name),
+            fluid,
+            fluid_depth,
+        }
+    }
+
+    /// Generate a chunk in the bridge's layout: blocks indexed
+    /// `(z * 32 + x) * height + (y - y_min)`, heightmap indexed `z * 32 + x`.
+    /// The heightmap holds the highest solid block, ignoring fluids above it.
+    pub fn generate(
+        &self,
+        chunk_x: i32,
+        chunk_z: i32,
+        y_min: i32,
+        y_max: i32,
+        palette: &mut BlockPalette,
+    ) -> OfflineChunk {
+        let height = (y_max - y_min).max(0) as usize;
+        let size = CHUNK_SIZE as usize;
+        let mut blocks = vec![0i32; size * size * height];
+        let mut heightmap = vec![y_min as i16; size * size];
+        let mut blend = self
+            .blender
+            .as_ref()
+            .filter(|b| b.is_blending())
+            .map(|b| BlendGrid::new(b.transition_distance));
+
+        for lz in 0..CHUNK_SIZE {
+            for lx in 0..CHUNK_SIZE {
+                let (x, z) = (chunk_x * CHUNK_SIZE + lx, chunk_z * CHUNK_SIZE + lz);
+                let weights = self.weights(x, z);
+                if let Some(grid) = blend.as_mut() {
+                    grid.push(&weights);
+                }
+                let base = (lz as usize * size + lx as usize) * height;
+                let mut top = None;
+
+                let (column, names) = self.blocks(&weights, x, z, y_min, y_max);
+                for (i, block) in names.into_iter().enumerate() {
+                    if let Some(name) = block {
+                        blocks[base + i] = palette.id(name);
+                        if column.solid[i] {
+                            top = Some(y_min + i as i32);
+                        }
+                    }
+                }
+                if let Some(y) = top {
+                    heightmap[lz as usize * size + lx as usize] = y as i16;
+                }
+            }
+        }
+
+        OfflineChunk {
+            chunk: ChunkDataResponse {
+                chunk_x,
+                chunk_z,
+                y_min,
+                y_max,
+                size_x: CHUNK_SIZE,
+                size_z: CHUNK_SIZE,
+                blocks,
+                heightmap,
+            },
+            palette: palette.to_map(),
+            blend,
+        }
+    }
+}
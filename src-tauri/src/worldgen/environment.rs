@@ -0,0 +1,226 @@
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::material::evaluator::parse_density_with_positions;
+use crate::noise::evaluator::DensityEvaluator;
+use crate::placement::positions::PositionScope;
+use crate::schema::biome::BiomeAsset;
+use crate::schema::environment::{EnvironmentProviderType, TintProviderType};
+
+/// A compiled EnvironmentProvider or TintProvider: a constant, or a density
+/// whose value picks a nested provider from half-open ranges.
+pub enum DelimitedProvider<T> {
+    /// `None` for skipped, empty and unknown providers
+    Constant(Option<T>),
+    DensityDelimited {
+        density: DensityEvaluator,
+        /// [min, max) with a nested provider each; the first match wins
+        delimiters: Vec<(f64, f64, DelimitedProvider<T>)>,
+    },
+}
+
+impl<T> DelimitedProvider<T> {
+    /// The value at a position, or `None` when no delimiter covers it.
+    pub fn sample(&self, x: f64, y: f64, z: f64) -> Option<&T> {
+        match self {
+            DelimitedProvider::Constant(value) => value.as_ref(),
+            DelimitedProvider::DensityDelimited {
+                density,
+                delimiters,
+            } => {
+                let value = density.evaluate(x, y, z);
+                delimiters
+                    .iter()
+                    .find(|(min, max, _)| value >= *min && value < *max)
+                    .and_then(|(_, _, provider)| provider.sample(x, y, z))
+            }
+        }
+    }
+}
+
+pub type EnvironmentProvider = DelimitedProvider<String>;
+pub type TintProvider = DelimitedProvider<[u8; 3]>;
+
+/// Parse a `#RRGGBB` color.
+pub fn parse_color(color: &str) -> Option<[u8; 3]> {
+    let hex = color.strip_prefix('#').unwrap_or(color);
+    if hex.len() != 6 || !hex.is_ascii() {
+        return None;
+    }
+    let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+    Some([channel(0)?, channel(2)?, channel(4)?])
+}
+
+/// Parser for the nested provider of a delimiter.
+type ParseProvider<T> =
+    fn(Option<&Value>, &str, &PositionScope) -> Result<DelimitedProvider<T>, String>;
+
+/// Parse `[{ Range: { MinInclusive, MaxExclusive }, <child>: provider }]`;
+/// missing bounds are unbounded.
+fn parse_delimiters<T>(
+    items: &[Value],
+    child: &str,
+    path: &str,
+    positions: &PositionScope,
+    parse: ParseProvider<T>,
+) -> Result<Vec<(f64, f64, DelimitedProvider<T>)>, String> {
+    let mut delimiters = Vec::new();
+    for (i, item) in items.iter().enumerate() {
+        let bound = |key: &str| item.get("Range").and_then(|r| r.get(key)?.as_f64());
+        let item_path = format!("{}[{}].{}", path, i, child);
+        delimiters.push((
+            bound("MinInclusive").unwrap_or(f64::NEG_INFINITY),
+            bound("MaxExclusive").unwrap_or(f64::INFINITY),
+            parse(item.get(child), &item_path, positions)?,
+        ));
+    }
+    Ok(delimiters)
+}
+
+/// Parse an EnvironmentProvider, resolving position providers in its
+/// densities against `positions`. Missing, skipped and unknown providers
+/// assign no environment.
+pub fn parse_environment(
+    json: Option<&Value>,
+    path: &str,
+    positions: &PositionScope,
+) -> Result<EnvironmentProvider, String> {
+    let Some(json) = json.filter(|v| v.get("Type").is_some()) else {
+        return Ok(DelimitedProvider::Constant(None));
+    };
+    if json.get("Skip").and_then(|v| v.as_bool()) == Some(true) {
+        return Ok(DelimitedProvider::Constant(None));
+    }
+    let Ok(provider) = serde_json::from_value::<EnvironmentProviderType>(json.clone()) else {
+        return Ok(DelimitedProvider::Constant(None));
+    };
+    Ok(match provider {
+        EnvironmentProviderType::Constant { environment } => {
+            DelimitedProvider::Constant(Some(environment).filter(|e| !e.is_empty()))
+        }
+        EnvironmentProviderType::DensityDelimited {
+            density,
+            delimiters,
+        } => DelimitedProvider::DensityDelimited {
+            density: parse_density_with_positions(density.as_ref(), positions)
+                .map_err(|e| format!("{}.Density: {}", path, e))?,
+            delimiters: parse_delimiters(
+                &delimiters,
+                "Environment",
+                &format!("{}.Delimiters", path),
+                positions,
+                parse_environment,
+            )?,
+        },
+    })
+}
+
+/// Parse a TintProvider, resolving position providers in its densities
+/// against `positions`. Missing, skipped and unknown providers leave
+/// positions untinted; a malformed color is an error.
+pub fn parse_tint(
+    json: Option<&Value>,
+    path: &str,
+    positions: &PositionScope,
+) -> Result<TintProvider, String> {
+    let Some(json) = json.filter(|v| v.get("Type").is_some()) else {
+        return Ok(DelimitedProvider::Constant(None));
+    };
+    if json.get("Skip").and_then(|v| v.as_bool()) == Some(true) {
+        return Ok(DelimitedProvider::Constant(None));
+    }
+    let Ok(provider) = serde_json::from_value::<TintProviderType>(json.clone()) else {
+        return Ok(DelimitedProvider::Constant(None));
+    };
+    Ok(match provider {
+        TintProviderType::Constant { color } => DelimitedProvider::Constant(Some(
+            parse_color(&color)
+                .ok_or_else(|| format!("Invalid tint color '{}' at {}.Color", color, path))?,
+        )),
+        TintProviderType::DensityDelimited {
+            density,
+            delimiters,
+        } => DelimitedProvider::DensityDelimited {
+            density: parse_density_with_positions(density.as_ref(), positions)
+                .map_err(|e| format!("{}.Density: {}", path, e))?,
+            delimiters: parse_delimiters(
+                &delimiters,
+                "Tint",
+                &format!("{}.Delimiters", path),
+                positions,
+                parse_tint,
+            )?,
+        },
+    })
+}
+
+/// Environment ids and tint colors over a world region.
+#[derive(Debug, Clone, Serialize)]
+pub struct EnvironmentMap {
+    pub resolution: u32,
+    /// Row-major indices into `environment_names`, Z as the row axis; null
+    /// where no environment applies
+    pub environments: Vec<Option<u16>>,
+    /// Environment ids in order of first appearance
+    pub environment_names: Vec<String>,
+    /// Row-major RGB triples; black where no tint applies
+    pub tints: Vec<u8>,
+    pub untinted_samples: u32,
+}
+
+/// Sample a biome's EnvironmentProvider and TintProvider on an NxN grid of
+/// cell centres at height `y`.
+pub fn environment_map(
+    biome: &BiomeAsset,
+    positions: &PositionScope,
+    x_range: (f64, f64),
+    z_range: (f64, f64),
+    y: f64,
+    resolution: u32,
+) -> Result<EnvironmentMap, String> {
+    let environment = parse_environment(
+        biome.environment_provider.as_ref(),
+        "$.EnvironmentProvider",
+        positions,
+    )?;
+    let tint = parse_tint(biome.tint_provider.as_ref(), "$.TintProvider", positions)?;
+
+    let n = resolution as usize;
+    let step_x = (x_range.1 - x_range.0) / n as f64;
+    let step_z = (z_range.1 - z_range.0) / n as f64;
+
+    let mut environments = Vec::with_capacity(n * n);
+    let mut environment_names: Vec<String> = Vec::new();
+    let mut tints = Vec::with_capacity(n * n * 3);
+    let mut untinted_samples = 0;
+    for z_idx in 0..n {
+        let z = z_range.0 + (z_idx as f64 + 0.5) * step_z;
+        for x_idx in 0..n {
+            let x = x_range.0 + (x_idx as f64 + 0.5) * step_x;
+            environments.push(environment.sample(x, y, z).map(|name| {
+                match environment_names.iter().position(|n| n == name) {
+                    Some(i) => i as u16,
+                    None => {
+                        environment_names.push(name.clone());
+                        (environment_names.len() - 1) as u16
+                    }
+                }
+            }));
+            match tint.sample(x, y, z) {
+                Some(rgb) => tints.extend_from_slice(rgb),
+                None => {
+                    untinted_samples += 1;
+                    tints.extend_from_slice(&[0, 0, 0]);
+                }
+            }
+        }
+    }
+
+    Ok(EnvironmentMap {
+        resolution,
+        environments,
+        environment_names,
+        tints,
+        untinted_samples,
+    })
+}
@@ -0,0 +1,100 @@
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::placement::positions::{PositionProvider, Region};
+use crate::schema::framework::FrameworkType;
+
+use super::pack::WorldgenPack;
+
+/// The items of a world structure's `Framework`, which may be one object or
+/// a list of them.
+pub fn framework_items(framework: Option<&Value>) -> Vec<Value> {
+    match framework {
+        Some(Value::Array(items)) => items.clone(),
+        Some(item @ Value::Object(_)) => vec![item.clone()],
+        _ => Vec::new(),
+    }
+}
+
+/// One named Framework entry and what it evaluates to.
+#[derive(Debug, Clone, Serialize)]
+pub struct FrameworkEntry {
+    /// World structure file stem
+    pub structure: String,
+    /// `DecimalConstants` or `Positions`
+    pub kind: String,
+    pub name: String,
+    /// Value of a DecimalConstants entry
+    pub value: Option<f64>,
+    /// Generated points of a Positions entry, at most the sample limit
+    pub points: Vec<[f64; 3]>,
+    /// Points before the sample limit
+    pub total_points: usize,
+    pub error: Option<String>,
+}
+
+/// Every Framework entry in the pack's world structures, ordered by
+/// structure then declaration. Positions entries are generated over `region`
+/// and resolve against the whole pack, so they may reference each other.
+pub fn framework_entries(
+    pack: &WorldgenPack,
+    region: &Region,
+    limit: usize,
+) -> Vec<FrameworkEntry> {
+    let mut stems: Vec<&String> = pack.world_structures.keys().collect();
+    stems.sort();
+
+    let mut entries = Vec::new();
+    for stem in stems {
+        let structure = &pack.world_structures[stem];
+        for item in framework_items(Some(&structure.framework)) {
+            let entry = |kind: &str, name: String| FrameworkEntry {
+                structure: stem.clone(),
+                kind: kind.to_string(),
+                name,
+                value: None,
+                points: Vec::new(),
+                total_points: 0,
+                error: None,
+            };
+            match serde_json::from_value::<FrameworkType>(item.clone()) {
+                Ok(FrameworkType::DecimalConstants { entries: constants }) => {
+                    entries.extend(constants.into_iter().map(|c| FrameworkEntry {
+                        value: Some(c.value),
+                        ..entry("DecimalConstants", c.name)
+                    }));
+                }
+                Ok(FrameworkType::Positions { entries: positions }) => {
+                    for p in positions {
+                        let mut listed = entry("Positions", p.name.clone());
+                        let provider = p
+                            .positions
+                            .as_ref()
+                            .ok_or_else(|| "Entry has no Positions".to_string())
+                            .and_then(|json| {
+                                PositionProvider::from_json(json, &pack.placement_scope.positions)
+                            });
+                        match provider {
+                            Ok(provider) => {
+                                let mut points = provider.positions(region);
+                                listed.total_points = points.len();
+                                points.truncate(limit);
+                                listed.points = points;
+                            }
+                            Err(e) => listed.error = Some(e),
+                        }
+                        entries.push(listed);
+                    }
+                }
+                Err(e) => {
+                    let kind = item.get("Type").and_then(|v| v.as_str()).unwrap_or("");
+                    entries.push(FrameworkEntry {
+                        error: Some(format!("Invalid framework: {}", e)),
+                        ..entry(kind, String::new())
+                    });
+                }
+            }
+        }
+    }
+    entries
+}
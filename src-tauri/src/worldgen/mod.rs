@@ -0,0 +1,10 @@
+pub mod biome_map;
+pub mod blend;
+pub mod chunk;
+pub mod environment;
+pub mod framework;
+pub mod pack;
+pub mod spawn;
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,158 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use serde_json::Value;
+
+use crate::io::asset_pack::AssetPack;
+use crate::material::evaluator::MaterialScope;
+use crate::placement::props::PlacementScope;
+use crate::schema::biome::BiomeAsset;
+use crate::schema::framework::FrameworkType;
+use crate::schema::world_structure::WorldStructureAsset;
+
+use super::framework::framework_items;
+
+/// The parts of an asset pack the offline generator needs.
+#[derive(Default)]
+pub struct WorldgenPack {
+    /// Biomes by name
+    pub biomes: HashMap<String, BiomeAsset>,
+    /// World structures by file stem
+    pub world_structures: HashMap<String, WorldStructureAsset>,
+    /// Material exports and BaseHeights shared across the pack
+    pub material_scope: MaterialScope,
+    /// Pattern, scanner, position, prop and assignment exports
+    pub placement_scope: PlacementScope,
+}
+
+impl WorldgenPack {
+    /// Load and index an asset pack directory.
+    pub fn load(root: &Path) -> Result<Self, String> {
+        if !root.is_dir() {
+            return Err(format!("Not a directory: {}", root.display()));
+        }
+        let pack = AssetPack::load(root).map_err(|e| e.to_string())?;
+        Ok(Self::from_asset_pack(&pack))
+    }
+
+    pub fn from_asset_pack(pack: &AssetPack) -> Self {
+        let mut index = WorldgenPack::default();
+
+        // Sorted so name collisions resolve the same way every time
+        let mut paths: Vec<&String> = pack.assets.keys().collect();
+        paths.sort();
+
+        for relative in paths {
+            let value = &pack.assets[relative];
+            index.material_scope.collect_exports(value);
+            index.placement_scope.collect_exports(value);
+
+            let path = Path::new(relative);
+            let in_dir = |dir: &str| path.components().any(|c| c.as_os_str() == dir);
+            let stem = path
+                .file_stem()
+                .map(|s| s.to_string_lossy().to_string())
+                .unwrap_or_default();
+
+            if in_dir("Biomes") {
+                if let Ok(mut biome) = serde_json::from_value::<BiomeAsset>(value.clone()) {
+                    if biome.name.is_empty() {
+                        biome.name = stem;
+                    }
+                    index.biomes.insert(biome.name.clone(), biome);
+                }
+            } else if in_dir("WorldStructures") {
+                collect_base_heights(value, &mut index.material_scope.base_heights);
+                collect_framework_positions(value, &mut index.placement_scope.positions.frameworks);
+                if let Ok(structure) = serde_json::from_value::<WorldStructureAsset>(value.clone())
+                {
+                    index.world_structures.insert(stem, structure);
+                }
+            }
+        }
+        index
+            .placement_scope
+            .set_base_heights(&index.material_scope.base_heights);
+        index.material_scope.positions = index.placement_scope.positions.clone();
+        index
+    }
+
+    /// The world structure to generate with: the named one, or the first by
+    /// name when there's no choice to make.
+    pub fn world_structure(&self, name: Option<&str>) -> Result<&WorldStructureAsset, String> {
+        match name {
+            Some(name) => self
+                .world_structures
+                .get(name)
+                .ok_or_else(|| format!("World structure '{}' not found in asset pack", name)),
+            None => self
+                .world_structures
+                .iter()
+                .min_by(|a, b| a.0.cmp(b.0))
+                .map(|(_, s)| s)
+                .ok_or_else(|| "Asset pack has no world structures".to_string()),
+        }
+    }
+
+    /// The named biome, or the pack's default: its world structure's
+    /// DefaultBiome, else the first biome by name.
+    pub fn biome(&self, name: Option<&str>) -> Result<&BiomeAsset, String> {
+        if let Some(name) = name {
+            return self
+                .biomes
+                .get(name)
+                .ok_or_else(|| format!("Biome '{}' not found in asset pack", name));
+        }
+        let default = self
+            .world_structure(None)
+            .ok()
+            .and_then(|s| self.biomes.get(&s.default_biome));
+        default
+            .or_else(|| {
+                self.biomes
+                    .iter()
+                    .min_by(|a, b| a.0.cmp(b.0))
+                    .map(|(_, b)| b)
+            })
+            .ok_or_else(|| "Asset pack has no biomes".to_string())
+    }
+}
+
+/// Named heights from a world structure's `BaseHeight` content fields and
+/// `DecimalConstants` framework entries.
+fn collect_base_heights(structure: &Value, heights: &mut HashMap<String, f64>) {
+    let fields = structure.get("ContentFields").and_then(|v| v.as_array());
+    for field in fields.into_iter().flatten() {
+        if field.get("Type").and_then(|v| v.as_str()) != Some("BaseHeight") {
+            continue;
+        }
+        if let (Some(name), Some(y)) = (
+            field.get("Name").and_then(|v| v.as_str()),
+            field.get("Y").and_then(|v| v.as_f64()),
+        ) {
+            heights.insert(name.to_string(), y);
+        }
+    }
+
+    for framework in framework_items(structure.get("Framework")) {
+        if let Ok(FrameworkType::DecimalConstants { entries }) = serde_json::from_value(framework) {
+            for entry in entries {
+                heights.insert(entry.name, entry.value);
+            }
+        }
+    }
+}
+
+/// Named position providers from a world structure's `Positions` framework
+/// entries.
+fn collect_framework_positions(structure: &Value, frameworks: &mut HashMap<String, Value>) {
+    for framework in framework_items(structure.get("Framework")) {
+        if let Ok(FrameworkType::Positions { entries }) = serde_json::from_value(framework) {
+            for entry in entries {
+                if let Some(positions) = entry.positions {
+                    frameworks.insert(entry.name, positions);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,76 @@
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+
+use crate::placement::positions::{PositionProvider, Region};
+use crate::schema::world_structure::WorldStructureAsset;
+
+use super::chunk::{ChunkGenerator, ColumnSurface};
+use super::pack::WorldgenPack;
+
+/// A SpawnPositions point checked against the offline terrain.
+#[derive(Debug, Clone, Serialize)]
+pub struct SpawnCandidate {
+    /// Position as generated by SpawnPositions
+    pub position: [f64; 3],
+    #[serde(flatten)]
+    pub column: ColumnSurface,
+    /// The column has ground to stand on
+    pub solid: bool,
+    /// Fluid covers the surface, or fills a column without ground
+    pub submerged: bool,
+}
+
+/// Every spawn candidate in a region with totals for a quick verdict.
+#[derive(Debug, Clone, Serialize)]
+pub struct SpawnPreview {
+    pub candidates: Vec<SpawnCandidate>,
+    pub submerged: usize,
+    /// Candidates with no ground in the generated height range
+    pub unsupported: usize,
+    /// Candidates per biome
+    pub biomes: BTreeMap<String, usize>,
+}
+
+/// Evaluate a world structure's SpawnPositions over `region` and probe the
+/// terrain column under each point. The region's Y bounds both filter the
+/// positions and limit the generated column.
+pub fn spawn_preview(
+    pack: &WorldgenPack,
+    structure: &WorldStructureAsset,
+    region: &Region,
+    blend: bool,
+) -> Result<SpawnPreview, String> {
+    let json = structure
+        .spawn_positions
+        .as_ref()
+        .filter(|v| !v.is_null())
+        .ok_or("World structure has no SpawnPositions")?;
+    let provider = PositionProvider::from_json(json, &pack.placement_scope.positions)
+        .map_err(|e| format!("SpawnPositions: {}", e))?;
+    let generator = ChunkGenerator::for_world(pack, structure, blend)?;
+    let (y_min, y_max) = (region.min[1].floor() as i32, region.max[1].ceil() as i32);
+
+    let mut preview = SpawnPreview {
+        candidates: Vec::new(),
+        submerged: 0,
+        unsupported: 0,
+        biomes: BTreeMap::new(),
+    };
+    for position in provider.positions(region) {
+        let (x, z) = (position[0].floor() as i32, position[2].floor() as i32);
+        let column = generator.surface(x, z, y_min, y_max);
+        let solid = column.surface_y.is_some();
+        let submerged = column.fluid.is_some();
+        preview.submerged += submerged as usize;
+        preview.unsupported += !solid as usize;
+        *preview.biomes.entry(column.biome.clone()).or_insert(0) += 1;
+        preview.candidates.push(SpawnCandidate {
+            position,
+            column,
+            solid,
+            submerged,
+        });
+    }
+    Ok(preview)
+}
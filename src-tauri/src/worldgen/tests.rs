@@ -0,0 +1,703 @@
+use std::collections::HashMap;
+
+use serde_json::json;
+
+use super::biome_map::{biome_map, BiomeSelector};
+use super::blend::BiomeBlender;
+use super::chunk::{BlockPalette, ChunkGenerator, CHUNK_SIZE};
+use super::environment::environment_map;
+use super::framework::framework_entries;
+use super::pack::WorldgenPack;
+use super::spawn::spawn_preview;
+use crate::io::asset_pack::AssetPack;
+use crate::material::column::SolidityColumn;
+use crate::material::evaluator::MaterialEvaluator;
+use crate::noise::evaluator::DensityEvaluator;
+use crate::placement::assignments::Assignment;
+use crate::placement::patterns::Pattern;
+use crate::placement::positions::{PositionProvider, PositionScope, Region};
+use crate::schema::biome::BiomeAsset;
+use crate::schema::world_structure::WorldStructureAsset;
+
+fn test_pack() -> AssetPack {
+    let biome = json!({
+        "Name": "Plains",
+        "Terrain": { "Type": "DAOTerrain", "Density": { "Type": "Constant", "Value": 1.0 } },
+        "MaterialProvider": {
+            "Type": "Queue",
+            "Queue": [
+                {
+                    "Type": "SpaceAndDepth",
+                    "Layers": [
+                        { "Type": "ConstantThickness", "Thickness": 1, "Material": { "Type": "Imported", "Name": "Grass" } }
+                    ]
+                },
+                { "Type": "SimpleHorizontal", "TopY": 1, "TopBaseHeight": "Bedrock", "Material": { "Type": "Constant", "Material": "Rock_Bedrock" } },
+                { "Type": "Constant", "Material": { "Solid": "Rock_Stone" } }
+            ]
+        }
+    });
+    let structure = json!({
+        "Type": "NoiseRange",
+        "DefaultBiome": "Plains",
+        "Biomes": [{ "Biome": "Plains", "Min": -1, "Max": 1 }],
+        "ContentFields": [{ "Type": "BaseHeight", "Name": "Bedrock", "Y": 2 }]
+    });
+    let grass = json!({
+        "Type": "Exported",
+        "ExportAs": "Grass",
+        "Material": { "Type": "Constant", "Material": "Soil_Grass" }
+    });
+
+    AssetPack {
+        path: String::new(),
+        assets: HashMap::from([
+            ("HytaleGenerator/Biomes/Plains.json".to_string(), biome),
+            (
+                "HytaleGenerator/WorldStructures/Main.json".to_string(),
+                structure,
+            ),
+            (
+                "HytaleGenerator/MaterialProviders/Grass.json".to_string(),
+                grass,
+            ),
+        ]),
+    }
+}
+
+#[test]
+fn pack_index_resolves_default_biome_and_base_heights() {
+    let pack = WorldgenPack::from_asset_pack(&test_pack());
+    assert_eq!(pack.biome(None).unwrap().name, "Plains");
+    assert!(pack.biome(Some("Desert")).is_err());
+    assert_eq!(pack.material_scope.base_heights.get("Bedrock"), Some(&2.0));
+    assert!(pack.material_scope.exports.contains_key("Grass"));
+}
+
+#[test]
+fn pack_generator_follows_the_requested_world_structure() {
+    let mut assets = test_pack().assets;
+    assets.insert(
+        "HytaleGenerator/Biomes/Ocean.json".to_string(),
+        json!({
+            "Name": "Ocean",
+            "Terrain": { "Type": "DAOTerrain", "Density": { "Type": "Constant", "Value": -1.0 } },
+            "MaterialProvider": { "Type": "Constant", "Material": "Rock_Stone" }
+        }),
+    );
+    assets.insert(
+        "HytaleGenerator/WorldStructures/Sea.json".to_string(),
+        json!({
+            "Type": "NoiseRange",
+            "DefaultBiome": "Ocean",
+            "Density": { "Type": "Constant", "Value": 0.0 },
+            "Biomes": [{ "Biome": "Ocean", "Min": -1, "Max": 1 }]
+        }),
+    );
+    assets.insert(
+        "HytaleGenerator/WorldStructures/Main.json".to_string(),
+        json!({
+            "Type": "NoiseRange",
+            "DefaultBiome": "Plains",
+            "Density": { "Type": "Constant", "Value": 0.0 },
+            "Biomes": [{ "Biome": "Plains", "Min": -1, "Max": 1 }]
+        }),
+    );
+    let pack = WorldgenPack::from_asset_pack(&AssetPack {
+        path: String::new(),
+        assets,
+    });
+    let surface = |biome: Option<&str>, structure: Option<&str>| {
+        let generator = ChunkGenerator::for_pack(&pack, biome, structure, false).unwrap();
+        generator.surface(0, 0, 0, 8).biome
+    };
+    // "Main" sorts first
+    assert_eq!(surface(None, None), "Plains");
+    assert_eq!(surface(None, Some("Sea")), "Ocean");
+    assert_eq!(surface(Some("Plains"), Some("Sea")), "Plains");
+    assert!(ChunkGenerator::for_pack(&pack, None, Some("Missing"), false).is_err());
+}
+
+#[test]
+fn offline_chunk_matches_bridge_layout() {
+    let pack = WorldgenPack::from_asset_pack(&test_pack());
+    let generator = ChunkGenerator::for_biome(pack.biome(None).unwrap(), &pack).unwrap();
+    let mut palette = BlockPalette::default();
+    let offline = generator.generate(1, -2, 0, 8, &mut palette);
+    let chunk = &offline.chunk;
+
+    let size = CHUNK_SIZE as usize;
+    assert_eq!(chunk.blocks.len(), size * size * 8);
+    assert_eq!(chunk.heightmap, vec![7i16; size * size]);
+
+    assert!(offline.blend.is_none());
+    let names = &offline.palette;
+    let name_at = |lx: usize, y: usize, lz: usize| {
+        let id = chunk.blocks[(lz * size + lx) * 8 + y];
+        names[&id.to_string()].as_str()
+    };
+    assert_eq!(name_at(5, 7, 9), "Soil_Grass");
+    assert_eq!(name_at(5, 4, 9), "Rock_Stone");
+    // TopY 1 + Bedrock 2 = 3, exclusive
+    assert_eq!(name_at(5, 2, 9), "Rock_Bedrock");
+    assert_eq!(name_at(5, 3, 9), "Rock_Stone");
+    assert_eq!(names["0"], "Empty");
+}
+
+#[test]
+fn offline_chunk_finds_the_surface_of_height_dependent_terrain() {
+    // Solid below y = 4: 4 - y
+    let hill: BiomeAsset = serde_json::from_value(json!({
+        "Name": "Hill",
+        "Terrain": { "Type": "DAOTerrain", "Density": {
+            "Type": "Sum",
+            "Inputs": [
+                { "Type": "Inverter", "Input": { "Type": "YValue" } },
+                { "Type": "Constant", "Value": 4.0 }
+            ]
+        } },
+        "MaterialProvider": {
+            "Type": "Solidity",
+            "Solid": {
+                "Type": "Queue",
+                "Queue": [
+                    {
+                        "Type": "SpaceAndDepth",
+                        "Layers": [
+                            { "Type": "ConstantThickness", "Thickness": 1, "Material": { "Type": "Constant", "Material": "Soil_Grass" } }
+                        ]
+                    },
+                    { "Type": "Constant", "Material": "Rock_Stone" }
+                ]
+            }
+        }
+    }))
+    .unwrap();
+    let pack = WorldgenPack::from_asset_pack(&test_pack());
+    let generator = ChunkGenerator::for_biome(&hill, &pack).unwrap();
+    let mut palette = BlockPalette::default();
+    let offline = generator.generate(0, 0, 0, 8, &mut palette);
+    let size = CHUNK_SIZE as usize;
+    assert_eq!(offline.chunk.heightmap, vec![3i16; size * size]);
+    assert_eq!(
+        generator.column_blocks(7, -3, 0, 6),
+        vec![
+            Some("Rock_Stone"),
+            Some("Rock_Stone"),
+            Some("Rock_Stone"),
+            Some("Soil_Grass"),
+            None,
+            None
+        ]
+    );
+    let surface = generator.surface(7, -3, -10, 10);
+    assert_eq!(surface.surface_y, Some(3));
+    assert_eq!(surface.surface_block.as_deref(), Some("Soil_Grass"));
+}
+
+#[test]
+fn offline_generator_rejects_unsupported_terrain_nodes() {
+    let biome: BiomeAsset = serde_json::from_value(json!({
+        "Name": "Curved",
+        "Terrain": { "Type": "DAOTerrain", "Density": {
+            "Type": "Sum",
+            "Inputs": [
+                { "Type": "Constant", "Value": 1.0 },
+                { "Type": "CurveMapper", "Input": { "Type": "YValue" } }
+            ]
+        } },
+        "MaterialProvider": { "Type": "Constant", "Material": "Rock_Stone" }
+    }))
+    .unwrap();
+    let pack = WorldgenPack::from_asset_pack(&test_pack());
+    let error = ChunkGenerator::for_biome(&biome, &pack).err().unwrap();
+    assert!(error.contains("'Curved'"), "{}", error);
+    assert!(error.contains("CurveMapper at $.Inputs[1]"), "{}", error);
+}
+
+#[test]
+fn offline_chunk_keeps_fluids_in_empty_space() {
+    let ocean: BiomeAsset = serde_json::from_value(json!({
+        "Name": "Ocean",
+        "Terrain": { "Type": "DAOTerrain", "Density": { "Type": "Constant", "Value": -1.0 } },
+        "MaterialProvider": {
+            "Type": "Solidity",
+            "Solid": { "Type": "Constant", "Material": "Rock_Stone" },
+            "Empty": { "Type": "SimpleHorizontal", "TopY": 2, "Material": { "Type": "Constant", "Material": "Water_Source" } }
+        }
+    }))
+    .unwrap();
+    let pack = WorldgenPack::from_asset_pack(&test_pack());
+    let generator = ChunkGenerator::for_biome(&ocean, &pack).unwrap();
+    let mut palette = BlockPalette::default();
+    let offline = generator.generate(0, 0, -2, 4, &mut palette);
+
+    let column: Vec<&str> = offline.chunk.blocks[..6]
+        .iter()
+        .map(|id| offline.palette[&id.to_string()].as_str())
+        .collect();
+    assert_eq!(
+        column,
+        vec![
+            "Water_Source",
+            "Water_Source",
+            "Water_Source",
+            "Water_Source",
+            "Empty",
+            "Empty"
+        ]
+    );
+    // Water is not terrain, so the heightmap stays at the bottom
+    let size = CHUNK_SIZE as usize;
+    assert_eq!(offline.chunk.heightmap, vec![-2i16; size * size]);
+    assert_eq!(
+        generator.column_blocks(5, 9, 1, 3),
+        vec![Some("Water_Source"), None]
+    );
+}
+
+#[test]
+fn biome_map_falls_back_to_default_and_reports_coverage() {
+    let structure: WorldStructureAsset = serde_json::from_value(json!({
+        "Type": "NoiseRange",
+        "DefaultBiome": "Ocean",
+        "Density": { "Type": "SimplexNoise2D", "Seed": "biomes", "Scale": 8, "Octaves": 2 },
+        "Biomes": [
+            { "Biome": "Desert", "Min": -1.0, "Max": -0.05 },
+            { "Biome": "Plains", "Min": 0.05, "Max": 1.0 }
+        ]
+    }))
+    .unwrap();
+    let selector = BiomeSelector::new(&structure, &PositionScope::default()).unwrap();
+    let blender = BiomeBlender::new(selector, 0.0);
+    let map = biome_map(&blender, (0.0, 512.0), (0.0, 512.0), 64);
+    assert!(map.blend.is_none());
+
+    assert_eq!(map.biomes.len(), 64 * 64);
+    let names: Vec<&str> = map.legend.iter().map(|e| e.name.as_str()).collect();
+    assert_eq!(names, vec!["Desert", "Plains", "Ocean"]);
+
+    let ocean = &map.legend[2];
+    assert!(ocean.is_default);
+    assert!(ocean.ranges.is_empty());
+    assert_eq!(ocean.samples, map.fallback_samples);
+    assert!(map.legend.iter().all(|e| e.samples > 0));
+
+    let total: f64 = map.legend.iter().map(|e| e.area_percent).sum();
+    assert!((total - 100.0).abs() < 1e-9);
+}
+
+#[test]
+fn blended_chunk_mixes_terrain_near_biome_borders() {
+    let mut assets = test_pack().assets;
+    assets.insert(
+        "HytaleGenerator/Biomes/Ocean.json".to_string(),
+        json!({
+            "Name": "Ocean",
+            "Terrain": { "Type": "DAOTerrain", "Density": { "Type": "Constant", "Value": -1.0 } },
+            "MaterialProvider": { "Type": "Constant", "Material": "Rock_Stone" }
+        }),
+    );
+    assets.insert(
+        "HytaleGenerator/WorldStructures/Main.json".to_string(),
+        json!({
+            "Type": "NoiseRange",
+            "DefaultBiome": "Ocean",
+            "DefaultTransitionDistance": 12,
+            "Density": { "Type": "SimplexNoise2D", "Seed": "biomes", "Scale": 8, "Octaves": 2 },
+            "Biomes": [{ "Biome": "Plains", "Min": 0.0, "Max": 1.0 }]
+        }),
+    );
+    let pack = WorldgenPack::from_asset_pack(&AssetPack {
+        path: String::new(),
+        assets,
+    });
+    let structure = pack.world_structure(None).unwrap();
+
+    let selector = BiomeSelector::new(structure, &pack.placement_scope.positions).unwrap();
+    let blender = BiomeBlender::new(selector, 12.0);
+    let map = biome_map(&blender, (0.0, 512.0), (0.0, 512.0), 64);
+    let grid = map.blend.unwrap();
+    assert_eq!(grid.dominant.len(), 64 * 64);
+    assert_eq!(grid.transition_distance, 12.0);
+    assert!(grid.secondary_weight.iter().any(|w| *w > 0.0));
+    for i in 0..grid.dominant.len() {
+        assert!(grid.dominant_weight[i] >= grid.secondary_weight[i]);
+        assert!(grid.dominant_weight[i] + grid.secondary_weight[i] <= 1.0 + 1e-6);
+    }
+
+    let generator = ChunkGenerator::for_world(&pack, structure, true).unwrap();
+    let mut palette = BlockPalette::default();
+    let offline = generator.generate(0, 0, 0, 8, &mut palette);
+    let grid = offline.blend.unwrap();
+    assert_eq!(grid.dominant.len(), offline.chunk.heightmap.len());
+    // Columns entirely inside one biome keep that biome's terrain
+    let plains = map.legend.iter().position(|e| e.name == "Plains").unwrap() as u16;
+    for (i, &top) in offline.chunk.heightmap.iter().enumerate() {
+        if grid.dominant_weight[i] == 1.0 {
+            let expected = if grid.dominant[i] == plains { 7 } else { 0 };
+            assert_eq!(top, expected);
+        }
+    }
+
+    let unblended = ChunkGenerator::for_world(&pack, structure, false).unwrap();
+    assert!(unblended.generate(0, 0, 0, 8, &mut palette).blend.is_none());
+}
+
+#[test]
+fn environment_map_resolves_delimiters_against_density() {
+    let noise = json!({ "Type": "SimplexNoise2D", "Scale": 4.0, "Seed": "coast" });
+    let biome: BiomeAsset = serde_json::from_value(json!({
+        "Name": "Coast",
+        "EnvironmentProvider": {
+            "Type": "DensityDelimited",
+            "Density": noise,
+            "Delimiters": [
+                { "Range": { "MinInclusive": -100, "MaxExclusive": 0 },
+                  "Environment": { "Type": "Constant", "Environment": "Env_Beach" } },
+                { "Range": { "MinInclusive": 0, "MaxExclusive": 0.3 },
+                  "Environment": { "Type": "Constant", "Environment": "Env_Shallows" } }
+            ]
+        },
+        "TintProvider": {
+            "Type": "DensityDelimited",
+            "Density": noise,
+            "Delimiters": [
+                { "Range": { "MaxExclusive": 0 }, "Tint": { "Type": "Constant", "Color": "#FF8000" } },
+                { "Range": { "MinInclusive": 0 }, "Tint": { "Type": "Constant", "Color": "#00ff40" } }
+            ]
+        }
+    }))
+    .unwrap();
+
+    let n = 16;
+    let map = environment_map(
+        &biome,
+        &PositionScope::default(),
+        (0.0, 64.0),
+        (0.0, 64.0),
+        0.0,
+        n,
+    )
+    .unwrap();
+    let density = DensityEvaluator::from_json(&noise).unwrap();
+    let mut names = Vec::new();
+    for (i, environment) in map.environments.iter().enumerate() {
+        let (x, z) = ((i % 16) as f64 * 4.0 + 2.0, (i / 16) as f64 * 4.0 + 2.0);
+        let value = density.evaluate(x, 0.0, z);
+        let expected = if value < 0.0 {
+            Some("Env_Beach")
+        } else if value < 0.3 {
+            Some("Env_Shallows")
+        } else {
+            None
+        };
+        let name = environment.map(|e| map.environment_names[e as usize].as_str());
+        assert_eq!(name, expected, "at ({}, {})", x, z);
+        names.extend(name);
+
+        let rgb = &map.tints[i * 3..i * 3 + 3];
+        let tint: &[u8] = if value < 0.0 {
+            &[0xFF, 0x80, 0x00]
+        } else {
+            &[0x00, 0xFF, 0x40]
+        };
+        assert_eq!(rgb, tint);
+    }
+    assert!(names.contains(&"Env_Beach") && names.contains(&"Env_Shallows"));
+    assert!(map.environments.contains(&None));
+    assert_eq!(map.untinted_samples, 0);
+
+    let bad: BiomeAsset = serde_json::from_value(json!({
+        "TintProvider": { "Type": "Constant", "Color": "green" }
+    }))
+    .unwrap();
+    let err = environment_map(
+        &bad,
+        &PositionScope::default(),
+        (0.0, 1.0),
+        (0.0, 1.0),
+        0.0,
+        1,
+    )
+    .unwrap_err();
+    assert!(err.contains("$.TintProvider.Color"), "{}", err);
+}
+
+#[test]
+fn spawn_preview_flags_candidates_in_ocean_columns() {
+    let mut assets = test_pack().assets;
+    assets.insert(
+        "HytaleGenerator/Biomes/Ocean.json".to_string(),
+        json!({
+            "Name": "Ocean",
+            "Terrain": { "Type": "DAOTerrain", "Density": { "Type": "Constant", "Value": -1.0 } },
+            "MaterialProvider": {
+                "Type": "SimpleHorizontal", "TopY": 4,
+                "Material": { "Type": "Constant", "Material": "Water_Source" }
+            }
+        }),
+    );
+    assets.insert(
+        "HytaleGenerator/WorldStructures/Main.json".to_string(),
+        json!({
+            "Type": "NoiseRange",
+            "DefaultBiome": "Ocean",
+            "Density": { "Type": "SimplexNoise2D", "Seed": "biomes", "Scale": 4 },
+            "Biomes": [{ "Biome": "Plains", "Min": 0.0, "Max": 1.0 }],
+            "SpawnPositions": {
+                "Type": "Mesh2D",
+                "PointGenerator": { "Spacing": 8, "Jitter": 0.5, "Seed": "spawn" },
+                "PointsY": 2
+            }
+        }),
+    );
+    let pack = WorldgenPack::from_asset_pack(&AssetPack {
+        path: String::new(),
+        assets,
+    });
+    let structure = pack.world_structure(None).unwrap();
+    let region = Region {
+        min: [0.0, 0.0, 0.0],
+        max: [128.0, 8.0, 128.0],
+    };
+    let preview = spawn_preview(&pack, structure, &region, false).unwrap();
+
+    assert!(!preview.candidates.is_empty());
+    for candidate in &preview.candidates {
+        let column = &candidate.column;
+        if column.biome == "Plains" {
+            assert!(candidate.solid && !candidate.submerged);
+            assert_eq!(column.surface_y, Some(7));
+            assert_eq!(column.surface_block.as_deref(), Some("Soil_Grass"));
+        } else {
+            assert!(!candidate.solid && candidate.submerged);
+            assert_eq!(column.fluid.as_deref(), Some("Water_Source"));
+            assert!(column.fluid_depth > 0 && column.fluid_depth < 8);
+        }
+    }
+    assert_eq!(preview.biomes.len(), 2);
+    assert_eq!(preview.submerged, preview.biomes["Ocean"]);
+    assert_eq!(preview.unsupported, preview.submerged);
+
+    let mut bare = structure.clone();
+    bare.spawn_positions = None;
+    assert!(spawn_preview(&pack, &bare, &region, false).is_err());
+}
+
+#[test]
+fn framework_positions_resolve_across_providers_and_densities() {
+    let mut assets = test_pack().assets;
+    assets.insert(
+        "HytaleGenerator/WorldStructures/Main.json".to_string(),
+        json!({
+            "Type": "NoiseRange",
+            "DefaultBiome": "Plains",
+            "Density": { "Type": "Constant", "Value": 0.0 },
+            "Framework": [
+                { "Type": "DecimalConstants", "Entries": [{ "Name": "Sea", "Value": 64 }] },
+                {
+                    "Type": "Positions",
+                    "Entries": [
+                        { "Name": "Villages", "Positions": {
+                            "Type": "List",
+                            "Positions": [{ "x": 8, "y": 0, "z": 8 }, { "x": 40, "y": 0, "z": 8 }, { "x": 500, "y": 0, "z": 0 }]
+                        } },
+                        { "Name": "Wells", "Positions": {
+                            "Type": "Offset", "OffsetX": 2,
+                            "Positions": { "Type": "Framework", "Name": "Villages" }
+                        } },
+                        { "Name": "Loop", "Positions": { "Type": "Framework", "Name": "Loop" } }
+                    ]
+                }
+            ]
+        }),
+    );
+    let pack = WorldgenPack::from_asset_pack(&AssetPack {
+        path: String::new(),
+        assets,
+    });
+    let scope = &pack.placement_scope.positions;
+    let region = Region {
+        min: [0.0, -8.0, 0.0],
+        max: [64.0, 8.0, 64.0],
+    };
+
+    let wells =
+        PositionProvider::from_json(&json!({ "Type": "Framework", "Name": "Wells" }), scope)
+            .unwrap();
+    assert_eq!(
+        wells.positions(&region),
+        vec![[10.0, 0.0, 8.0], [42.0, 0.0, 8.0]]
+    );
+    let missing =
+        PositionProvider::from_json(&json!({ "Type": "Framework", "Name": "Towns" }), scope);
+    assert!(missing
+        .err()
+        .unwrap()
+        .contains("Unknown framework positions 'Towns'"));
+
+    let density = json!({
+        "Type": "PositionsCellNoise",
+        "MaxDistance": 32,
+        "Positions": { "Type": "Framework", "Name": "Villages" }
+    });
+    let cells = DensityEvaluator::from_json_with_positions(&density, scope).unwrap();
+    assert!((cells.evaluate(8.0, 0.0, 11.0) - 3.0).abs() < 1e-9);
+
+    let entries = framework_entries(&pack, &region, 1);
+    let summary: Vec<(&str, &str)> = entries
+        .iter()
+        .map(|e| (e.kind.as_str(), e.name.as_str()))
+        .collect();
+    assert_eq!(
+        summary,
+        vec![
+            ("DecimalConstants", "Sea"),
+            ("Positions", "Villages"),
+            ("Positions", "Wells"),
+            ("Positions", "Loop"),
+        ]
+    );
+    assert_eq!(entries[0].value, Some(64.0));
+    assert_eq!(entries[1].total_points, 2);
+    assert_eq!(entries[1].points, vec![[8.0, 0.0, 8.0]]);
+    assert_eq!(entries[2].points, vec![[10.0, 0.0, 8.0]]);
+    assert!(entries[3]
+        .error
+        .as_deref()
+        .unwrap()
+        .contains("Cyclic framework positions 'Loop'"));
+}
+
+#[test]
+fn structure_and_material_densities_see_framework_positions() {
+    let near_villages = json!({
+        "Type": "PositionsCellNoise",
+        "MaxDistance": 32,
+        "Positions": { "Type": "Framework", "Name": "Villages" }
+    });
+    let mut assets = test_pack().assets;
+    assets.insert(
+        "HytaleGenerator/WorldStructures/Main.json".to_string(),
+        json!({
+            "Type": "NoiseRange",
+            "DefaultBiome": "Ocean",
+            "Density": near_villages,
+            "Biomes": [{ "Biome": "Plains", "Min": 0, "Max": 4 }],
+            "Framework": [{
+                "Type": "Positions",
+                "Entries": [{ "Name": "Villages", "Positions": {
+                    "Type": "List",
+                    "Positions": [{ "x": 8, "y": 0, "z": 8 }]
+                } }]
+            }]
+        }),
+    );
+    let pack = WorldgenPack::from_asset_pack(&AssetPack {
+        path: String::new(),
+        assets,
+    });
+
+    let structure = pack.world_structure(None).unwrap();
+    let selector = BiomeSelector::new(structure, &pack.placement_scope.positions).unwrap();
+    let biome_at = |x: f64, z: f64| {
+        let index = selector
+            .range_biome(selector.value_at(x, z))
+            .unwrap_or(selector.default_index);
+        selector.names[index].as_str()
+    };
+    assert_eq!(biome_at(8.0, 11.0), "Plains");
+    assert_eq!(biome_at(30.0, 30.0), "Ocean");
+
+    let provider = json!({
+        "Type": "FieldFunction",
+        "FieldFunction": near_villages,
+        "Delimiters": [
+            { "To": 4, "Material": { "Type": "Constant", "Material": "Rock_Cobble" } },
+            { "From": 4, "Material": { "Type": "Constant", "Material": "Rock_Stone" } }
+        ]
+    });
+    let materials = MaterialEvaluator::from_json(&provider, &pack.material_scope).unwrap();
+    let column = SolidityColumn::new(0, vec![true]);
+    assert_eq!(
+        materials.pick_in_column(&column, 8, 0, 10, None),
+        Some("Rock_Cobble")
+    );
+    assert_eq!(
+        materials.pick_in_column(&column, 30, 0, 30, None),
+        Some("Rock_Stone")
+    );
+}
+
+#[test]
+fn environment_and_placement_densities_see_framework_positions() {
+    let near_villages = json!({
+        "Type": "PositionsCellNoise",
+        "MaxDistance": 32,
+        "Positions": { "Type": "Framework", "Name": "Villages" }
+    });
+    let mut assets = test_pack().assets;
+    assets.insert(
+        "HytaleGenerator/WorldStructures/Main.json".to_string(),
+        json!({
+            "Type": "NoiseRange",
+            "DefaultBiome": "Plains",
+            "Biomes": [{ "Biome": "Plains", "Min": -1, "Max": 1 }],
+            "Framework": [{
+                "Type": "Positions",
+                "Entries": [{ "Name": "Villages", "Positions": {
+                    "Type": "List",
+                    "Positions": [{ "x": 8, "y": 0, "z": 8 }]
+                } }]
+            }]
+        }),
+    );
+    let pack = WorldgenPack::from_asset_pack(&AssetPack {
+        path: String::new(),
+        assets,
+    });
+    let scope = &pack.placement_scope;
+
+    let biome: BiomeAsset = serde_json::from_value(json!({
+        "Name": "Village",
+        "EnvironmentProvider": {
+            "Type": "DensityDelimited",
+            "Density": near_villages,
+            "Delimiters": [{
+                "Range": { "MaxExclusive": 4 },
+                "Environment": { "Type": "Constant", "Environment": "Env_Village" }
+            }]
+        },
+        "TintProvider": {
+            "Type": "DensityDelimited",
+            "Density": near_villages,
+            "Delimiters": [{ "Range": { "MaxExclusive": 4 }, "Tint": { "Type": "Constant", "Color": "#ffffff" } }]
+        }
+    }))
+    .unwrap();
+    let map = environment_map(&biome, &scope.positions, (0.0, 32.0), (4.0, 12.0), 0.0, 2).unwrap();
+    assert_eq!(map.environment_names, vec!["Env_Village"]);
+    assert_eq!(map.environments[..2], [Some(0), None]);
+    assert_eq!(map.tints[..3], [0xff, 0xff, 0xff]);
+
+    let assignment = Assignment::from_json(
+        &json!({
+            "Type": "FieldFunction",
+            "FieldFunction": near_villages,
+            "Delimiters": [{ "Min": 0, "Max": 4, "Assignments": { "Type": "Constant" } }]
+        }),
+        scope,
+    )
+    .unwrap();
+    let trace = assignment.trace([8.0, 0.0, 10.0]);
+    assert!(
+        trace.steps[0].contains("in delimiter 0"),
+        "{:?}",
+        trace.steps
+    );
+
+    let pattern = json!({ "Type": "FieldFunction", "FieldFunction": near_villages });
+    assert!(Pattern::from_json(&pattern, &scope.patterns).is_err());
+    assert!(Pattern::from_json_with_positions(&pattern, &scope.patterns, &scope.positions).is_ok());
+}
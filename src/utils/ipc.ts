@@ -18,6 +18,31 @@ export interface EvaluateRequest {
   range_min: number;
   range_max: number;
   y_level: number;
+  statistics?: StatisticsOptions;
+}
+
+export interface StatisticsOptions {
+  bins?: number;
+  percentiles?: number[];
+  thresholds?: number[];
+  histogram_min?: number;
+  histogram_max?: number;
+}
+
+export interface PreviewStatistics {
+  count: number;
+  non_finite: number;
+  mean: number;
+  std_dev: number;
+  histogram: {
+    bins: number[];
+    bin_edges: number[];
+    below_range: number;
+    above_range: number;
+  };
+  percentiles: { percentile: number; value: number }[];
+  solid_fraction: number;
+  threshold_coverage: { threshold: number; fraction: number }[];
 }
 
 export interface EvaluateResponse {
@@ -25,6 +50,74 @@ export interface EvaluateResponse {
   resolution: number;
   min_value: number;
   max_value: number;
+  statistics: PreviewStatistics | null;
+}
+
+export interface NodeProfile {
+  path: string;
+  node_type: string;
+  calls: number;
+  calls_per_sample: number;
+  total_ms: number;
+  self_ms: number;
+  self_percent: number;
+}
+
+export interface ProfileResponse {
+  samples: number;
+  elapsed_ms: number;
+  nodes: NodeProfile[];
+}
+
+export interface WorldBounds {
+  x_min: number;
+  x_max: number;
+  y_min: number;
+  y_max: number;
+  z_min: number;
+  z_max: number;
+}
+
+export interface RangeAnalysisRequest {
+  graph: unknown;
+  bounds?: WorldBounds;
+}
+
+export interface NodeRange {
+  path: string;
+  node_type: string;
+  min: number | null;
+  max: number | null;
+}
+
+export interface RangeWarning {
+  path: string;
+  node_type: string;
+  message: string;
+}
+
+export interface RangeReport {
+  root: NodeRange;
+  nodes: NodeRange[];
+  warnings: RangeWarning[];
+}
+
+export interface GradientRequest {
+  graph: unknown;
+  resolution: number;
+  range_min: number;
+  range_max: number;
+  y_level: number;
+  step?: number;
+  normals?: boolean;
+}
+
+export interface GradientResponse {
+  values: number[];
+  gradients: number[];
+  normals: number[];
+  resolution: number;
+  max_slope: number;
 }
 
 export interface ValidationResult {
@@ -90,6 +183,18 @@ export async function evaluateDensity(request: EvaluateRequest): Promise<Evaluat
   return invoke<EvaluateResponse>("evaluate_density", { request });
 }
 
+export async function profileDensity(request: EvaluateRequest): Promise<ProfileResponse> {
+  return invoke<ProfileResponse>("profile_density", { request });
+}
+
+export async function analyzeDensityRanges(request: RangeAnalysisRequest): Promise<RangeReport> {
+  return invoke<RangeReport>("analyze_density_ranges", { request });
+}
+
+export async function evaluateGradient(request: GradientRequest): Promise<GradientResponse> {
+  return invoke<GradientResponse>("evaluate_gradient", { request });
+}
+
 export async function validateAssetPack(path: string): Promise<ValidationResult> {
   return invoke<ValidationResult>("validate_asset_pack", { path });
 }
@@ -177,3 +282,597 @@ export async function bridgeFetchPalette(): Promise<BlockPaletteResponse> {
 export async function bridgeFetchChunk(chunkX: number, chunkZ: number, yMin: number, yMax: number, forceLoad: boolean = false): Promise<ChunkDataResponse> {
   return invoke<ChunkDataResponse>("bridge_fetch_chunk", { chunkX, chunkZ, yMin, yMax, forceLoad });
 }
+
+// ── Density tile types ──
+
+export interface TileRequest {
+  graph: unknown;
+  y_level: number;
+  z: number;
+  x: number;
+  y: number;
+}
+
+export interface TileResponse {
+  values: number[];
+  size: number;
+  world_x: number;
+  world_z: number;
+  span: number;
+  min_value: number;
+  max_value: number;
+  source: "Memory" | "Disk" | "Evaluated";
+}
+
+// ── Density tile IPC wrappers ──
+
+export async function getDensityTile(request: TileRequest): Promise<TileResponse> {
+  return invoke<TileResponse>("get_density_tile", { request });
+}
+
+export async function clearTileCache(): Promise<void> {
+  return invoke("clear_tile_cache");
+}
+
+// ── Material types ──
+
+export interface MaterialColumnRequest {
+  provider: unknown;
+  exports?: Record<string, unknown>;
+  base_heights?: Record<string, number>;
+  x: number;
+  z: number;
+  y_min: number;
+  solid: boolean[];
+  biome?: string;
+}
+
+export interface MaterialColumnResponse {
+  y_min: number;
+  blocks: (string | null)[];
+}
+
+export interface LayerStackRequest {
+  provider: unknown;
+  exports?: Record<string, unknown>;
+  base_heights?: Record<string, number>;
+  x: number;
+  z: number;
+  y_min: number;
+  solid: boolean[];
+  biome?: string;
+}
+
+export interface PlacedLayer {
+  index: number;
+  layer_type: string;
+  thickness: number;
+  depth_start: number;
+  depth_end: number;
+  blocks: number;
+  y_start: number | null;
+  material: string | null;
+}
+
+export interface SurfaceStack {
+  surface_y: number;
+  space_above_floor: number;
+  space_below_ceiling: number;
+  condition_met: boolean;
+  layers: PlacedLayer[];
+}
+
+export interface LayerStackResponse {
+  layer_context: "DEPTH_INTO_FLOOR" | "DEPTH_INTO_CEILING";
+  max_expected_depth: number | null;
+  surfaces: SurfaceStack[];
+}
+
+// ── Material IPC wrappers ──
+
+export async function evaluateMaterialColumn(request: MaterialColumnRequest): Promise<MaterialColumnResponse> {
+  return invoke<MaterialColumnResponse>("evaluate_material_column", { request });
+}
+
+export async function computeLayerStack(request: LayerStackRequest): Promise<LayerStackResponse> {
+  return invoke<LayerStackResponse>("compute_layer_stack", { request });
+}
+
+// ── Offline world generation types ──
+
+export interface OfflineChunkRequest {
+  pack_path: string;
+  chunk_x: number;
+  chunk_z: number;
+  y_min: number;
+  y_max: number;
+  biome?: string;
+  world_structure?: string;
+  blend?: boolean;
+}
+
+export interface BlendGrid {
+  transition_distance: number;
+  dominant: number[];
+  dominant_weight: number[];
+  secondary: number[];
+  secondary_weight: number[];
+}
+
+export interface OfflineChunk extends ChunkDataResponse {
+  palette: Record<string, string>;
+  blend: BlendGrid | null;
+}
+
+export interface BiomeMapRequest {
+  pack_path: string;
+  world_structure?: string;
+  x_min: number;
+  x_max: number;
+  z_min: number;
+  z_max: number;
+  resolution: number;
+  blend?: boolean;
+}
+
+export interface BiomeLegendEntry {
+  index: number;
+  name: string;
+  ranges: [number, number][];
+  is_default: boolean;
+  samples: number;
+  area_percent: number;
+}
+
+export interface BiomeMap {
+  biomes: number[];
+  resolution: number;
+  legend: BiomeLegendEntry[];
+  fallback_samples: number;
+  blend: BlendGrid | null;
+}
+
+export interface EnvironmentMapRequest {
+  pack_path: string;
+  biome?: string;
+  x_min: number;
+  x_max: number;
+  z_min: number;
+  z_max: number;
+  y?: number;
+  resolution: number;
+}
+
+export interface EnvironmentMap {
+  resolution: number;
+  environments: (number | null)[];
+  environment_names: string[];
+  tints: number[];
+  untinted_samples: number;
+}
+
+export interface SpawnPreviewRequest {
+  pack_path: string;
+  world_structure?: string;
+  x_min: number;
+  x_max: number;
+  y_min: number;
+  y_max: number;
+  z_min: number;
+  z_max: number;
+  blend?: boolean;
+}
+
+export interface ColumnSurface {
+  biome: string;
+  surface_y: number | null;
+  surface_block: string | null;
+  fluid: string | null;
+  fluid_depth: number;
+}
+
+export interface SpawnCandidate extends ColumnSurface {
+  position: [number, number, number];
+  solid: boolean;
+  submerged: boolean;
+}
+
+export interface SpawnPreview {
+  candidates: SpawnCandidate[];
+  submerged: number;
+  unsupported: number;
+  biomes: Record<string, number>;
+}
+
+export interface FrameworkEntriesRequest {
+  pack_path: string;
+  x_min: number;
+  x_max: number;
+  y_min: number;
+  y_max: number;
+  z_min: number;
+  z_max: number;
+  sample_limit?: number;
+}
+
+export interface FrameworkEntry {
+  structure: string;
+  kind: "DecimalConstants" | "Positions";
+  name: string;
+  value: number | null;
+  points: [number, number, number][];
+  total_points: number;
+  error: string | null;
+}
+
+// ── Offline world generation IPC wrappers ──
+
+export async function generateOfflineChunk(request: OfflineChunkRequest): Promise<OfflineChunk> {
+  return invoke<OfflineChunk>("generate_offline_chunk", { request });
+}
+
+export async function generateBiomeMap(request: BiomeMapRequest): Promise<BiomeMap> {
+  return invoke<BiomeMap>("generate_biome_map", { request });
+}
+
+export async function generateEnvironmentMap(request: EnvironmentMapRequest): Promise<EnvironmentMap> {
+  return invoke<EnvironmentMap>("generate_environment_map", { request });
+}
+
+export async function previewSpawnPositions(request: SpawnPreviewRequest): Promise<SpawnPreview> {
+  return invoke<SpawnPreview>("preview_spawn_positions", { request });
+}
+
+export async function listFrameworkEntries(request: FrameworkEntriesRequest): Promise<FrameworkEntry[]> {
+  return invoke<FrameworkEntry[]>("list_framework_entries", { request });
+}
+
+// ── Placement types ──
+
+export interface PatternMaskRequest {
+  pattern: unknown;
+  exports?: Record<string, unknown>;
+  chunk: ChunkDataResponse;
+  palette: Record<string, string>;
+}
+
+export interface PatternMaskResponse {
+  mask: boolean[];
+  matches: number;
+}
+
+export interface ScanRequest {
+  scanner?: unknown;
+  pattern?: unknown;
+  pattern_exports?: Record<string, unknown>;
+  scanner_exports?: Record<string, unknown>;
+  base_heights?: Record<string, number>;
+  chunk: ChunkDataResponse;
+  palette: Record<string, string>;
+  x: number;
+  y: number;
+  z: number;
+}
+
+export interface ScanResult {
+  scanned: [number, number, number][];
+  accepted: [number, number, number][];
+}
+
+export interface PropSimulationRequest {
+  pack_path: string;
+  biome?: string;
+  chunk_x: number;
+  chunk_z: number;
+  y_min: number;
+  y_max: number;
+  chunk?: ChunkDataResponse;
+  palette?: Record<string, string>;
+  prefab_dir?: string;
+}
+
+export interface BoundingBox {
+  min: [number, number, number];
+  max: [number, number, number];
+}
+
+export interface PlacedProp {
+  prop_type: string;
+  origin: [number, number, number];
+  position: [number, number, number];
+  runtime: number;
+  bbox: BoundingBox;
+  prefab: string | null;
+  rotation: number;
+  direction: Direction | null;
+  blocks: PrefabBlock[];
+  rejected: Record<string, number>;
+  prefab_error: string | null;
+}
+
+export interface PropSimulationResponse {
+  biome: string;
+  placed: PlacedProp[];
+  counts: Record<string, number>;
+  prefab_errors: string[];
+}
+
+export interface AssignmentArea {
+  x_min: number;
+  x_max: number;
+  z_min: number;
+  z_max: number;
+  y: number;
+  resolution: number;
+}
+
+export interface AssignmentRequest {
+  assignment: unknown;
+  pack_path?: string;
+  positions?: [number, number, number][];
+  area?: AssignmentArea;
+}
+
+export interface AssignmentSample {
+  position: [number, number, number];
+  prop: string | null;
+  reason: string[];
+}
+
+export interface AssignmentHistogramEntry {
+  prop: string | null;
+  count: number;
+  percent: number;
+}
+
+export interface AssignmentResponse {
+  samples: AssignmentSample[];
+  histogram: AssignmentHistogramEntry[];
+}
+
+export interface PrefabRequest {
+  asset_dir: string;
+  path: string;
+  variant?: number;
+  rotation?: number;
+  block_mask?: unknown;
+  block_mask_exports?: Record<string, unknown>;
+}
+
+export interface PrefabBlock {
+  x: number;
+  y: number;
+  z: number;
+  name: string;
+  rotation: number;
+}
+
+export interface Prefab {
+  path: string;
+  blocks: PrefabBlock[];
+  bounds: BoundingBox;
+}
+
+export interface PrefabResponse {
+  variants: string[];
+  prefab: Prefab;
+}
+
+export type Direction = "Up" | "Down" | "North" | "South" | "East" | "West";
+
+export interface DirectionalityRequest {
+  directionality: unknown;
+  pack_path?: string;
+  chunk: ChunkDataResponse;
+  palette: Record<string, string>;
+  positions: [number, number, number][];
+}
+
+export interface Orientation {
+  turns: number;
+  direction: Direction | null;
+  matched: Direction[];
+}
+
+export interface DirectionalitySample {
+  position: [number, number, number];
+  placeable: boolean;
+  rotation: number | null;
+  orientation: Orientation | null;
+}
+
+export interface BlockMaskRequest {
+  block_mask: unknown;
+  exports?: Record<string, unknown>;
+  chunk: ChunkDataResponse;
+  palette: Record<string, string>;
+  blocks: PrefabBlock[];
+}
+
+export interface MaskRejection {
+  rule: string;
+  x: number;
+  y: number;
+  z: number;
+  block: string;
+  existing: string;
+}
+
+export interface MaskResult {
+  merged: PrefabBlock[];
+  placed: number;
+  rejected: MaskRejection[];
+  counts: Record<string, number>;
+}
+
+// ── Placement IPC wrappers ──
+
+export async function evaluatePatternMask(request: PatternMaskRequest): Promise<PatternMaskResponse> {
+  return invoke<PatternMaskResponse>("evaluate_pattern_mask", { request });
+}
+
+export async function scanPositions(request: ScanRequest): Promise<ScanResult> {
+  return invoke<ScanResult>("scan_positions", { request });
+}
+
+export async function simulateBiomeProps(request: PropSimulationRequest): Promise<PropSimulationResponse> {
+  return invoke<PropSimulationResponse>("simulate_biome_props", { request });
+}
+
+export async function evaluateAssignments(request: AssignmentRequest): Promise<AssignmentResponse> {
+  return invoke<AssignmentResponse>("evaluate_assignments", { request });
+}
+
+export async function loadPrefab(request: PrefabRequest): Promise<PrefabResponse> {
+  return invoke<PrefabResponse>("load_prefab", { request });
+}
+
+export async function evaluateDirectionality(request: DirectionalityRequest): Promise<DirectionalitySample[]> {
+  return invoke<DirectionalitySample[]>("evaluate_directionality", { request });
+}
+
+export async function applyBlockMask(request: BlockMaskRequest): Promise<MaskResult> {
+  return invoke<MaskResult>("apply_block_mask", { request });
+}
+
+// ── Export types ──
+
+export interface HeightmapExportRequest {
+  pack_path: string;
+  biome?: string;
+  world_structure?: string;
+  blend?: boolean;
+  x_min: number;
+  z_min: number;
+  width: number;
+  depth: number;
+  y_min: number;
+  y_max: number;
+  output: string;
+  formats: ("png" | "raw" | "tiff")[];
+}
+
+export interface MeshExportRequest {
+  pack_path: string;
+  biome?: string;
+  world_structure?: string;
+  blend?: boolean;
+  x_min: number;
+  y_min: number;
+  z_min: number;
+  size_x: number;
+  size_y: number;
+  size_z: number;
+  output: string;
+  format: "obj" | "glb" | "stl";
+  colors?: boolean;
+}
+
+export interface MeshExport {
+  path: string;
+  vertices: number;
+  triangles: number;
+}
+
+export interface BlockExportRequest {
+  pack_path: string;
+  biome?: string;
+  world_structure?: string;
+  blend?: boolean;
+  x_min: number;
+  y_min: number;
+  z_min: number;
+  size_x: number;
+  size_y: number;
+  size_z: number;
+  output: string;
+}
+
+export interface FetchedBlockExportRequest {
+  x_min: number;
+  y_min: number;
+  z_min: number;
+  size_x: number;
+  size_y: number;
+  size_z: number;
+  output: string;
+  force_load?: boolean;
+}
+
+export interface VoxPaletteEntry {
+  index: number;
+  block: string;
+  color: [number, number, number];
+}
+
+export interface VoxExport {
+  path: string;
+  models: number;
+  voxels: number;
+  palette: VoxPaletteEntry[];
+}
+
+export interface SchematicExport {
+  path: string;
+  blocks: number;
+  block_types: number;
+}
+
+export interface SchematicImport {
+  size: [number, number, number];
+  offset: [number, number, number];
+  block_types: string[];
+  positions: unknown;
+  prefab: Prefab;
+}
+
+export interface MapImageRequest {
+  pack_path: string;
+  kind: "biome" | "material";
+  world_structure?: string;
+  biome?: string;
+  blend?: boolean;
+  x_min: number;
+  z_min: number;
+  width: number;
+  depth: number;
+  blocks_per_pixel?: number;
+  y_min?: number;
+  y_max?: number;
+  tile_size?: number;
+  output: string;
+}
+
+// ── Export IPC wrappers ──
+
+export async function exportHeightmap(request: HeightmapExportRequest): Promise<string[]> {
+  return invoke<string[]>("export_heightmap", { request });
+}
+
+export async function exportMesh(request: MeshExportRequest): Promise<MeshExport> {
+  return invoke<MeshExport>("export_mesh", { request });
+}
+
+export async function exportVox(request: BlockExportRequest): Promise<VoxExport> {
+  return invoke<VoxExport>("export_vox", { request });
+}
+
+export async function exportFetchedVox(request: FetchedBlockExportRequest): Promise<VoxExport> {
+  return invoke<VoxExport>("export_fetched_vox", { request });
+}
+
+export async function exportSchematic(request: BlockExportRequest): Promise<SchematicExport> {
+  return invoke<SchematicExport>("export_schematic", { request });
+}
+
+export async function exportFetchedSchematic(request: FetchedBlockExportRequest): Promise<SchematicExport> {
+  return invoke<SchematicExport>("export_fetched_schematic", { request });
+}
+
+export async function importSchematic(path: string): Promise<SchematicImport> {
+  return invoke<SchematicImport>("import_schematic", { path });
+}
+
+export async function exportMapImage(request: MapImageRequest): Promise<string[]> {
+  return invoke<string[]>("export_map_image", { request });
+}